@@ -1,3 +1,9 @@
+pub mod ai_function_traits;
 pub mod aifunc_architect;
 pub mod aifunc_backend;
+pub mod aifunc_data_model;
+pub mod aifunc_docs;
+pub mod aifunc_frontend;
 pub mod aifunc_managing;
+pub mod aifunc_memory;
+pub mod aifunc_security;
@@ -0,0 +1,77 @@
+use crate::helpers::general::ai_function_name;
+use crate::helpers::prompt_registry::PromptInput;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+// An #[ai_function] prompt with typed input and output, called through ai_task_request_typed
+pub trait AiFunction {
+    type Input: Serialize;
+    type Output: DeserializeOwned + JsonSchema;
+
+    // The #[ai_function] whose doc comments are the prompt
+    const PROMPT: fn(&str) -> &'static str;
+
+    fn name() -> &'static str {
+        ai_function_name(Self::PROMPT)
+    }
+
+    // Text handed to the prompt as its input
+    fn context(input: &Self::Input) -> String;
+
+    // Context plus every top-level input field as an {UPPER_SNAKE} template placeholder
+    fn prompt_input(input: &Self::Input) -> PromptInput {
+        let mut prompt_input: PromptInput = PromptInput::new(Self::context(input));
+        if let Ok(Value::Object(fields)) = serde_json::to_value(input) {
+            for (field, value) in fields {
+                let value: String = match value {
+                    Value::String(text) => text,
+                    other => other.to_string(),
+                };
+                prompt_input = prompt_input.var(&field.to_ascii_uppercase(), value);
+            }
+        }
+        prompt_input
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai_functions::aifunc_backend::PrintRestApiEndpoints;
+    use crate::apis::llm_provider::with_provider;
+    use crate::apis::scripted_provider::ScriptedProvider;
+    use crate::helpers::general::ai_task_request_typed;
+    use crate::models::agents::agent_traits::{CodeInput, RouteObject};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn tests_typed_call_builds_context_and_decodes_output() {
+        let input: CodeInput = CodeInput {
+            code_input: "async fn list_items() {}".to_string(),
+        };
+        let prompt_input: PromptInput = PrintRestApiEndpoints::prompt_input(&input);
+        assert_eq!(prompt_input.context, "CODE_INPUT: async fn list_items() {}");
+        assert_eq!(
+            prompt_input.vars.get("CODE_INPUT").map(String::as_str),
+            Some("async fn list_items() {}")
+        );
+        assert_eq!(PrintRestApiEndpoints::name(), "print_rest_api_endpoints");
+
+        let scripted: Arc<ScriptedProvider> = Arc::new(ScriptedProvider::new().reply_to_function(
+            "print_rest_api_endpoints",
+            r#"[{"is_route_dynamic": "false", "method": "get", "request_body": "None", "response": "None", "route": "/items"}]"#,
+        ));
+        let routes: Vec<RouteObject> = with_provider(
+            scripted,
+            ai_task_request_typed::<PrintRestApiEndpoints>(&input, "Backend Developer"),
+        )
+        .await
+        .expect("Typed call failed");
+
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].route, "/items");
+    }
+}
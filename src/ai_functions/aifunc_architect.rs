@@ -1,3 +1,5 @@
+use crate::ai_functions::ai_function_traits::AiFunction;
+use crate::models::agents::agent_traits::{ProjectDescription, ProjectScope};
 use ai_functions::ai_function;
 
 #[ai_function]
@@ -43,3 +45,29 @@ pub fn print_site_urls(_project_description: &str) {
     /// ["https://api.binance.com/api/v3/exchangeInfo", "https://api.binance.com/api/v3/klines?symbol=BTCUSDT&interval=1d"]
     println!(OUTPUT)
 }
+
+// Typed print_project_scope: the user's project description in, the scope flags out
+pub struct PrintProjectScope;
+
+impl AiFunction for PrintProjectScope {
+    type Input = ProjectDescription;
+    type Output = ProjectScope;
+    const PROMPT: fn(&str) -> &'static str = print_project_scope;
+
+    fn context(input: &ProjectDescription) -> String {
+        input.project_description.clone()
+    }
+}
+
+// Typed print_site_urls: the project description in, external API urls out
+pub struct PrintSiteUrls;
+
+impl AiFunction for PrintSiteUrls {
+    type Input = ProjectDescription;
+    type Output = Vec<String>;
+    const PROMPT: fn(&str) -> &'static str = print_site_urls;
+
+    fn context(input: &ProjectDescription) -> String {
+        input.project_description.clone()
+    }
+}
@@ -1,3 +1,5 @@
+use crate::ai_functions::ai_function_traits::AiFunction;
+use crate::models::agents::agent_traits::{CodeInput, RouteObject};
 use ai_functions::ai_function;
 
 #[ai_function]
@@ -148,6 +150,15 @@ pub fn print_improved_springboot_webapi_code(_project_description_and_template:
     println!(OUTPUT)
 }
 
+#[ai_function]
+pub fn print_fixed_springboot_code(_broken_code_with_bugs: &str) {
+    /// INPUT: Takes in Java Spring Boot BROKEN_CODE and the ERROR_BUGS reported by the Maven or Gradle build
+    /// FUNCTION: Removes bugs from code so the project compiles and starts
+    /// IMPORTANT: The code is one file, src/main/java/com/andreanator/webapi/Application.java, in package com.andreanator.webapi. Keep the public class Application with the main method.
+    /// IMPORTANT: Only prints out the new and improved code. No commentary or anything else
+    println!(OUTPUT)
+}
+
 #[ai_function]
 pub fn print_springboot_rest_api_endpoints(_code_input: &str) {
     /// INPUT: Takes in Java Spring Boot REST controller CODE_INPUT
@@ -244,3 +255,29 @@ pub fn print_springboot_rest_api_endpoints(_code_input: &str) {
     /// ]
     println!(OUTPUT)
 }
+
+// Typed print_rest_api_endpoints: webserver code in, its routes out
+pub struct PrintRestApiEndpoints;
+
+impl AiFunction for PrintRestApiEndpoints {
+    type Input = CodeInput;
+    type Output = Vec<RouteObject>;
+    const PROMPT: fn(&str) -> &'static str = print_rest_api_endpoints;
+
+    fn context(input: &CodeInput) -> String {
+        format!("CODE_INPUT: {}", input.code_input)
+    }
+}
+
+// Typed print_springboot_rest_api_endpoints: Spring Boot controller code in, its routes out
+pub struct PrintSpringBootRestApiEndpoints;
+
+impl AiFunction for PrintSpringBootRestApiEndpoints {
+    type Input = CodeInput;
+    type Output = Vec<RouteObject>;
+    const PROMPT: fn(&str) -> &'static str = print_springboot_rest_api_endpoints;
+
+    fn context(input: &CodeInput) -> String {
+        format!("CODE_INPUT: {}", input.code_input)
+    }
+}
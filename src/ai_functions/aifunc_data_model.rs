@@ -0,0 +1,87 @@
+use crate::ai_functions::ai_function_traits::AiFunction;
+use crate::models::agents::agent_traits::{DataModel, DataModelFix, DataModelInput};
+use ai_functions::ai_function;
+
+#[ai_function]
+pub fn print_data_model(_project_description_and_crud: &str) {
+    /// INPUT: Takes in a PROJECT_DESCRIPTION for a website backend and IS_CRUD_REQUIRED
+    /// FUNCTION: Designs the data model the backend stores and returns: its entities, their fields and the relationships between them
+    /// LOGIC:
+    ///   If IS_CRUD_REQUIRED is true, model every entity users create, read, update or delete. Every entity has an "id" field of type "integer".
+    ///   If IS_CRUD_REQUIRED is false, model only the data the API returns. No ids are needed.
+    ///   Entity names are PascalCase singular nouns. Field names are snake_case.
+    ///   "field_type" is one of "string", "integer", "number", "bool", "datetime" or "list<...>" of those.
+    ///   A relationship's "via_field" is a field of the entity holding the target's id, or ids for "many_to_many".
+    ///   "kind" is one of "one_to_one", "one_to_many" or "many_to_many".
+    /// IMPORTANT: Keep the model as small as the project allows. Only prints out the JSON. No commentary or anything else.
+    /// EXAMPLE:
+    ///   PROJECT_DESCRIPTION: build a todo app where users own lists of tasks
+    ///   IS_CRUD_REQUIRED: true
+    /// PRINTS JSON FORMATTED OUTPUT:
+    /// {
+    ///   "entities": [
+    ///     {
+    ///       "name": "User",
+    ///       "fields": [
+    ///         { "name": "id", "field_type": "integer", "is_optional": false },
+    ///         { "name": "username", "field_type": "string", "is_optional": false }
+    ///       ],
+    ///       "relationships": []
+    ///     },
+    ///     {
+    ///       "name": "Task",
+    ///       "fields": [
+    ///         { "name": "id", "field_type": "integer", "is_optional": false },
+    ///         { "name": "title", "field_type": "string", "is_optional": false },
+    ///         { "name": "completed", "field_type": "bool", "is_optional": false },
+    ///         { "name": "due_date", "field_type": "datetime", "is_optional": true },
+    ///         { "name": "user_id", "field_type": "integer", "is_optional": false }
+    ///       ],
+    ///       "relationships": [
+    ///         { "kind": "one_to_many", "target": "User", "via_field": "user_id" }
+    ///       ]
+    ///     }
+    ///   ]
+    /// }
+    println!(OUTPUT)
+}
+
+#[ai_function]
+pub fn print_fixed_data_model(_broken_model_with_bugs: &str) {
+    /// INPUT: Takes in a PROJECT_DESCRIPTION, the BROKEN_MODEL data model JSON and the ERROR_BUGS found in its design
+    /// FUNCTION: Fixes every issue in ERROR_BUGS while keeping the rest of the model unchanged
+    /// IMPORTANT: Only prints out the fixed JSON in the same format as BROKEN_MODEL. No commentary or anything else
+    println!(OUTPUT)
+}
+
+// Typed print_data_model: the project description and CRUD flag in, the data model out
+pub struct PrintDataModel;
+
+impl AiFunction for PrintDataModel {
+    type Input = DataModelInput;
+    type Output = DataModel;
+    const PROMPT: fn(&str) -> &'static str = print_data_model;
+
+    fn context(input: &DataModelInput) -> String {
+        format!(
+            "PROJECT_DESCRIPTION: {} \n IS_CRUD_REQUIRED: {} \n",
+            input.project_description, input.is_crud_required
+        )
+    }
+}
+
+// Typed print_fixed_data_model: a draft and its design issues in, the fixed model out
+pub struct PrintFixedDataModel;
+
+impl AiFunction for PrintFixedDataModel {
+    type Input = DataModelFix;
+    type Output = DataModel;
+    const PROMPT: fn(&str) -> &'static str = print_fixed_data_model;
+
+    fn context(input: &DataModelFix) -> String {
+        format!(
+            "PROJECT_DESCRIPTION: {} \n BROKEN_MODEL: {} \n ERROR_BUGS: {} \n",
+            input.project_description, input.broken_model, input.error_bugs
+        )
+    }
+}
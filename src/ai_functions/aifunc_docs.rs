@@ -0,0 +1,22 @@
+use ai_functions::ai_function;
+
+#[ai_function]
+pub fn print_project_readme(_project_facts: &str) {
+    /// INPUT: Takes in the PROJECT_DESCRIPTION, PROJECT_SCOPE, EXTERNAL_URLS and API_ENDPOINTS of a generated webserver, its BACKEND_LANGUAGE and the RUN_COMMAND that starts it on http://localhost:8080
+    /// FUNCTION: Writes the project's README.md in Markdown with these sections:
+    ///   1. What was built, in a few sentences based on PROJECT_DESCRIPTION and PROJECT_SCOPE
+    ///   2. Running: prerequisites for BACKEND_LANGUAGE and the RUN_COMMAND
+    ///   3. API reference: one entry per route in API_ENDPOINTS, headed "METHOD /route" exactly as listed, with its request body, its response and an example call such as curl -X POST http://localhost:8080/item -H "Content-Type: application/json" -d '{"name": "example"}'
+    ///   4. Third-party APIs: every url in EXTERNAL_URLS and what it is used for, or "None" if the list is empty
+    /// IMPORTANT: Only document routes listed in API_ENDPOINTS. Never invent routes, fields or urls.
+    /// OUTPUT: Print ONLY the Markdown, nothing else.
+    println!(OUTPUT)
+}
+
+#[ai_function]
+pub fn print_fixed_readme(_broken_readme_with_bugs: &str) {
+    /// INPUT: Takes in a generated BROKEN_README, the API_ENDPOINTS and EXTERNAL_URLS it documents and the ERROR_BUGS found when checking it against them
+    /// FUNCTION: Fixes every issue in ERROR_BUGS: removes or corrects routes that are not in API_ENDPOINTS, documents the routes it left out and lists missing third-party APIs
+    /// IMPORTANT: Keep everything else unchanged. Only prints out the fixed Markdown. No commentary or anything else
+    println!(OUTPUT)
+}
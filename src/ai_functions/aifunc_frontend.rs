@@ -0,0 +1,20 @@
+use ai_functions::ai_function;
+
+#[ai_function]
+pub fn print_frontend_code(_project_description_and_endpoints: &str) {
+    /// INPUT: Takes in a PROJECT_DESCRIPTION and the API_ENDPOINTS of its running backend as a JSON list of routes with "route", "method", "request_body" and "response". Wireframe images may be attached.
+    /// FUNCTION: Writes a frontend for the project as ONE index.html file with inline <style> and <script>
+    /// IMPORTANT: Only call the backend with fetch() on relative urls of routes listed in API_ENDPOINTS, e.g. fetch("/task") or fetch(`/task/${id}`, { method: "DELETE" }). Never call any other url.
+    /// IMPORTANT: Plain HTML, CSS and JavaScript only. No frameworks, build steps, CDNs or external scripts.
+    /// IMPORTANT: If wireframes are attached, follow their layout and wording.
+    /// OUTPUT: Print ONLY the HTML file, nothing else. This function ONLY prints code.
+    println!(OUTPUT)
+}
+
+#[ai_function]
+pub fn print_fixed_frontend_code(_broken_code_with_bugs: &str) {
+    /// INPUT: Takes in the frontend BROKEN_CODE (one index.html), the API_ENDPOINTS it may call and the ERROR_BUGS listing fetch() calls to routes that do not exist
+    /// FUNCTION: Rewrites those fetch() calls so every one targets a route in API_ENDPOINTS with a relative url, removing features no route supports
+    /// IMPORTANT: Only prints out the new and improved HTML file. No commentary or anything else
+    println!(OUTPUT)
+}
@@ -0,0 +1,11 @@
+use ai_functions::ai_function;
+
+#[ai_function]
+pub fn summarise_conversation(_conversation_history: &str) {
+    /// Input: Takes in the history of an agent's conversation with an LLM (instructions, code attempts and errors)
+    /// Function: Summarises the history so the agent can continue the task with much less context
+    /// Important: Keep every decision, requirement, file or route name, and every error message still relevant to the task
+    /// Important: Keep the most recent code attempt's structure and what was wrong with it, but do not repeat full code
+    /// Output: Prints the summary as plain text only, no preamble
+    println!(OUTPUT)
+}
@@ -0,0 +1,39 @@
+use crate::ai_functions::ai_function_traits::AiFunction;
+use crate::helpers::security_scan::SecurityFinding;
+use crate::models::agents::agent_traits::CodeInput;
+use ai_functions::ai_function;
+
+#[ai_function]
+pub fn print_security_review(_code_input: &str) {
+    /// INPUT: Takes in CODE_INPUT, the AI written webserver files about to be built and run on the user's machine, each preceded by a "// FILE: <path>" line
+    /// FUNCTION: Reviews the code for anything that could harm the user's machine or data before it runs
+    /// LOGIC: Looks for:
+    ///   Processes or shell commands being started, including from build scripts
+    ///   Files written, changed or deleted outside the project directory
+    ///   Servers listening on addresses other than 127.0.0.1 or localhost
+    ///   unsafe Rust
+    ///   Passwords stored or compared without hashing
+    ///   Hardcoded API keys, tokens, passwords or other secrets
+    ///   Data sent to hosts the project description does not need
+    ///   "severity" is "high" for anything that can damage the machine or leak secrets, "medium" for risky defaults, "low" for minor issues
+    /// IMPORTANT: Only report real problems in the code. An empty list is a valid answer. Only prints out the JSON. No commentary or anything else.
+    /// EXAMPLE OUTPUT:
+    /// [
+    ///   { "severity": "high", "line": 42, "message": "std::fs::remove_dir_all on a path taken from the request body" },
+    ///   { "severity": "medium", "line": 88, "message": "server binds to 0.0.0.0" }
+    /// ]
+    println!(OUTPUT)
+}
+
+// Typed print_security_review: the generated files in, findings out
+pub struct PrintSecurityReview;
+
+impl AiFunction for PrintSecurityReview {
+    type Input = CodeInput;
+    type Output = Vec<SecurityFinding>;
+    const PROMPT: fn(&str) -> &'static str = print_security_review;
+
+    fn context(input: &CodeInput) -> String {
+        format!("CODE_INPUT: {}", input.code_input)
+    }
+}
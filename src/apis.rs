@@ -1 +1,15 @@
+pub mod anthropic_provider;
 pub mod call_request;
+pub mod cassette_provider;
+pub mod llm_config;
+pub mod llm_error;
+pub mod llm_provider;
+pub mod rate_limiter;
+pub mod response_cache;
+pub mod retry;
+#[cfg(test)]
+pub mod scripted_provider;
+pub mod sse;
+#[cfg(test)]
+pub mod stub_server;
+pub mod usage_tracker;
@@ -0,0 +1,667 @@
+use crate::apis::call_request::post_chat_request;
+use crate::apis::llm_error::LlmError;
+use crate::apis::llm_provider::{LlmProvider, OnDelta};
+use crate::apis::sse::SseDecoder;
+use crate::models::general::llm::{
+    ChatOptions, LlmReply, Message, ResponseFormat, ToolCall, ToolCallFunction, ToolDefinition,
+    Usage,
+};
+
+use async_trait::async_trait;
+use reqwest::Response;
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::env;
+use std::error::Error;
+
+const ANTHROPIC_DEFAULT_BASE_URL: &str = "https://api.anthropic.com/v1";
+const ANTHROPIC_DEFAULT_MODEL: &str = "claude-sonnet-4-20250514";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+// The Messages API requires max_tokens on every request
+const ANTHROPIC_DEFAULT_MAX_TOKENS: u32 = 8192;
+const DEFAULT_TEMPERATURE: f32 = 0.1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ImageSource {
+    Base64 { media_type: String, data: String },
+    Url { url: String },
+}
+
+// Content block of a Messages API turn ({"type": "text" | "image" | "tool_use" | "tool_result", ...})
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentBlock {
+    Text {
+        text: String,
+    },
+    Image {
+        source: ImageSource,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: Value,
+    },
+    ToolResult {
+        tool_use_id: String,
+        content: String,
+    },
+    // Thinking and other blocks the crate has no use for
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct AnthropicMessage {
+    role: String,
+    content: Vec<ContentBlock>,
+}
+
+#[derive(Debug, Serialize)]
+struct AnthropicTool {
+    name: String,
+    description: String,
+    input_schema: Value,
+}
+
+#[derive(Debug, Serialize)]
+struct MessagesRequest {
+    model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<AnthropicMessage>,
+    max_tokens: u32,
+    temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<AnthropicTool>>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessagesResponse {
+    content: Vec<ContentBlock>,
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamMessage {
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamDelta {
+    #[serde(default)]
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamError {
+    #[serde(default)]
+    message: String,
+}
+
+// One "data:" event of a streamed Messages API reply
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamEvent {
+    MessageStart {
+        message: StreamMessage,
+    },
+    ContentBlockDelta {
+        delta: StreamDelta,
+    },
+    MessageDelta {
+        usage: Option<AnthropicUsage>,
+    },
+    MessageStop,
+    Error {
+        error: StreamError,
+    },
+    // ping, content_block_start, content_block_stop
+    #[serde(other)]
+    Other,
+}
+
+fn to_usage(input_tokens: u64, output_tokens: u64) -> Usage {
+    Usage {
+        prompt_tokens: input_tokens,
+        completion_tokens: output_tokens,
+        total_tokens: input_tokens + output_tokens,
+    }
+}
+
+fn message_text(message: &Message) -> String {
+    message
+        .content
+        .iter()
+        .map(|content| content.text.as_str())
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+// Data URLs are sent inline as base64, anything else by reference
+fn image_source(url: &str) -> ImageSource {
+    let inline = url
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(";base64,"));
+    match inline {
+        Some((media_type, data)) => ImageSource::Base64 {
+            media_type: media_type.to_string(),
+            data: data.to_string(),
+        },
+        None => ImageSource::Url {
+            url: url.to_string(),
+        },
+    }
+}
+
+// Blocks of one crate message: text and images, tool calls the assistant made, or a tool's result
+fn content_blocks(message: &Message) -> Vec<ContentBlock> {
+    if let Some(tool_use_id) = &message.tool_call_id {
+        return vec![ContentBlock::ToolResult {
+            tool_use_id: tool_use_id.clone(),
+            content: message_text(message),
+        }];
+    }
+
+    let mut blocks: Vec<ContentBlock> = message
+        .content
+        .iter()
+        .filter_map(|content| match &content.image_url {
+            Some(image_url) => Some(ContentBlock::Image {
+                source: image_source(&image_url.url),
+            }),
+            None if content.text.is_empty() => None,
+            None => Some(ContentBlock::Text {
+                text: content.text.clone(),
+            }),
+        })
+        .collect();
+    for call in message.tool_calls.iter().flatten() {
+        blocks.push(ContentBlock::ToolUse {
+            id: call.id.clone(),
+            name: call.function.name.clone(),
+            input: serde_json::from_str(&call.function.arguments)
+                .unwrap_or(Value::Object(Default::default())),
+        });
+    }
+    blocks
+}
+
+// Split OpenAI style messages into the top-level system prompt and alternating user/assistant turns.
+// Leading system messages become the system prompt; a system message after the conversation started
+// (the ai_function prompt following replayed memory) or one with no turns to attach to is sent as a
+// user turn, since the API needs at least one message and must not end on history.
+fn to_anthropic_messages(messages: &[Message]) -> (Option<String>, Vec<AnthropicMessage>) {
+    let leading_system: usize = messages
+        .iter()
+        .take_while(|message| message.role == "system")
+        .count();
+    let (system_messages, turns) = if leading_system == messages.len() {
+        (&messages[..0], messages)
+    } else {
+        messages.split_at(leading_system)
+    };
+
+    let system: Option<String> = Some(
+        system_messages
+            .iter()
+            .map(message_text)
+            .collect::<Vec<String>>()
+            .join("\n\n"),
+    )
+    .filter(|system| !system.is_empty());
+
+    let mut converted: Vec<AnthropicMessage> = vec![];
+    for message in turns {
+        let role: &str = match message.role.as_str() {
+            "assistant" => "assistant",
+            // user, system and tool results all speak to the model as the user
+            _ => "user",
+        };
+        let blocks: Vec<ContentBlock> = content_blocks(message);
+        if blocks.is_empty() {
+            continue;
+        }
+
+        // Consecutive turns of one role are merged, e.g. several tool results
+        match converted.last_mut() {
+            Some(last) if last.role == role => last.content.extend(blocks),
+            _ => converted.push(AnthropicMessage {
+                role: role.to_string(),
+                content: blocks,
+            }),
+        }
+    }
+
+    (system, converted)
+}
+
+// The Messages API has no response_format, so the schema is asked for in the system prompt
+fn schema_instruction(response_format: &ResponseFormat) -> String {
+    format!(
+        "Reply with only a JSON value matching this JSON schema, without commentary or code fences: {}",
+        response_format.json_schema.schema
+    )
+}
+
+fn to_anthropic_tools(tools: &[ToolDefinition]) -> Vec<AnthropicTool> {
+    tools
+        .iter()
+        .map(|tool| AnthropicTool {
+            name: tool.function.name.clone(),
+            description: tool.function.description.clone(),
+            input_schema: tool.function.parameters.clone(),
+        })
+        .collect()
+}
+
+// Text and tool calls of a Messages API reply in the crate's reply shape
+fn from_anthropic_response(response: MessagesResponse) -> LlmReply {
+    let mut texts: Vec<String> = vec![];
+    let mut tool_calls: Vec<ToolCall> = vec![];
+    for block in response.content {
+        match block {
+            ContentBlock::Text { text } => texts.push(text),
+            ContentBlock::ToolUse { id, name, input } => tool_calls.push(ToolCall {
+                id,
+                r#type: "function".to_string(),
+                function: ToolCallFunction {
+                    name,
+                    arguments: input.to_string(),
+                },
+            }),
+            ContentBlock::Image { .. }
+            | ContentBlock::ToolResult { .. }
+            | ContentBlock::Unsupported => {}
+        }
+    }
+
+    LlmReply {
+        content: texts.join(""),
+        usage: response
+            .usage
+            .map(|usage| to_usage(usage.input_tokens, usage.output_tokens)),
+        tool_calls,
+    }
+}
+
+// Claude models through the Anthropic Messages API
+#[derive(Debug, Clone)]
+pub struct AnthropicProvider {
+    base_url: String,
+    api_key: String,
+    model: String,
+    temperature: f32,
+    max_tokens: u32,
+}
+
+impl AnthropicProvider {
+    pub fn new(base_url: String, api_key: String, model: String) -> Self {
+        Self {
+            base_url,
+            api_key,
+            model,
+            temperature: DEFAULT_TEMPERATURE,
+            max_tokens: ANTHROPIC_DEFAULT_MAX_TOKENS,
+        }
+    }
+
+    pub fn from_env() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let api_key: String =
+            env::var("ANTHROPIC_API_KEY").map_err(|_| "ANTHROPIC_API_KEY not set in .env file")?;
+        let base_url: String =
+            env::var("ANTHROPIC_BASE_URL").unwrap_or(ANTHROPIC_DEFAULT_BASE_URL.to_string());
+        let model: String =
+            env::var("ANTHROPIC_MODEL").unwrap_or(ANTHROPIC_DEFAULT_MODEL.to_string());
+        let mut provider: Self = Self::new(base_url, api_key, model);
+        if let Some(max_tokens) = env::var("ANTHROPIC_MAX_TOKENS")
+            .ok()
+            .and_then(|value| value.trim().parse::<u32>().ok())
+        {
+            provider.max_tokens = max_tokens;
+        }
+        Ok(provider)
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}/messages", self.base_url.trim_end_matches('/'))
+    }
+
+    fn headers(&self) -> Result<HeaderMap, LlmError> {
+        let mut headers: HeaderMap = HeaderMap::new();
+        headers.insert("x-api-key", HeaderValue::from_str(&self.api_key)?);
+        headers.insert(
+            "anthropic-version",
+            HeaderValue::from_static(ANTHROPIC_VERSION),
+        );
+        Ok(headers)
+    }
+
+    fn messages_request(
+        &self,
+        messages: &[Message],
+        options: &ChatOptions,
+        stream: bool,
+    ) -> MessagesRequest {
+        let (mut system, messages) = to_anthropic_messages(messages);
+        if let Some(response_format) = &options.response_format {
+            let instruction: String = schema_instruction(response_format);
+            system = Some(match system {
+                Some(system) => format!("{}\n\n{}", system, instruction),
+                None => instruction,
+            });
+        }
+
+        MessagesRequest {
+            model: options.model.clone().unwrap_or(self.model.clone()),
+            system,
+            messages,
+            max_tokens: options.max_tokens.unwrap_or(self.max_tokens),
+            temperature: options.temperature.unwrap_or(self.temperature),
+            tools: options.tools.as_deref().map(to_anthropic_tools),
+            stream,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for AnthropicProvider {
+    fn name(&self) -> &str {
+        "anthropic"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn temperature(&self) -> f32 {
+        self.temperature
+    }
+
+    async fn chat(
+        &self,
+        messages: Vec<Message>,
+        options: &ChatOptions,
+    ) -> Result<LlmReply, LlmError> {
+        let request: MessagesRequest = self.messages_request(&messages, options, false);
+        let response: Response =
+            post_chat_request(&self.endpoint(), self.headers()?, &request).await?;
+        let res: MessagesResponse = serde_json::from_str(&response.text().await?)?;
+
+        Ok(from_anthropic_response(res))
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: Vec<Message>,
+        options: &ChatOptions,
+        on_delta: &mut OnDelta<'_>,
+    ) -> Result<LlmReply, LlmError> {
+        let request: MessagesRequest = self.messages_request(&messages, options, true);
+        let mut response: Response =
+            post_chat_request(&self.endpoint(), self.headers()?, &request).await?;
+
+        let mut decoder: SseDecoder = SseDecoder::new();
+        let mut content: String = String::new();
+        let mut input_tokens: Option<u64> = None;
+        let mut output_tokens: Option<u64> = None;
+        let mut done: bool = false;
+
+        while !done {
+            let events: Vec<String> = match response.chunk().await? {
+                Some(chunk) => decoder.push(&chunk),
+                None => {
+                    done = true;
+                    decoder.finish().into_iter().collect()
+                }
+            };
+
+            for event in events {
+                match serde_json::from_str::<StreamEvent>(&event)? {
+                    StreamEvent::MessageStart { message } => {
+                        if let Some(usage) = message.usage {
+                            input_tokens = Some(usage.input_tokens);
+                            output_tokens = Some(usage.output_tokens);
+                        }
+                    }
+                    StreamEvent::ContentBlockDelta { delta } => {
+                        // input_json_delta of tool_use blocks carries no text
+                        if let Some(text) = delta.text {
+                            on_delta(&text);
+                            content.push_str(&text);
+                        }
+                    }
+                    StreamEvent::MessageDelta { usage } => {
+                        if let Some(usage) = usage {
+                            output_tokens = Some(usage.output_tokens);
+                        }
+                    }
+                    StreamEvent::MessageStop => {
+                        done = true;
+                        break;
+                    }
+                    // Errors after the 200 status line, e.g. overloaded_error mid-stream
+                    StreamEvent::Error { error } => {
+                        return Err(LlmError::Server {
+                            status: 529,
+                            message: error.message,
+                        });
+                    }
+                    StreamEvent::Other => {}
+                }
+            }
+        }
+
+        Ok(LlmReply {
+            content,
+            usage: input_tokens
+                .map(|input_tokens| to_usage(input_tokens, output_tokens.unwrap_or(0))),
+            tool_calls: vec![],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai_functions::aifunc_managing::convert_user_input_to_goal;
+    use crate::apis::stub_server::{StubResponse, StubServer};
+    use crate::helpers::general::{extend_ai_funtion, text_message};
+    use crate::models::general::llm::{Content, FunctionDefinition, ImageUrl};
+
+    fn stub_provider(server: &StubServer) -> AnthropicProvider {
+        AnthropicProvider::new(
+            server.base_url(),
+            "test-key".to_string(),
+            ANTHROPIC_DEFAULT_MODEL.to_string(),
+        )
+    }
+
+    #[test]
+    fn tests_converts_system_and_tool_messages() {
+        let tool_call: ToolCall = ToolCall {
+            id: "toolu_1".to_string(),
+            r#type: "function".to_string(),
+            function: ToolCallFunction {
+                name: "read_project_file".to_string(),
+                arguments: r#"{"path": "src/main.rs"}"#.to_string(),
+            },
+        };
+        let messages: Vec<Message> = vec![
+            text_message("system", "You write Rust."),
+            text_message("user", "Fix the build"),
+            Message {
+                role: "assistant".to_string(),
+                tool_calls: Some(vec![tool_call]),
+                ..Message::default()
+            },
+            Message {
+                tool_call_id: Some("toolu_1".to_string()),
+                ..text_message("tool", "fn main() {}")
+            },
+            text_message("system", "FUNCTION print_fixed_code ..."),
+        ];
+
+        let (system, converted) = to_anthropic_messages(&messages);
+        assert_eq!(system.as_deref(), Some("You write Rust."));
+        assert_eq!(converted.len(), 3);
+        assert_eq!(converted[1].role, "assistant");
+        assert_eq!(
+            converted[1].content[0],
+            ContentBlock::ToolUse {
+                id: "toolu_1".to_string(),
+                name: "read_project_file".to_string(),
+                input: serde_json::json!({"path": "src/main.rs"}),
+            }
+        );
+        // The tool result and the trailing ai_function prompt share one user turn
+        assert_eq!(converted[2].role, "user");
+        assert_eq!(converted[2].content.len(), 2);
+    }
+
+    #[test]
+    fn tests_converts_image_parts() {
+        let mut message: Message = text_message("user", "Build this page");
+        message.content.push(Content {
+            r#type: "image_url".to_string(),
+            text: String::new(),
+            image_url: Some(ImageUrl {
+                url: "data:image/png;base64,iVBORw0KGgo=".to_string(),
+            }),
+        });
+
+        let (_, converted) = to_anthropic_messages(&[message]);
+        assert_eq!(
+            converted[0].content[1],
+            ContentBlock::Image {
+                source: ImageSource::Base64 {
+                    media_type: "image/png".to_string(),
+                    data: "iVBORw0KGgo=".to_string(),
+                },
+            }
+        );
+        let json: String = serde_json::to_string(&converted[0].content[1]).expect("Not JSON");
+        assert!(json.contains(r#""type":"image","source":{"type":"base64""#));
+    }
+
+    #[tokio::test]
+    async fn tests_ai_function_prompt_round_trip() {
+        let server: StubServer = StubServer::start(vec![StubResponse::json(
+            200,
+            include_str!("../../fixtures/anthropic/message_text.json"),
+        )])
+        .await;
+        let provider: AnthropicProvider = stub_provider(&server);
+        let func_msg: Message = extend_ai_funtion(convert_user_input_to_goal, "stock price api");
+
+        let reply: LlmReply = provider
+            .chat(vec![func_msg], &ChatOptions::default())
+            .await
+            .expect("Anthropic chat failed");
+        assert!(reply.content.starts_with("Build a webserver"));
+        assert_eq!(reply.usage.map(|usage| usage.total_tokens), Some(116));
+
+        let body: Value =
+            serde_json::from_str(&server.requests()[0]).expect("Request body not JSON");
+        // A lone system prompt is sent as the user turn; the API needs at least one message
+        assert!(body.get("system").is_none());
+        assert_eq!(body["messages"][0]["role"], "user");
+        assert_eq!(body["messages"][0]["content"][0]["type"], "text");
+        assert_eq!(body["max_tokens"], ANTHROPIC_DEFAULT_MAX_TOKENS);
+        assert_eq!(body["model"], ANTHROPIC_DEFAULT_MODEL);
+    }
+
+    #[tokio::test]
+    async fn tests_tool_use_reply_becomes_tool_calls() {
+        let server: StubServer = StubServer::start(vec![StubResponse::json(
+            200,
+            include_str!("../../fixtures/anthropic/message_tool_use.json"),
+        )])
+        .await;
+        let provider: AnthropicProvider = stub_provider(&server);
+        let options: ChatOptions = ChatOptions {
+            tools: Some(vec![ToolDefinition {
+                r#type: "function".to_string(),
+                function: FunctionDefinition {
+                    name: "cargo_check".to_string(),
+                    description: "Run cargo check".to_string(),
+                    parameters: serde_json::json!({"type": "object", "properties": {}}),
+                },
+            }]),
+            max_tokens: Some(1024),
+            ..ChatOptions::default()
+        };
+
+        let reply: LlmReply = provider
+            .chat(
+                vec![
+                    text_message("system", "You fix Rust code."),
+                    text_message("user", "Fix the build"),
+                ],
+                &options,
+            )
+            .await
+            .expect("Anthropic chat failed");
+        assert_eq!(reply.content, "Let me compile the project first.");
+        assert_eq!(reply.tool_calls[0].function.name, "cargo_check");
+        assert_eq!(reply.tool_calls[0].function.arguments, "{}");
+
+        let body: Value =
+            serde_json::from_str(&server.requests()[0]).expect("Request body not JSON");
+        assert_eq!(body["system"], "You fix Rust code.");
+        assert_eq!(body["tools"][0]["input_schema"]["type"], "object");
+        assert_eq!(body["max_tokens"], 1024);
+    }
+
+    #[tokio::test]
+    async fn tests_streams_text_deltas() {
+        let events: Vec<&str> = include_str!("../../fixtures/anthropic/message_stream.jsonl")
+            .lines()
+            .collect();
+        let server: StubServer = StubServer::start(vec![StubResponse::sse(&events)]).await;
+        let provider: AnthropicProvider = stub_provider(&server);
+
+        let mut deltas: Vec<String> = vec![];
+        let reply: LlmReply = provider
+            .chat_stream(
+                vec![text_message("user", "write main")],
+                &ChatOptions::default(),
+                &mut |delta: &str| deltas.push(delta.to_string()),
+            )
+            .await
+            .expect("Anthropic stream failed");
+
+        assert_eq!(deltas, vec!["fn main() ", "{}"]);
+        assert_eq!(reply.content, "fn main() {}");
+        assert_eq!(reply.usage.map(|usage| usage.total_tokens), Some(66));
+        assert!(server.requests()[0].contains("\"stream\":true"));
+    }
+
+    #[tokio::test]
+    async fn tests_overloaded_status_is_retryable() {
+        let server: StubServer = StubServer::start(vec![StubResponse::json(
+            529,
+            r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#,
+        )])
+        .await;
+        let provider: AnthropicProvider = stub_provider(&server);
+
+        let error: LlmError = provider
+            .chat(vec![text_message("user", "hi")], &ChatOptions::default())
+            .await
+            .expect_err("529 should fail");
+        assert!(error.is_retryable());
+    }
+}
@@ -1,67 +1,150 @@
-#[allow(unused_imports)]
-use crate::models::general::llm::{ApiResponse, ChatCompletion, Content, Message};
-use dotenv::dotenv;
-use reqwest::Client;
-use std::env;
+use crate::apis::llm_error::LlmError;
+use crate::apis::llm_provider::OnDelta;
+use crate::apis::sse::SseDecoder;
+use crate::models::general::llm::{ApiResponse, ApiStreamChunk, LlmReply, Usage};
+use reqwest::{Client, Response};
+use serde::Serialize;
+use std::time::Duration;
 
-use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderValue};
+use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderValue};
 
-// Call Large Language Model (LLM) API CHAT-GPT4.1
-
-pub async fn call_gpt(
-    messages: Vec<Message>,
-) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    dotenv().ok();
-
-    //Extract the API key from the environment variable
-    let api_key: String =
-        env::var("OPEN_ROUTER_AI_KEY").expect("OPEN_ROUTER_AI_KEY not set in .env file");
-
-    // Configure endpoint URL
-    let endpoint: &str = "https://openrouter.ai/api/v1/chat/completions";
+// Long enough for a full webserver rewrite, short enough to notice a hung connection
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(180);
 
+// POST a chat body and classify failures (rate limits, server errors, auth...) before decoding
+pub async fn post_chat_request<B: Serialize + ?Sized>(
+    endpoint: &str,
+    mut headers: HeaderMap,
+    body: &B,
+) -> Result<Response, LlmError> {
     //create header
-    let mut headers = HeaderMap::new();
     headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-    headers.insert(
-        AUTHORIZATION,
-        HeaderValue::from_str(&format!("Bearer {}", api_key))?,
-    );
 
     //Create client
-    let client: Client = Client::builder().default_headers(headers).build()?;
+    let client: Client = Client::builder()
+        .default_headers(headers)
+        .timeout(REQUEST_TIMEOUT)
+        .build()?;
 
-    // Create the request body
-    let chat_completion: ChatCompletion = ChatCompletion {
-        model: "openai/gpt-4.1".to_string(),
-        messages,
-        temperature: 0.1, // Adjust temperature as needed but consistent answers
-    };
+    let response: Response = client.post(endpoint).json(body).send().await?;
 
-    let res: ApiResponse = client
-        .post(endpoint)
-        .json(&chat_completion)
-        .send()
-        .await?
-        .json()
-        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let response_headers: HeaderMap = response.headers().clone();
+        let error_body: String = response.text().await.unwrap_or_default();
+        return Err(LlmError::from_status(
+            status,
+            &response_headers,
+            &error_body,
+        ));
+    }
 
-    let content = res
+    Ok(response)
+}
+
+// Call Large Language Model (LLM) API with an OpenAI style chat completion body
+pub async fn send_chat_request<B: Serialize + ?Sized>(
+    endpoint: &str,
+    headers: HeaderMap,
+    body: &B,
+) -> Result<LlmReply, LlmError> {
+    let response: Response = post_chat_request(endpoint, headers, body).await?;
+    let res: ApiResponse = serde_json::from_str(&response.text().await?)?;
+
+    let usage = res.usage;
+    let message = res
         .choices
         .into_iter()
         .next()
-        .ok_or("No choices in response")?
-        .message
-        .content;
+        .ok_or(LlmError::Decode("No choices in response".to_string()))?
+        .message;
 
     // Send response back
-    Ok(content)
+    Ok(LlmReply {
+        content: message.content.unwrap_or_default(),
+        usage,
+        tool_calls: message.tool_calls,
+    })
+}
+
+#[derive(Debug, Serialize)]
+struct StreamOptions {
+    include_usage: bool,
+}
+
+// The same chat body with streaming switched on
+#[derive(Debug, Serialize)]
+struct StreamingBody<'a, B: Serialize + ?Sized> {
+    #[serde(flatten)]
+    body: &'a B,
+    stream: bool,
+    stream_options: StreamOptions,
+}
+
+// Stream a chat completion over SSE, handing each text delta to on_delta as it arrives
+pub async fn send_chat_stream_request<B: Serialize + ?Sized>(
+    endpoint: &str,
+    headers: HeaderMap,
+    body: &B,
+    on_delta: &mut OnDelta<'_>,
+) -> Result<LlmReply, LlmError> {
+    let streaming_body: StreamingBody<B> = StreamingBody {
+        body,
+        stream: true,
+        stream_options: StreamOptions {
+            include_usage: true,
+        },
+    };
+    let mut response: Response = post_chat_request(endpoint, headers, &streaming_body).await?;
+
+    let mut decoder: SseDecoder = SseDecoder::new();
+    let mut content: String = String::new();
+    let mut usage: Option<Usage> = None;
+    let mut done: bool = false;
+
+    while !done {
+        let events: Vec<String> = match response.chunk().await? {
+            Some(chunk) => decoder.push(&chunk),
+            None => {
+                done = true;
+                decoder.finish().into_iter().collect()
+            }
+        };
+
+        for event in events {
+            if event.trim() == "[DONE]" {
+                done = true;
+                break;
+            }
+
+            let chunk: ApiStreamChunk = serde_json::from_str(&event)?;
+            if chunk.usage.is_some() {
+                usage = chunk.usage;
+            }
+            for delta in chunk
+                .choices
+                .into_iter()
+                .filter_map(|choice| choice.delta.content)
+            {
+                on_delta(&delta);
+                content.push_str(&delta);
+            }
+        }
+    }
+
+    Ok(LlmReply {
+        content,
+        usage,
+        tool_calls: vec![],
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-    use crate::models::general::llm::Message;
+    use crate::apis::cassette_provider::CassetteProvider;
+    use crate::apis::llm_provider::{LlmProvider, active_provider, with_provider};
+    use crate::models::general::llm::{ChatOptions, Content, Message};
+    use std::sync::Arc;
 
     #[tokio::test]
     async fn test_call_gpt() {
@@ -70,13 +153,22 @@ mod tests {
             content: vec![Content {
                 r#type: "text".to_string(),
                 text: "Hello, Andreanator this is a test. Give me a short response!".to_string(),
+                image_url: None,
             }],
+            ..Message::default()
         }];
 
-        let res = call_gpt(messages).await;
+        let cassette: Arc<dyn LlmProvider> =
+            Arc::new(CassetteProvider::from_env("call_gpt").expect("Failed to load cassette"));
+        let res = with_provider(cassette, async {
+            let provider: Arc<dyn LlmProvider> =
+                active_provider().expect("Failed to configure LLM provider");
+            provider.chat(messages, &ChatOptions::default()).await
+        })
+        .await;
         if let Ok(response) = res {
-            println!("Response: {}", response);
-            assert!(!response.is_empty(), "Response should not be empty");
+            println!("Response: {}", response.content);
+            assert!(!response.content.is_empty(), "Response should not be empty");
         } else {
             panic!("Failed to call GPT API");
         }
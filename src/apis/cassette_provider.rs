@@ -0,0 +1,241 @@
+use crate::apis::llm_error::LlmError;
+use crate::apis::llm_provider::{LlmProvider, provider_from_env};
+use crate::models::general::llm::{ChatOptions, LlmReply, Message, ToolCall, Usage};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const CASSETTE_DIR: &str = "fixtures/cassettes";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CassetteMode {
+    Replay,
+    Record,
+}
+
+// One recorded request and the response the model gave to it
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Interaction {
+    pub key: String,
+    pub messages: Vec<Message>,
+    pub response: String,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct Cassette {
+    interactions: Vec<Interaction>,
+}
+
+// Provider replaying recorded responses from a JSON fixture (or recording them)
+pub struct CassetteProvider {
+    path: PathBuf,
+    mode: CassetteMode,
+    inner: Option<Arc<dyn LlmProvider>>,
+    cassette: Mutex<Cassette>,
+}
+
+// Stable fingerprint of a request, used as the cassette lookup key
+pub fn request_key(messages: &[Message], options: &ChatOptions) -> String {
+    // Plain calls hash the messages alone so their fixtures stay valid
+    let serialized: String = if *options == ChatOptions::default() {
+        serde_json::to_string(messages)
+    } else {
+        serde_json::to_string(&(messages, options))
+    }
+    .expect("Failed to serialize request for hashing");
+    Sha256::digest(serialized.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+pub fn cassette_path(name: &str) -> PathBuf {
+    Path::new(CASSETTE_DIR).join(format!("{}.json", name))
+}
+
+fn load_cassette(path: &Path) -> Result<Cassette, Box<dyn Error + Send + Sync>> {
+    if !path.exists() {
+        return Ok(Cassette::default());
+    }
+    let contents: String = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+impl CassetteProvider {
+    pub fn replay(path: PathBuf) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let cassette: Cassette = load_cassette(&path)?;
+        Ok(Self {
+            path,
+            mode: CassetteMode::Replay,
+            inner: None,
+            cassette: Mutex::new(cassette),
+        })
+    }
+
+    pub fn record(
+        path: PathBuf,
+        inner: Arc<dyn LlmProvider>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let cassette: Cassette = load_cassette(&path)?;
+        Ok(Self {
+            path,
+            mode: CassetteMode::Record,
+            inner: Some(inner),
+            cassette: Mutex::new(cassette),
+        })
+    }
+
+    // Replay the named fixture, or re-record it against the real provider when LLM_CASSETTE=record
+    pub fn from_env(name: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let path: PathBuf = cassette_path(name);
+        match env::var("LLM_CASSETTE").as_deref() {
+            Ok("record") => Self::record(path, provider_from_env()?),
+            _ => Self::replay(path),
+        }
+    }
+
+    fn save(&self, cassette: &Cassette) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(cassette)? + "\n")?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LlmProvider for CassetteProvider {
+    fn name(&self) -> &str {
+        "cassette"
+    }
+
+    fn model(&self) -> &str {
+        match &self.inner {
+            Some(inner) => inner.model(),
+            None => "replay",
+        }
+    }
+
+    async fn chat(
+        &self,
+        messages: Vec<Message>,
+        options: &ChatOptions,
+    ) -> Result<LlmReply, LlmError> {
+        let key: String = request_key(&messages, options);
+
+        if self.mode == CassetteMode::Replay {
+            let cassette = self.cassette.lock().expect("Cassette lock poisoned");
+            return cassette
+                .interactions
+                .iter()
+                .find(|interaction| interaction.key == key)
+                .map(|interaction| LlmReply {
+                    content: interaction.response.clone(),
+                    usage: interaction.usage,
+                    tool_calls: interaction.tool_calls.clone(),
+                })
+                .ok_or_else(|| {
+                    LlmError::Config(format!(
+                        "No recorded response for request {} in {} (re-record with LLM_CASSETTE=record)",
+                        key,
+                        self.path.display()
+                    ))
+                });
+        }
+
+        let inner: &Arc<dyn LlmProvider> = self.inner.as_ref().ok_or(LlmError::Config(
+            "Recording cassette has no provider".to_string(),
+        ))?;
+        let reply: LlmReply = inner.chat(messages.clone(), options).await?;
+
+        let mut cassette = self.cassette.lock().expect("Cassette lock poisoned");
+        cassette
+            .interactions
+            .retain(|interaction| interaction.key != key);
+        cassette.interactions.push(Interaction {
+            key,
+            messages,
+            response: reply.content.clone(),
+            usage: reply.usage,
+            tool_calls: reply.tool_calls.clone(),
+        });
+        self.save(&cassette)
+            .map_err(|e| LlmError::Config(format!("Failed to save cassette: {}", e)))?;
+
+        Ok(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::scripted_provider::ScriptedProvider;
+    use crate::models::general::llm::Content;
+
+    fn user_message(text: &str) -> Message {
+        Message {
+            role: "user".to_string(),
+            content: vec![Content {
+                r#type: "text".to_string(),
+                text: text.to_string(),
+                image_url: None,
+            }],
+            ..Message::default()
+        }
+    }
+
+    #[test]
+    fn tests_request_key_is_stable() {
+        let key: String = request_key(&[user_message("hello")], &ChatOptions::default());
+        assert_eq!(key.len(), 64);
+        assert_eq!(
+            key,
+            request_key(&[user_message("hello")], &ChatOptions::default())
+        );
+        assert_ne!(
+            key,
+            request_key(&[user_message("hello!")], &ChatOptions::default())
+        );
+    }
+
+    #[tokio::test]
+    async fn tests_cassette_records_then_replays() {
+        let path: PathBuf =
+            env::temp_dir().join(format!("andreanator_cassette_{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let scripted: Arc<dyn LlmProvider> = Arc::new(ScriptedProvider::new().reply_next("hi"));
+        let recorder: CassetteProvider =
+            CassetteProvider::record(path.clone(), scripted).expect("Failed to open cassette");
+        let recorded: LlmReply = recorder
+            .chat(vec![user_message("hello")], &ChatOptions::default())
+            .await
+            .expect("Failed to record");
+
+        let player: CassetteProvider =
+            CassetteProvider::replay(path.clone()).expect("Failed to load cassette");
+        let replayed: LlmReply = player
+            .chat(vec![user_message("hello")], &ChatOptions::default())
+            .await
+            .expect("Failed to replay");
+
+        assert_eq!(recorded, replayed);
+        assert!(
+            player
+                .chat(vec![user_message("other")], &ChatOptions::default())
+                .await
+                .is_err()
+        );
+        fs::remove_file(&path).expect("Failed to clean up cassette");
+    }
+}
@@ -0,0 +1,280 @@
+use crate::models::general::llm::ChatOptions;
+
+use dotenv::dotenv;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const DEFAULT_CONFIG_PATH: &str = "llm_config.toml";
+
+// Model settings for one scope; unset fields fall through to the broader scope
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LlmSettings {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+}
+
+impl LlmSettings {
+    // Fields set in `other` win
+    pub fn merged_with(&self, other: &LlmSettings) -> LlmSettings {
+        LlmSettings {
+            provider: other.provider.clone().or(self.provider.clone()),
+            model: other.model.clone().or(self.model.clone()),
+            temperature: other.temperature.or(self.temperature),
+            max_tokens: other.max_tokens.or(self.max_tokens),
+        }
+    }
+
+    // Overrides from <PREFIX>_PROVIDER, <PREFIX>_MODEL, <PREFIX>_TEMPERATURE and <PREFIX>_MAX_TOKENS
+    fn from_env(prefix: &str) -> LlmSettings {
+        let read = |field: &str| {
+            env::var(format!("{}_{}", prefix, field))
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        LlmSettings {
+            provider: read("PROVIDER"),
+            model: read("MODEL"),
+            temperature: read("TEMPERATURE").and_then(|value| value.parse().ok()),
+            max_tokens: read("MAX_TOKENS").and_then(|value| value.parse().ok()),
+        }
+    }
+
+    // Per-call options carrying these overrides (the provider is chosen separately)
+    pub fn apply(&self, options: &ChatOptions) -> ChatOptions {
+        ChatOptions {
+            model: self.model.clone().or(options.model.clone()),
+            temperature: self.temperature.or(options.temperature),
+            max_tokens: self.max_tokens.or(options.max_tokens),
+            ..options.clone()
+        }
+    }
+
+    fn describe(&self) -> String {
+        let fields: Vec<String> = [
+            self.provider
+                .as_ref()
+                .map(|provider| format!("provider={}", provider)),
+            self.model.as_ref().map(|model| format!("model={}", model)),
+            self.temperature
+                .map(|temperature| format!("temperature={}", temperature)),
+            self.max_tokens
+                .map(|max_tokens| format!("max_tokens={}", max_tokens)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        if fields.is_empty() {
+            "provider defaults".to_string()
+        } else {
+            fields.join(" ")
+        }
+    }
+}
+
+// LLM_AGENT_BACKEND_DEVELOPER style prefix for a position or function name
+fn env_prefix(scope: &str, name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("LLM_{}_{}", scope, name)
+}
+
+// Model settings by agent position and ai_function, loaded from llm_config.toml
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LlmConfig {
+    #[serde(default)]
+    pub defaults: LlmSettings,
+    #[serde(default)]
+    pub agents: BTreeMap<String, LlmSettings>,
+    #[serde(default)]
+    pub functions: BTreeMap<String, LlmSettings>,
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+impl LlmConfig {
+    pub fn from_toml(contents: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    // File named by LLM_CONFIG, else llm_config.toml when present, else no overrides
+    pub fn load() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        // LLM_CONFIG and the LLM_DEFAULT_*/LLM_AGENT_*/LLM_FUNCTION_* overrides may live in .env
+        dotenv().ok();
+
+        let path: PathBuf = match env::var("LLM_CONFIG") {
+            Ok(path) => PathBuf::from(path),
+            Err(_) if Path::new(DEFAULT_CONFIG_PATH).exists() => PathBuf::from(DEFAULT_CONFIG_PATH),
+            Err(_) => return Ok(Self::default()),
+        };
+
+        let contents: String = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read LLM config {}: {}", path.display(), e))?;
+        let mut config: LlmConfig = Self::from_toml(&contents)
+            .map_err(|e| format!("Invalid LLM config {}: {}", path.display(), e))?;
+        config.source = Some(path);
+        Ok(config)
+    }
+
+    // Agent then ai_function overrides, each from the file and then the environment
+    fn scoped_settings(&self, agent_position: &str, function_name: &str) -> LlmSettings {
+        let empty: LlmSettings = LlmSettings::default();
+        self.agents
+            .get(agent_position)
+            .unwrap_or(&empty)
+            .merged_with(&LlmSettings::from_env(&env_prefix("AGENT", agent_position)))
+            .merged_with(self.functions.get(function_name).unwrap_or(&empty))
+            .merged_with(&LlmSettings::from_env(&env_prefix(
+                "FUNCTION",
+                function_name,
+            )))
+    }
+
+    pub fn default_settings(&self) -> LlmSettings {
+        self.defaults
+            .merged_with(&LlmSettings::from_env("LLM_DEFAULT"))
+    }
+
+    // Most specific wins: defaults < agent < ai_function, with env overriding the file at each level
+    pub fn settings_for(&self, agent_position: &str, function_name: &str) -> LlmSettings {
+        self.default_settings()
+            .merged_with(&self.scoped_settings(agent_position, function_name))
+    }
+
+    // Provider an agent or ai_function asks for instead of the process default
+    // (the default scope's provider is installed once at startup)
+    pub fn provider_override(&self, agent_position: &str, function_name: &str) -> Option<String> {
+        self.scoped_settings(agent_position, function_name).provider
+    }
+
+    // Effective settings for the default scope and every configured agent and ai_function
+    pub fn print_summary(&self, agent_positions: &[String]) {
+        match &self.source {
+            Some(path) => println!("LLM settings from {}:", path.display()),
+            None => println!("LLM settings (no config file):"),
+        }
+        println!("  default: {}", self.settings_for("", "").describe());
+
+        let mut positions: Vec<&str> = agent_positions.iter().map(String::as_str).collect();
+        for position in self.agents.keys() {
+            if !positions.contains(&position.as_str()) {
+                positions.push(position);
+            }
+        }
+        for position in positions {
+            println!(
+                "  agent {}: {}",
+                position,
+                self.settings_for(position, "").describe()
+            );
+        }
+        for function_name in self.functions.keys() {
+            println!(
+                "  ai_function {}: {}",
+                function_name,
+                self.settings_for("", function_name).describe()
+            );
+        }
+    }
+}
+
+static LLM_CONFIG: OnceLock<LlmConfig> = OnceLock::new();
+
+// Install the loaded config for the process (only the first call wins)
+pub fn set_llm_config(config: LlmConfig) -> bool {
+    LLM_CONFIG.set(config).is_ok()
+}
+
+// Installed config, or an empty one so only env overrides apply
+pub fn active_config() -> &'static LlmConfig {
+    LLM_CONFIG.get_or_init(LlmConfig::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_CONFIG: &str = r#"
+        [defaults]
+        model = "openai/gpt-4.1-mini"
+        temperature = 0.1
+
+        [agents."Backend Developer"]
+        model = "openai/gpt-4.1"
+        max_tokens = 8000
+
+        [functions.print_fixed_code]
+        temperature = 0.0
+        provider = "local"
+    "#;
+
+    #[test]
+    fn tests_most_specific_settings_win() {
+        let config: LlmConfig = LlmConfig::from_toml(SAMPLE_CONFIG).expect("Invalid config");
+
+        let manager: LlmSettings =
+            config.settings_for("Project Manager", "convert_user_input_to_goal");
+        assert_eq!(manager.model.as_deref(), Some("openai/gpt-4.1-mini"));
+        assert_eq!(manager.max_tokens, None);
+
+        let fixer: LlmSettings = config.settings_for("Backend Developer", "print_fixed_code");
+        assert_eq!(fixer.model.as_deref(), Some("openai/gpt-4.1"));
+        assert_eq!(fixer.max_tokens, Some(8000));
+        assert_eq!(fixer.temperature, Some(0.0));
+        assert_eq!(fixer.provider.as_deref(), Some("local"));
+        assert_eq!(
+            config
+                .provider_override("Backend Developer", "print_fixed_code")
+                .as_deref(),
+            Some("local")
+        );
+        assert_eq!(
+            config.provider_override("Project Manager", "convert_user_input_to_goal"),
+            None
+        );
+
+        let options: ChatOptions = fixer.apply(&ChatOptions::default());
+        assert_eq!(options.model.as_deref(), Some("openai/gpt-4.1"));
+        assert_eq!(options.max_tokens, Some(8000));
+    }
+
+    #[test]
+    fn tests_example_config_parses() {
+        let config: LlmConfig = LlmConfig::from_toml(include_str!("../../llm_config.example.toml"))
+            .expect("Example config invalid");
+        assert_eq!(
+            config
+                .settings_for("Backend Developer", "print_fixed_code")
+                .max_tokens,
+            Some(8000)
+        );
+    }
+
+    #[test]
+    fn tests_rejects_unknown_keys() {
+        assert!(LlmConfig::from_toml("[defaults]\nmodle = \"typo\"").is_err());
+        assert_eq!(
+            env_prefix("AGENT", "Backend Developer"),
+            "LLM_AGENT_BACKEND_DEVELOPER"
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+// Why an LLM call failed, classified so the caller can decide whether to retry
+#[derive(Debug, Clone, PartialEq)]
+pub enum LlmError {
+    RateLimited {
+        retry_after: Option<Duration>,
+        message: String,
+    },
+    Server {
+        status: u16,
+        message: String,
+    },
+    Timeout(String),
+    Network(String),
+    Auth {
+        status: u16,
+        message: String,
+    },
+    BadRequest {
+        status: u16,
+        message: String,
+    },
+    Decode(String),
+    Config(String),
+    BudgetExceeded(String),
+    ToolLoop(String),
+}
+
+impl LlmError {
+    // Map a non-success HTTP response onto an error class
+    pub fn from_status(status: StatusCode, headers: &HeaderMap, body: &str) -> Self {
+        let message: String = body.chars().take(500).collect();
+        match status.as_u16() {
+            429 => Self::RateLimited {
+                retry_after: parse_retry_after(headers),
+                message,
+            },
+            401 | 403 => Self::Auth {
+                status: status.as_u16(),
+                message,
+            },
+            408 => Self::Timeout(message),
+            code if status.is_server_error() => Self::Server {
+                status: code,
+                message,
+            },
+            code => Self::BadRequest {
+                status: code,
+                message,
+            },
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::RateLimited { .. } | Self::Server { .. } | Self::Timeout(_) | Self::Network(_)
+        )
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+// Retry-After is either delay seconds or an HTTP date; only seconds are honoured
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|seconds| *seconds >= 0.0)
+        .map(Duration::from_secs_f64)
+}
+
+impl fmt::Display for LlmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RateLimited { message, .. } => {
+                write!(f, "rate limited by LLM provider: {}", message)
+            }
+            Self::Server { status, message } => {
+                write!(f, "LLM provider server error {}: {}", status, message)
+            }
+            Self::Timeout(message) => write!(f, "LLM request timed out: {}", message),
+            Self::Network(message) => write!(f, "network error calling LLM: {}", message),
+            Self::Auth { status, message } => write!(
+                f,
+                "LLM provider rejected credentials ({}): {}",
+                status, message
+            ),
+            Self::BadRequest { status, message } => {
+                write!(f, "LLM provider rejected request ({}): {}", status, message)
+            }
+            Self::Decode(message) => write!(f, "failed to decode LLM response: {}", message),
+            Self::Config(message) => write!(f, "LLM configuration error: {}", message),
+            Self::BudgetExceeded(message) => write!(f, "LLM budget exhausted: {}", message),
+            Self::ToolLoop(message) => write!(f, "LLM tool loop did not finish: {}", message),
+        }
+    }
+}
+
+impl Error for LlmError {}
+
+impl From<reqwest::Error> for LlmError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            Self::Timeout(error.to_string())
+        } else if error.is_decode() {
+            Self::Decode(error.to_string())
+        } else if error.is_builder() {
+            Self::Config(error.to_string())
+        } else {
+            Self::Network(error.to_string())
+        }
+    }
+}
+
+impl From<serde_json::Error> for LlmError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Decode(error.to_string())
+    }
+}
+
+impl From<reqwest::header::InvalidHeaderValue> for LlmError {
+    fn from(error: reqwest::header::InvalidHeaderValue) -> Self {
+        Self::Config(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn tests_classifies_status_codes() {
+        let mut headers: HeaderMap = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+
+        let rate_limited: LlmError =
+            LlmError::from_status(StatusCode::TOO_MANY_REQUESTS, &headers, "");
+        assert_eq!(rate_limited.retry_after(), Some(Duration::from_secs(7)));
+        assert!(rate_limited.is_retryable());
+
+        let server: LlmError =
+            LlmError::from_status(StatusCode::BAD_GATEWAY, &HeaderMap::new(), "");
+        assert!(server.is_retryable());
+
+        let auth: LlmError = LlmError::from_status(StatusCode::UNAUTHORIZED, &HeaderMap::new(), "");
+        assert!(matches!(auth, LlmError::Auth { status: 401, .. }));
+        assert!(!auth.is_retryable());
+
+        let bad: LlmError = LlmError::from_status(StatusCode::BAD_REQUEST, &HeaderMap::new(), "");
+        assert!(!bad.is_retryable());
+    }
+}
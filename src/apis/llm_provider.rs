@@ -0,0 +1,534 @@
+use crate::apis::anthropic_provider::AnthropicProvider;
+use crate::apis::call_request::{send_chat_request, send_chat_stream_request};
+use crate::apis::llm_error::LlmError;
+use crate::models::general::llm::{
+    ChatCompletion, ChatOptions, LlmReply, Message, ResponseFormat, ToolCall, ToolDefinition,
+};
+
+use async_trait::async_trait;
+use dotenv::dotenv;
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderValue};
+use serde::Serialize;
+use std::env;
+use std::error::Error;
+use std::sync::{Arc, OnceLock};
+
+const OPEN_ROUTER_ENDPOINT: &str = "https://openrouter.ai/api/v1/chat/completions";
+const OPEN_ROUTER_DEFAULT_MODEL: &str = "openai/gpt-4.1";
+const OPENAI_DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+const OPENAI_DEFAULT_MODEL: &str = "gpt-4.1";
+const LOCAL_DEFAULT_BASE_URL: &str = "http://localhost:11434/v1";
+const LOCAL_DEFAULT_MODEL: &str = "llama3.1";
+const DEFAULT_TEMPERATURE: f32 = 0.1; // Low temperature keeps answers consistent
+
+// Receives streamed reply text as it arrives
+pub type OnDelta<'s> = dyn for<'a> FnMut(&'a str) + Send + 's;
+
+// Anything able to answer a list of chat messages
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    fn name(&self) -> &str;
+    fn model(&self) -> &str;
+
+    // Sampling temperature sent with each request (part of the response cache key)
+    fn temperature(&self) -> f32 {
+        0.0
+    }
+    async fn chat(
+        &self,
+        messages: Vec<Message>,
+        options: &ChatOptions,
+    ) -> Result<LlmReply, LlmError>;
+
+    // Stream the reply, handing text to on_delta as it arrives (default: one delta with the full reply)
+    async fn chat_stream(
+        &self,
+        messages: Vec<Message>,
+        options: &ChatOptions,
+        on_delta: &mut OnDelta<'_>,
+    ) -> Result<LlmReply, LlmError> {
+        let reply: LlmReply = self.chat(messages, options).await?;
+        on_delta(&reply.content);
+        Ok(reply)
+    }
+}
+
+// OpenRouter gateway (default provider)
+#[derive(Debug, Clone)]
+pub struct OpenRouterProvider {
+    api_key: String,
+    model: String,
+    temperature: f32,
+}
+
+impl OpenRouterProvider {
+    pub fn new(api_key: String, model: String) -> Self {
+        Self {
+            api_key,
+            model,
+            temperature: DEFAULT_TEMPERATURE,
+        }
+    }
+
+    pub fn from_env() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let api_key: String = env::var("OPEN_ROUTER_AI_KEY")
+            .map_err(|_| "OPEN_ROUTER_AI_KEY not set in .env file")?;
+        let model: String =
+            env::var("OPEN_ROUTER_MODEL").unwrap_or(OPEN_ROUTER_DEFAULT_MODEL.to_string());
+        Ok(Self::new(api_key, model))
+    }
+
+    fn chat_completion(&self, messages: Vec<Message>, options: &ChatOptions) -> ChatCompletion {
+        ChatCompletion {
+            model: options.model.clone().unwrap_or(self.model.clone()),
+            messages,
+            temperature: options.temperature.unwrap_or(self.temperature),
+            max_tokens: options.max_tokens,
+            response_format: options.response_format.clone(),
+            tools: options.tools.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenRouterProvider {
+    fn name(&self) -> &str {
+        "openrouter"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn temperature(&self) -> f32 {
+        self.temperature
+    }
+
+    async fn chat(
+        &self,
+        messages: Vec<Message>,
+        options: &ChatOptions,
+    ) -> Result<LlmReply, LlmError> {
+        let headers: HeaderMap = bearer_headers(Some(&self.api_key))?;
+        let chat_completion: ChatCompletion = self.chat_completion(messages, options);
+
+        send_chat_request(OPEN_ROUTER_ENDPOINT, headers, &chat_completion).await
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: Vec<Message>,
+        options: &ChatOptions,
+        on_delta: &mut OnDelta<'_>,
+    ) -> Result<LlmReply, LlmError> {
+        let headers: HeaderMap = bearer_headers(Some(&self.api_key))?;
+        let chat_completion: ChatCompletion = self.chat_completion(messages, options);
+
+        send_chat_stream_request(OPEN_ROUTER_ENDPOINT, headers, &chat_completion, on_delta).await
+    }
+}
+
+// Any server speaking the OpenAI chat completions protocol (OpenAI, Azure proxies, vLLM...)
+#[derive(Debug, Clone)]
+pub struct OpenAiCompatibleProvider {
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+    temperature: f32,
+}
+
+impl OpenAiCompatibleProvider {
+    pub fn new(base_url: String, api_key: Option<String>, model: String) -> Self {
+        Self {
+            base_url,
+            api_key,
+            model,
+            temperature: DEFAULT_TEMPERATURE,
+        }
+    }
+
+    pub fn from_env() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let base_url: String =
+            env::var("OPENAI_BASE_URL").unwrap_or(OPENAI_DEFAULT_BASE_URL.to_string());
+        let api_key: Option<String> = env::var("OPENAI_API_KEY").ok();
+        let model: String = env::var("OPENAI_MODEL").unwrap_or(OPENAI_DEFAULT_MODEL.to_string());
+        Ok(Self::new(base_url, api_key, model))
+    }
+
+    fn chat_completion(&self, messages: Vec<Message>, options: &ChatOptions) -> ChatCompletion {
+        ChatCompletion {
+            model: options.model.clone().unwrap_or(self.model.clone()),
+            messages,
+            temperature: options.temperature.unwrap_or(self.temperature),
+            max_tokens: options.max_tokens,
+            response_format: options.response_format.clone(),
+            tools: options.tools.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiCompatibleProvider {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn temperature(&self) -> f32 {
+        self.temperature
+    }
+
+    async fn chat(
+        &self,
+        messages: Vec<Message>,
+        options: &ChatOptions,
+    ) -> Result<LlmReply, LlmError> {
+        let headers: HeaderMap = bearer_headers(self.api_key.as_deref())?;
+        let chat_completion: ChatCompletion = self.chat_completion(messages, options);
+
+        send_chat_request(&chat_endpoint(&self.base_url), headers, &chat_completion).await
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: Vec<Message>,
+        options: &ChatOptions,
+        on_delta: &mut OnDelta<'_>,
+    ) -> Result<LlmReply, LlmError> {
+        let headers: HeaderMap = bearer_headers(self.api_key.as_deref())?;
+        let chat_completion: ChatCompletion = self.chat_completion(messages, options);
+
+        send_chat_stream_request(
+            &chat_endpoint(&self.base_url),
+            headers,
+            &chat_completion,
+            on_delta,
+        )
+        .await
+    }
+}
+
+// Local model server (Ollama or llama.cpp server), no key required
+#[derive(Debug, Clone)]
+pub struct LocalProvider {
+    base_url: String,
+    model: String,
+    temperature: f32,
+}
+
+// Local servers do not all accept content part arrays, so text is sent flat
+#[derive(Debug, Serialize)]
+struct PlainMessage {
+    role: String,
+    content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_calls: Option<Vec<ToolCall>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PlainChatCompletion {
+    model: String,
+    messages: Vec<PlainMessage>,
+    temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<ToolDefinition>>,
+}
+
+impl LocalProvider {
+    pub fn new(base_url: String, model: String) -> Self {
+        Self {
+            base_url,
+            model,
+            temperature: DEFAULT_TEMPERATURE,
+        }
+    }
+
+    pub fn from_env() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let base_url: String =
+            env::var("LOCAL_LLM_BASE_URL").unwrap_or(LOCAL_DEFAULT_BASE_URL.to_string());
+        let model: String = env::var("LOCAL_LLM_MODEL").unwrap_or(LOCAL_DEFAULT_MODEL.to_string());
+        Ok(Self::new(base_url, model))
+    }
+
+    // Fails on image parts instead of dropping them, the flat text format has nowhere to put them
+    fn plain_chat_completion(
+        &self,
+        messages: Vec<Message>,
+        options: &ChatOptions,
+    ) -> Result<PlainChatCompletion, LlmError> {
+        if messages
+            .iter()
+            .flat_map(|message| message.content.iter())
+            .any(|content| content.image_url.is_some())
+        {
+            return Err(LlmError::Config(format!(
+                "local provider ({}) cannot accept image content",
+                self.model
+            )));
+        }
+
+        let messages: Vec<PlainMessage> = messages
+            .into_iter()
+            .map(|message| PlainMessage {
+                role: message.role,
+                content: message
+                    .content
+                    .into_iter()
+                    .map(|content| content.text)
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+                tool_calls: message.tool_calls,
+                tool_call_id: message.tool_call_id,
+            })
+            .collect();
+
+        Ok(PlainChatCompletion {
+            model: options.model.clone().unwrap_or(self.model.clone()),
+            messages,
+            temperature: options.temperature.unwrap_or(self.temperature),
+            max_tokens: options.max_tokens,
+            response_format: options.response_format.clone(),
+            tools: options.tools.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for LocalProvider {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn temperature(&self) -> f32 {
+        self.temperature
+    }
+
+    async fn chat(
+        &self,
+        messages: Vec<Message>,
+        options: &ChatOptions,
+    ) -> Result<LlmReply, LlmError> {
+        send_chat_request(
+            &chat_endpoint(&self.base_url),
+            bearer_headers(None)?,
+            &self.plain_chat_completion(messages, options)?,
+        )
+        .await
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: Vec<Message>,
+        options: &ChatOptions,
+        on_delta: &mut OnDelta<'_>,
+    ) -> Result<LlmReply, LlmError> {
+        send_chat_stream_request(
+            &chat_endpoint(&self.base_url),
+            bearer_headers(None)?,
+            &self.plain_chat_completion(messages, options)?,
+            on_delta,
+        )
+        .await
+    }
+}
+
+fn chat_endpoint(base_url: &str) -> String {
+    format!("{}/chat/completions", base_url.trim_end_matches('/'))
+}
+
+fn bearer_headers(api_key: Option<&str>) -> Result<HeaderMap, LlmError> {
+    let mut headers = HeaderMap::new();
+    if let Some(api_key) = api_key {
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", api_key))?,
+        );
+    }
+    Ok(headers)
+}
+
+// Pick the provider named by LLM_PROVIDER (openrouter, openai, anthropic or local)
+pub fn provider_from_env() -> Result<Arc<dyn LlmProvider>, Box<dyn Error + Send + Sync>> {
+    dotenv().ok();
+
+    let provider_name: String = env::var("LLM_PROVIDER").unwrap_or("openrouter".to_string());
+    provider_from_name(&provider_name)
+}
+
+pub fn provider_from_name(
+    provider_name: &str,
+) -> Result<Arc<dyn LlmProvider>, Box<dyn Error + Send + Sync>> {
+    // API keys are often kept only in .env
+    dotenv().ok();
+
+    match provider_name.trim().to_lowercase().as_str() {
+        "openrouter" => Ok(Arc::new(OpenRouterProvider::from_env()?)),
+        "openai" => Ok(Arc::new(OpenAiCompatibleProvider::from_env()?)),
+        "anthropic" | "claude" => Ok(Arc::new(AnthropicProvider::from_env()?)),
+        "local" | "ollama" | "llamacpp" => Ok(Arc::new(LocalProvider::from_env()?)),
+        other => Err(format!("Unknown LLM provider: {}", other).into()),
+    }
+}
+
+static DEFAULT_PROVIDER: OnceLock<Arc<dyn LlmProvider>> = OnceLock::new();
+
+tokio::task_local! {
+    static SCOPED_PROVIDER: Arc<dyn LlmProvider>;
+}
+
+// Replace the process wide provider (only the first call wins)
+pub fn set_default_provider(provider: Arc<dyn LlmProvider>) -> bool {
+    DEFAULT_PROVIDER.set(provider).is_ok()
+}
+
+// Run a future with every LLM call inside it routed to the given provider
+#[cfg(test)]
+pub async fn with_provider<F: Future>(provider: Arc<dyn LlmProvider>, future: F) -> F::Output {
+    SCOPED_PROVIDER.scope(provider, future).await
+}
+
+// Provider in scope, falling back to the one configured from the environment
+pub fn active_provider() -> Result<Arc<dyn LlmProvider>, Box<dyn Error + Send + Sync>> {
+    if let Ok(provider) = SCOPED_PROVIDER.try_with(Arc::clone) {
+        return Ok(provider);
+    }
+
+    if let Some(provider) = DEFAULT_PROVIDER.get() {
+        return Ok(Arc::clone(provider));
+    }
+
+    let provider: Arc<dyn LlmProvider> = provider_from_env()?;
+    Ok(Arc::clone(DEFAULT_PROVIDER.get_or_init(|| provider)))
+}
+
+// Provider in scope, else the one named by the agent's config, else the default
+pub fn provider_for(
+    provider_name: Option<&str>,
+) -> Result<Arc<dyn LlmProvider>, Box<dyn Error + Send + Sync>> {
+    if let Ok(provider) = SCOPED_PROVIDER.try_with(Arc::clone) {
+        return Ok(provider);
+    }
+
+    match provider_name {
+        Some(provider_name) => provider_from_name(provider_name),
+        None => active_provider(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::stub_server::{StubResponse, StubServer};
+    use crate::models::general::llm::{Content, ImageUrl};
+
+    #[test]
+    fn tests_chat_endpoint_joins_base_url() {
+        assert_eq!(
+            chat_endpoint("http://localhost:11434/v1/"),
+            "http://localhost:11434/v1/chat/completions"
+        );
+    }
+
+    #[test]
+    fn tests_provider_from_name() {
+        let provider: Arc<dyn LlmProvider> =
+            provider_from_name("Ollama").expect("Failed to build local provider");
+        assert_eq!(provider.name(), "local");
+        assert!(provider_from_name("unknown").is_err());
+    }
+
+    #[tokio::test]
+    async fn tests_streams_deltas_from_sse() {
+        let server: StubServer = StubServer::start(vec![StubResponse::sse(&[
+            r#"{"choices":[{"delta":{"role":"assistant"}}]}"#,
+            r#"{"choices":[{"delta":{"content":"fn main() "}}]}"#,
+            r#"{"choices":[{"delta":{"content":"{}"}}]}"#,
+            r#"{"choices":[],"usage":{"prompt_tokens":12,"completion_tokens":4,"total_tokens":16}}"#,
+            "[DONE]",
+        ])])
+        .await;
+        let provider: OpenAiCompatibleProvider =
+            OpenAiCompatibleProvider::new(server.base_url(), None, "gpt-4.1".to_string());
+        let messages: Vec<Message> = vec![Message {
+            role: "user".to_string(),
+            content: vec![Content {
+                r#type: "text".to_string(),
+                text: "write main".to_string(),
+                image_url: None,
+            }],
+            ..Message::default()
+        }];
+
+        let mut deltas: Vec<String> = vec![];
+        let reply: LlmReply = provider
+            .chat_stream(messages, &ChatOptions::default(), &mut |delta: &str| {
+                deltas.push(delta.to_string())
+            })
+            .await
+            .expect("Stream failed");
+
+        assert_eq!(deltas, vec!["fn main() ", "{}"]);
+        assert_eq!(reply.content, "fn main() {}");
+        assert_eq!(reply.usage.map(|usage| usage.total_tokens), Some(16));
+        assert!(server.requests()[0].contains("\"stream\":true"));
+    }
+
+    #[tokio::test]
+    async fn tests_local_provider_rejects_images() {
+        let provider: LocalProvider =
+            LocalProvider::new("http://127.0.0.1:9".to_string(), "llama3".to_string());
+        let messages: Vec<Message> = vec![Message {
+            role: "user".to_string(),
+            content: vec![Content {
+                r#type: "image_url".to_string(),
+                text: String::new(),
+                image_url: Some(ImageUrl {
+                    url: "data:image/png;base64,iVBORw0KGgo=".to_string(),
+                }),
+            }],
+            ..Message::default()
+        }];
+
+        let result: Result<LlmReply, LlmError> =
+            provider.chat(messages, &ChatOptions::default()).await;
+        assert!(matches!(result, Err(LlmError::Config(_))));
+    }
+
+    #[tokio::test]
+    async fn tests_options_override_model_settings() {
+        let server: StubServer = StubServer::start(vec![StubResponse::json(
+            200,
+            r#"{"choices":[{"message":{"content":"ok"}}]}"#,
+        )])
+        .await;
+        let provider: OpenAiCompatibleProvider =
+            OpenAiCompatibleProvider::new(server.base_url(), None, "gpt-4.1".to_string());
+        let options: ChatOptions = ChatOptions {
+            model: Some("gpt-4.1-mini".to_string()),
+            temperature: Some(0.7),
+            max_tokens: Some(256),
+            ..ChatOptions::default()
+        };
+
+        provider.chat(vec![], &options).await.expect("Chat failed");
+
+        let body: serde_json::Value =
+            serde_json::from_str(&server.requests()[0]).expect("Request body not JSON");
+        assert_eq!(body["model"], "gpt-4.1-mini");
+        assert_eq!(body["max_tokens"], 256);
+        assert!((body["temperature"].as_f64().unwrap_or_default() - 0.7).abs() < 1e-6);
+    }
+}
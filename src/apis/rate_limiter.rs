@@ -0,0 +1,266 @@
+use std::env;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio::time::{Instant, sleep};
+
+const DEFAULT_MAX_IN_FLIGHT: usize = 4;
+
+// Quotas shared by every LLM call of the process
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimits {
+    pub requests_per_minute: Option<u32>,
+    pub tokens_per_minute: Option<u64>,
+    pub max_in_flight: usize,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+        }
+    }
+}
+
+impl RateLimits {
+    // Read LLM_RPM, LLM_TPM and LLM_MAX_IN_FLIGHT (unset RPM/TPM means unlimited)
+    pub fn from_env() -> Self {
+        let read = |name: &str| {
+            env::var(name)
+                .ok()
+                .and_then(|value| value.trim().parse::<u64>().ok())
+                .filter(|value| *value > 0)
+        };
+
+        Self {
+            requests_per_minute: read("LLM_RPM").map(|rpm| rpm as u32),
+            tokens_per_minute: read("LLM_TPM"),
+            max_in_flight: read("LLM_MAX_IN_FLIGHT")
+                .map(|max| max as usize)
+                .unwrap_or(DEFAULT_MAX_IN_FLIGHT),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let per_minute = |limit: Option<u64>, unit: &str| match limit {
+            Some(limit) => format!("{} {}/min", limit, unit),
+            None => format!("unlimited {}", unit),
+        };
+        format!(
+            "{}, {}, {} in flight",
+            per_minute(self.requests_per_minute.map(u64::from), "requests"),
+            per_minute(self.tokens_per_minute, "tokens"),
+            self.max_in_flight
+        )
+    }
+}
+
+// Bucket refilled continuously up to one minute's quota
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    available: f64,
+    refill_per_sec: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn per_minute(quota: u64, now: Instant) -> Self {
+        Self {
+            capacity: quota as f64,
+            available: quota as f64,
+            refill_per_sec: quota as f64 / 60.0,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed: f64 = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.available = (self.available + elapsed * self.refill_per_sec).min(self.capacity);
+        self.updated = now;
+    }
+
+    // Time until `amount` can be taken; more than a full bucket is clamped so it is not stuck forever
+    fn wait_for(&self, amount: f64) -> Duration {
+        let missing: f64 = amount.min(self.capacity) - self.available;
+        if missing <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(missing / self.refill_per_sec)
+        }
+    }
+
+    // Balance may go negative when a call used more than reserved; later calls then wait it off
+    fn take(&mut self, amount: f64) {
+        self.available -= amount;
+    }
+}
+
+#[derive(Debug)]
+struct Buckets {
+    requests: Option<TokenBucket>,
+    tokens: Option<TokenBucket>,
+}
+
+// Permission to make one LLM call; the in-flight slot is released on drop
+#[derive(Debug)]
+pub struct RatePermit<'a> {
+    _slot: SemaphorePermit<'a>,
+    reserved_tokens: u64,
+    pub waited: Duration,
+}
+
+// Token buckets for RPM and TPM plus a cap on concurrent calls
+#[derive(Debug)]
+pub struct RateLimiter {
+    limits: RateLimits,
+    in_flight: Semaphore,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        let now: Instant = Instant::now();
+        Self {
+            limits,
+            in_flight: Semaphore::new(limits.max_in_flight.max(1)),
+            buckets: Mutex::new(Buckets {
+                requests: limits
+                    .requests_per_minute
+                    .map(|rpm| TokenBucket::per_minute(u64::from(rpm), now)),
+                tokens: limits
+                    .tokens_per_minute
+                    .map(|tpm| TokenBucket::per_minute(tpm, now)),
+            }),
+        }
+    }
+
+    pub fn limits(&self) -> RateLimits {
+        self.limits
+    }
+
+    // Wait for a free slot and for one request plus the estimated tokens in the buckets
+    pub async fn acquire(&self, estimated_tokens: u64) -> RatePermit<'_> {
+        let started: Instant = Instant::now();
+        let slot: SemaphorePermit<'_> = self
+            .in_flight
+            .acquire()
+            .await
+            .expect("Rate limiter semaphore closed");
+
+        loop {
+            let wait: Duration = {
+                let mut buckets = self.buckets.lock().expect("Rate limiter lock poisoned");
+                let now: Instant = Instant::now();
+                let mut wait: Duration = Duration::ZERO;
+                if let Some(requests) = buckets.requests.as_mut() {
+                    requests.refill(now);
+                    wait = wait.max(requests.wait_for(1.0));
+                }
+                if let Some(tokens) = buckets.tokens.as_mut() {
+                    tokens.refill(now);
+                    wait = wait.max(tokens.wait_for(estimated_tokens as f64));
+                }
+
+                if wait.is_zero() {
+                    if let Some(requests) = buckets.requests.as_mut() {
+                        requests.take(1.0);
+                    }
+                    if let Some(tokens) = buckets.tokens.as_mut() {
+                        tokens.take(estimated_tokens as f64);
+                    }
+                }
+                wait
+            };
+
+            if wait.is_zero() {
+                break;
+            }
+            sleep(wait).await;
+        }
+
+        RatePermit {
+            _slot: slot,
+            reserved_tokens: estimated_tokens,
+            waited: started.elapsed(),
+        }
+    }
+
+    // Charge (or refund) the difference between the reserved and the reported tokens
+    pub fn settle(&self, permit: &RatePermit<'_>, actual_tokens: u64) {
+        let mut buckets = self.buckets.lock().expect("Rate limiter lock poisoned");
+        if let Some(tokens) = buckets.tokens.as_mut() {
+            tokens.take(actual_tokens as f64 - permit.reserved_tokens as f64);
+        }
+    }
+}
+
+static DEFAULT_LIMITER: OnceLock<Arc<RateLimiter>> = OnceLock::new();
+
+// Process wide limiter configured from the environment, shared by every agent
+pub fn active_limiter() -> Arc<RateLimiter> {
+    Arc::clone(DEFAULT_LIMITER.get_or_init(|| Arc::new(RateLimiter::new(RateLimits::from_env()))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::timeout;
+
+    #[test]
+    fn tests_bucket_refills_over_the_minute() {
+        let start: Instant = Instant::now();
+        let mut bucket: TokenBucket = TokenBucket::per_minute(60, start);
+        assert_eq!(bucket.wait_for(60.0), Duration::ZERO);
+
+        bucket.take(60.0);
+        assert_eq!(bucket.wait_for(1.0), Duration::from_secs(1));
+        // Requests larger than the quota wait for a full bucket, not forever
+        assert_eq!(bucket.wait_for(600.0), Duration::from_secs(60));
+
+        bucket.refill(start + Duration::from_secs(30));
+        assert_eq!(bucket.wait_for(30.0), Duration::ZERO);
+        bucket.refill(start + Duration::from_secs(600));
+        assert_eq!(bucket.available, 60.0);
+    }
+
+    #[tokio::test]
+    async fn tests_limits_in_flight_calls() {
+        let limiter: RateLimiter = RateLimiter::new(RateLimits {
+            max_in_flight: 1,
+            ..RateLimits::default()
+        });
+
+        let first: RatePermit<'_> = limiter.acquire(10).await;
+        assert!(
+            timeout(Duration::from_millis(50), limiter.acquire(10))
+                .await
+                .is_err()
+        );
+        drop(first);
+        assert!(
+            timeout(Duration::from_millis(50), limiter.acquire(10))
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn tests_waits_for_token_quota() {
+        // 6000 tokens per minute refill at 100 per second
+        let limiter: RateLimiter = RateLimiter::new(RateLimits {
+            tokens_per_minute: Some(6000),
+            ..RateLimits::default()
+        });
+
+        let first: RatePermit<'_> = limiter.acquire(5000).await;
+        assert!(first.waited < Duration::from_millis(50));
+        limiter.settle(&first, 6000);
+        drop(first);
+
+        let second: RatePermit<'_> = limiter.acquire(20).await;
+        assert!(second.waited >= Duration::from_millis(150));
+    }
+}
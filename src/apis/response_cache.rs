@@ -0,0 +1,217 @@
+use crate::models::general::llm::{ChatOptions, LlmReply, Message, ToolCall, Usage};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_CACHE_DIR: &str = ".cache/llm_responses";
+const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+// Cached reply stored as <key>.json in the cache directory
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    created_at: u64,
+    model: String,
+    response: String,
+    #[serde(default)]
+    usage: Option<Usage>,
+    #[serde(default)]
+    tool_calls: Vec<ToolCall>,
+}
+
+// Content-addressed store of LLM replies so identical requests are not paid for twice
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+impl ResponseCache {
+    pub fn new(dir: PathBuf, ttl: Duration) -> Self {
+        Self { dir, ttl }
+    }
+
+    // Directory from LLM_CACHE_DIR, time to live from LLM_CACHE_TTL_SECS
+    pub fn from_env() -> Self {
+        let dir: PathBuf = env::var("LLM_CACHE_DIR")
+            .map(PathBuf::from)
+            .unwrap_or(PathBuf::from(DEFAULT_CACHE_DIR));
+        let ttl: Duration = env::var("LLM_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TTL);
+        Self::new(dir, ttl)
+    }
+
+    pub fn dir(&self) -> &PathBuf {
+        &self.dir
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    // Fingerprint of everything that shapes the reply: provider, model, temperature, messages and options
+    pub fn key(
+        provider: &str,
+        model: &str,
+        temperature: f32,
+        messages: &[Message],
+        options: &ChatOptions,
+    ) -> String {
+        let serialized: String =
+            serde_json::to_string(&(provider, model, temperature, messages, options))
+                .expect("Failed to serialize request for hashing");
+        Sha256::digest(serialized.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    // Reply stored under the key, unless missing, unreadable or older than the TTL
+    pub fn get(&self, key: &str) -> Option<LlmReply> {
+        let path: PathBuf = self.entry_path(key);
+        let entry: CacheEntry = serde_json::from_str(&fs::read_to_string(&path).ok()?).ok()?;
+
+        if unix_now().saturating_sub(entry.created_at) > self.ttl.as_secs() {
+            let _ = fs::remove_file(&path);
+            return None;
+        }
+
+        Some(LlmReply {
+            content: entry.response,
+            usage: entry.usage,
+            tool_calls: entry.tool_calls,
+        })
+    }
+
+    pub fn put(&self, key: &str, model: &str, reply: &LlmReply) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let entry: CacheEntry = CacheEntry {
+            created_at: unix_now(),
+            model: model.to_string(),
+            response: reply.content.clone(),
+            usage: reply.usage,
+            tool_calls: reply.tool_calls.clone(),
+        };
+        fs::write(self.entry_path(key), serde_json::to_string_pretty(&entry)?)
+    }
+}
+
+static DEFAULT_CACHE: OnceLock<Arc<ResponseCache>> = OnceLock::new();
+
+tokio::task_local! {
+    static SCOPED_CACHE: Arc<ResponseCache>;
+}
+
+// Turn caching on for the whole process (main does this with --cache or LLM_CACHE=1)
+pub fn set_default_cache(cache: Arc<ResponseCache>) -> bool {
+    DEFAULT_CACHE.set(cache).is_ok()
+}
+
+// Run a future with LLM calls inside it served from and stored into the given cache
+#[cfg(test)]
+pub async fn with_response_cache<F: Future>(cache: Arc<ResponseCache>, future: F) -> F::Output {
+    SCOPED_CACHE.scope(cache, future).await
+}
+
+// Cache in scope, else the process default; None means caching is off
+pub fn active_cache() -> Option<Arc<ResponseCache>> {
+    SCOPED_CACHE
+        .try_with(Arc::clone)
+        .ok()
+        .or_else(|| DEFAULT_CACHE.get().cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::general::text_message;
+
+    fn temp_cache(name: &str, ttl: Duration) -> ResponseCache {
+        let dir: PathBuf =
+            env::temp_dir().join(format!("llm_cache_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        ResponseCache::new(dir, ttl)
+    }
+
+    #[test]
+    fn tests_cache_round_trip_and_key_inputs() {
+        let cache: ResponseCache = temp_cache("round_trip", DEFAULT_TTL);
+        let messages: Vec<Message> = vec![text_message("user", "goal please")];
+        let key: String =
+            ResponseCache::key("openai", "gpt-4.1", 0.1, &messages, &ChatOptions::default());
+
+        assert_ne!(
+            key,
+            ResponseCache::key("openai", "gpt-4.1", 0.7, &messages, &ChatOptions::default())
+        );
+        assert_ne!(
+            key,
+            ResponseCache::key(
+                "openai",
+                "gpt-4.1-mini",
+                0.1,
+                &messages,
+                &ChatOptions::default()
+            )
+        );
+        // Another provider serving the same model name may answer differently
+        assert_ne!(
+            key,
+            ResponseCache::key(
+                "openrouter",
+                "gpt-4.1",
+                0.1,
+                &messages,
+                &ChatOptions::default()
+            )
+        );
+        assert!(cache.get(&key).is_none());
+
+        let reply: LlmReply = LlmReply {
+            content: "build a webserver that says hi".to_string(),
+            ..LlmReply::default()
+        };
+        cache
+            .put(&key, "gpt-4.1", &reply)
+            .expect("Cache write failed");
+        assert_eq!(cache.get(&key), Some(reply));
+
+        let _ = fs::remove_dir_all(cache.dir());
+    }
+
+    #[test]
+    fn tests_cache_expires_entries() {
+        let cache: ResponseCache = temp_cache("expiry", Duration::from_secs(60));
+        let key: String = "expired".to_string();
+        fs::create_dir_all(cache.dir()).expect("Cache dir failed");
+        fs::write(
+            cache.entry_path(&key),
+            r#"{"created_at": 1, "model": "gpt-4.1", "response": "stale"}"#,
+        )
+        .expect("Cache write failed");
+
+        assert!(cache.get(&key).is_none());
+        assert!(!cache.entry_path(&key).exists());
+
+        let _ = fs::remove_dir_all(cache.dir());
+    }
+}
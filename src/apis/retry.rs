@@ -0,0 +1,277 @@
+use crate::apis::llm_error::LlmError;
+use crate::apis::llm_provider::{LlmProvider, OnDelta};
+use crate::models::general::llm::{ChatOptions, LlmReply, Message};
+
+use std::collections::hash_map::RandomState;
+use std::env;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use tokio::time::sleep;
+
+// How hard to try before giving up on an LLM call
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    // Defaults overridden by LLM_MAX_ATTEMPTS, LLM_RETRY_BASE_MS and LLM_RETRY_MAX_MS
+    pub fn from_env() -> Self {
+        let defaults: RetryPolicy = RetryPolicy::default();
+        let read = |name: &str| {
+            env::var(name)
+                .ok()
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+
+        Self {
+            max_attempts: read("LLM_MAX_ATTEMPTS")
+                .map(|attempts| attempts.max(1) as u32)
+                .unwrap_or(defaults.max_attempts),
+            base_delay: read("LLM_RETRY_BASE_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.base_delay),
+            max_delay: read("LLM_RETRY_MAX_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.max_delay),
+        }
+    }
+
+    // Retry-After wins when the provider sent one, otherwise exponential backoff with jitter
+    pub fn delay_for(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after.min(self.max_delay);
+        }
+
+        let exponential: Duration = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+
+        // Equal jitter: half fixed, half random, so parallel callers spread out
+        let half: Duration = exponential / 2;
+        half + half.mul_f64(random_fraction())
+    }
+
+    // Delay before the next attempt, or None when the error is final or attempts ran out
+    fn next_delay(&self, attempt: u32, error: &LlmError) -> Option<Duration> {
+        (error.is_retryable() && attempt < self.max_attempts)
+            .then(|| self.delay_for(attempt, error.retry_after()))
+    }
+}
+
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(0);
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// Call the provider, retrying transient failures according to the policy
+pub async fn chat_with_retry<F>(
+    provider: &dyn LlmProvider,
+    messages: &[Message],
+    options: &ChatOptions,
+    policy: &RetryPolicy,
+    mut on_retry: F,
+) -> Result<LlmReply, LlmError>
+where
+    F: FnMut(u32, &LlmError, Duration),
+{
+    let mut attempt: u32 = 1;
+    loop {
+        let error: LlmError = match provider.chat(messages.to_vec(), options).await {
+            Ok(reply) => return Ok(reply),
+            Err(error) => error,
+        };
+        let Some(delay) = policy.next_delay(attempt, &error) else {
+            return Err(error);
+        };
+        on_retry(attempt, &error, delay);
+        sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+// Stream from the provider with the same retries; a retried attempt streams again from the start
+pub async fn chat_stream_with_retry<F>(
+    provider: &dyn LlmProvider,
+    messages: &[Message],
+    options: &ChatOptions,
+    policy: &RetryPolicy,
+    mut on_retry: F,
+    on_delta: &mut OnDelta<'_>,
+) -> Result<LlmReply, LlmError>
+where
+    F: FnMut(u32, &LlmError, Duration),
+{
+    let mut attempt: u32 = 1;
+    loop {
+        let error: LlmError = match provider
+            .chat_stream(messages.to_vec(), options, &mut *on_delta)
+            .await
+        {
+            Ok(reply) => return Ok(reply),
+            Err(error) => error,
+        };
+        let Some(delay) = policy.next_delay(attempt, &error) else {
+            return Err(error);
+        };
+        on_retry(attempt, &error, delay);
+        sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::llm_provider::OpenAiCompatibleProvider;
+    use crate::apis::stub_server::{StubResponse, StubServer};
+    use crate::models::general::llm::Content;
+
+    const OK_BODY: &str = r#"{"choices":[{"message":{"content":"pong"}}],"usage":{"prompt_tokens":3,"completion_tokens":1,"total_tokens":4}}"#;
+
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(20),
+        }
+    }
+
+    fn ping() -> Vec<Message> {
+        vec![Message {
+            role: "user".to_string(),
+            content: vec![Content {
+                r#type: "text".to_string(),
+                text: "ping".to_string(),
+                image_url: None,
+            }],
+            ..Message::default()
+        }]
+    }
+
+    fn stub_provider(server: &StubServer) -> OpenAiCompatibleProvider {
+        OpenAiCompatibleProvider::new(server.base_url(), None, "gpt-4.1".to_string())
+    }
+
+    #[test]
+    fn tests_delay_honours_retry_after_and_backs_off() {
+        let policy: RetryPolicy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+        };
+
+        assert_eq!(
+            policy.delay_for(1, Some(Duration::from_secs(3))),
+            Duration::from_secs(3)
+        );
+        assert_eq!(
+            policy.delay_for(1, Some(Duration::from_secs(60))),
+            Duration::from_secs(10)
+        );
+
+        let third: Duration = policy.delay_for(3, None);
+        assert!(third >= Duration::from_millis(200) && third <= Duration::from_millis(400));
+    }
+
+    #[tokio::test]
+    async fn tests_retries_transient_failures() {
+        let server: StubServer = StubServer::start(vec![
+            StubResponse::json(503, r#"{"error":"overloaded"}"#),
+            StubResponse::json(429, r#"{"error":"slow down"}"#).with_header("Retry-After", "0"),
+            StubResponse::json(200, OK_BODY),
+        ])
+        .await;
+
+        let mut retries: Vec<u32> = vec![];
+        let reply: LlmReply = chat_with_retry(
+            &stub_provider(&server),
+            &ping(),
+            &ChatOptions::default(),
+            &fast_policy(4),
+            |attempt, _, _| retries.push(attempt),
+        )
+        .await
+        .expect("Expected success after retries");
+
+        assert_eq!(reply.content, "pong");
+        assert_eq!(reply.usage.map(|usage| usage.total_tokens), Some(4));
+        assert_eq!(retries, vec![1, 2]);
+        assert_eq!(server.hits(), 3);
+        assert!(server.requests()[2].contains("\"model\":\"gpt-4.1\""));
+    }
+
+    #[tokio::test]
+    async fn tests_does_not_retry_auth_errors() {
+        let server: StubServer =
+            StubServer::start(vec![StubResponse::json(401, r#"{"error":"bad key"}"#)]).await;
+
+        let error: LlmError = chat_with_retry(
+            &stub_provider(&server),
+            &ping(),
+            &ChatOptions::default(),
+            &fast_policy(4),
+            |_, _, _| {},
+        )
+        .await
+        .expect_err("Expected auth failure");
+
+        assert!(matches!(error, LlmError::Auth { status: 401, .. }));
+        assert_eq!(server.hits(), 1);
+    }
+
+    #[tokio::test]
+    async fn tests_gives_up_after_max_attempts() {
+        let server: StubServer = StubServer::start(vec![
+            StubResponse::json(500, "boom"),
+            StubResponse::json(502, "boom"),
+        ])
+        .await;
+
+        let error: LlmError = chat_with_retry(
+            &stub_provider(&server),
+            &ping(),
+            &ChatOptions::default(),
+            &fast_policy(2),
+            |_, _, _| {},
+        )
+        .await
+        .expect_err("Expected server failure");
+
+        assert!(matches!(error, LlmError::Server { status: 502, .. }));
+        assert_eq!(server.hits(), 2);
+    }
+
+    #[tokio::test]
+    async fn tests_reports_malformed_body_as_decode_error() {
+        let server: StubServer =
+            StubServer::start(vec![StubResponse::json(200, "{\"choices\": [")]).await;
+
+        let error: LlmError = chat_with_retry(
+            &stub_provider(&server),
+            &ping(),
+            &ChatOptions::default(),
+            &fast_policy(3),
+            |_, _, _| {},
+        )
+        .await
+        .expect_err("Expected decode failure");
+
+        assert!(matches!(error, LlmError::Decode(_)));
+        assert_eq!(server.hits(), 1);
+    }
+}
@@ -0,0 +1,181 @@
+use crate::apis::llm_error::LlmError;
+use crate::apis::llm_provider::LlmProvider;
+use crate::helpers::general::messages_text;
+use crate::models::general::llm::{ChatOptions, LlmReply, Message, ToolCall, ToolCallFunction};
+
+use async_trait::async_trait;
+use std::sync::Mutex;
+
+// Canned reply handed out when a prompt contains the needle
+#[derive(Debug, Clone)]
+struct ScriptedReply {
+    needle: String,
+    response: String,
+    tool_calls: Vec<ToolCall>,
+}
+
+// Offline provider answering from a script instead of a model
+#[derive(Debug, Default)]
+pub struct ScriptedProvider {
+    replies: Mutex<Vec<ScriptedReply>>,
+    calls: Mutex<Vec<(Vec<Message>, ChatOptions)>>,
+}
+
+impl ScriptedProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Reply to any prompt whose latest message contains the needle (replies are used once, in order)
+    pub fn reply_to(self, needle: &str, response: &str) -> Self {
+        self.replies
+            .lock()
+            .expect("Scripted replies lock poisoned")
+            .push(ScriptedReply {
+                needle: needle.to_string(),
+                response: response.to_string(),
+                tool_calls: vec![],
+            });
+        self
+    }
+
+    // Answer the next prompt by asking for a tool to be run with the given JSON arguments
+    pub fn call_tool_next(self, call_id: &str, tool_name: &str, arguments: &str) -> Self {
+        self.replies
+            .lock()
+            .expect("Scripted replies lock poisoned")
+            .push(ScriptedReply {
+                needle: String::new(),
+                response: String::new(),
+                tool_calls: vec![ToolCall {
+                    id: call_id.to_string(),
+                    r#type: "function".to_string(),
+                    function: ToolCallFunction {
+                        name: tool_name.to_string(),
+                        arguments: arguments.to_string(),
+                    },
+                }],
+            });
+        self
+    }
+
+    // Reply to the prompt generated by the named ai_function
+    pub fn reply_to_function(self, function_name: &str, response: &str) -> Self {
+        self.reply_to(&format!("{}(", function_name), response)
+    }
+
+    // Reply to the next prompt whatever it contains
+    pub fn reply_next(self, response: &str) -> Self {
+        self.reply_to("", response)
+    }
+
+    // Every message list received so far
+    pub fn calls(&self) -> Vec<Vec<Message>> {
+        self.calls
+            .lock()
+            .expect("Scripted calls lock poisoned")
+            .iter()
+            .map(|(messages, _)| messages.clone())
+            .collect()
+    }
+
+    // Options (response format...) sent with each call so far
+    pub fn call_options(&self) -> Vec<ChatOptions> {
+        self.calls
+            .lock()
+            .expect("Scripted calls lock poisoned")
+            .iter()
+            .map(|(_, options)| options.clone())
+            .collect()
+    }
+
+    pub fn remaining_replies(&self) -> usize {
+        self.replies
+            .lock()
+            .expect("Scripted replies lock poisoned")
+            .len()
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ScriptedProvider {
+    fn name(&self) -> &str {
+        "scripted"
+    }
+
+    fn model(&self) -> &str {
+        "scripted"
+    }
+
+    async fn chat(
+        &self,
+        messages: Vec<Message>,
+        options: &ChatOptions,
+    ) -> Result<LlmReply, LlmError> {
+        // Match on the latest message so replayed history does not trigger old replies
+        let prompt: String = messages_text(
+            messages
+                .last()
+                .map(std::slice::from_ref)
+                .unwrap_or_default(),
+        );
+        self.calls
+            .lock()
+            .expect("Scripted calls lock poisoned")
+            .push((messages, options.clone()));
+
+        let mut replies = self.replies.lock().expect("Scripted replies lock poisoned");
+        let position: usize = replies
+            .iter()
+            .position(|reply| prompt.contains(&reply.needle))
+            .ok_or_else(|| {
+                LlmError::Config(format!("No scripted reply for prompt: {:.120}", prompt))
+            })?;
+
+        let reply: ScriptedReply = replies.remove(position);
+        Ok(LlmReply {
+            content: reply.response,
+            usage: None,
+            tool_calls: reply.tool_calls,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai_functions::aifunc_managing::convert_user_input_to_goal;
+    use crate::helpers::general::extend_ai_funtion;
+
+    #[tokio::test]
+    async fn tests_scripted_provider_matches_function() {
+        let provider: ScriptedProvider = ScriptedProvider::new()
+            .reply_to_function("print_site_urls", "[]")
+            .reply_to_function(
+                "convert_user_input_to_goal",
+                "build a webserver that says hi",
+            );
+
+        let message: Message = extend_ai_funtion(convert_user_input_to_goal, "say hi");
+        let response: String = provider
+            .chat(vec![message], &ChatOptions::default())
+            .await
+            .expect("Scripted reply missing")
+            .content;
+
+        assert_eq!(response, "build a webserver that says hi");
+        assert_eq!(provider.calls().len(), 1);
+        assert_eq!(provider.remaining_replies(), 1);
+    }
+
+    #[tokio::test]
+    async fn tests_scripted_provider_errors_when_exhausted() {
+        let provider: ScriptedProvider = ScriptedProvider::new();
+        assert!(
+            provider
+                .chat(vec![], &ChatOptions::default())
+                .await
+                .is_err()
+        );
+    }
+}
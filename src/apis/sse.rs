@@ -0,0 +1,82 @@
+// Incremental server-sent events decoder: feed raw body chunks, get complete event payloads
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+    data_lines: Vec<String>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Data of every event completed by this chunk (multi-line data joined with \n)
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events: Vec<String> = vec![];
+
+        // Split on \n only once the whole line arrived, so UTF-8 split across chunks stays intact
+        while let Some(newline) = self.buffer.iter().position(|byte| *byte == b'\n') {
+            let line_bytes: Vec<u8> = self.buffer.drain(..=newline).collect();
+            let line: String = String::from_utf8_lossy(&line_bytes)
+                .trim_end_matches(['\r', '\n'])
+                .to_string();
+
+            if line.is_empty() {
+                if let Some(event) = self.take_event() {
+                    events.push(event);
+                }
+            } else if let Some(data) = line.strip_prefix("data:") {
+                self.data_lines
+                    .push(data.strip_prefix(' ').unwrap_or(data).to_string());
+            }
+            // event:, id:, retry: and ": comment" lines carry nothing the chat stream needs
+        }
+
+        events
+    }
+
+    // Event left open when the body ended without a trailing blank line
+    pub fn finish(&mut self) -> Option<String> {
+        if !self.buffer.is_empty() {
+            let rest: Vec<u8> = std::mem::take(&mut self.buffer);
+            let line: String = String::from_utf8_lossy(&rest).trim_end().to_string();
+            if let Some(data) = line.strip_prefix("data:") {
+                self.data_lines
+                    .push(data.strip_prefix(' ').unwrap_or(data).to_string());
+            }
+        }
+        self.take_event()
+    }
+
+    fn take_event(&mut self) -> Option<String> {
+        if self.data_lines.is_empty() {
+            return None;
+        }
+        Some(std::mem::take(&mut self.data_lines).join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_decodes_events_split_across_chunks() {
+        let body: &str = ": keep-alive\n\ndata: {\"a\":1}\n\ndata: first\ndata: second\r\n\r\ndata: héllo\n\ndata: [DONE]";
+        let bytes: &[u8] = body.as_bytes();
+        let mut decoder: SseDecoder = SseDecoder::new();
+        let mut events: Vec<String> = vec![];
+
+        // Three byte chunks also split the two-byte 'é'
+        for chunk in bytes.chunks(3) {
+            events.extend(decoder.push(chunk));
+        }
+        events.extend(decoder.finish());
+
+        assert_eq!(
+            events,
+            vec!["{\"a\":1}", "first\nsecond", "héllo", "[DONE]"]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+// One canned HTTP response served by the stub
+#[derive(Debug, Clone)]
+pub struct StubResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl StubResponse {
+    pub fn json(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: body.to_string(),
+        }
+    }
+
+    // Server-sent events stream, one "data:" event per payload
+    pub fn sse(events: &[&str]) -> Self {
+        Self {
+            status: 200,
+            headers: vec![("Content-Type".to_string(), "text/event-stream".to_string())],
+            body: events
+                .iter()
+                .map(|event| format!("data: {}\n\n", event))
+                .collect(),
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+// Local HTTP server answering each request with the next scripted response
+pub struct StubServer {
+    port: u16,
+    requests: Arc<Mutex<Vec<String>>>,
+    handle: JoinHandle<()>,
+}
+
+impl StubServer {
+    pub async fn start(responses: Vec<StubResponse>) -> Self {
+        let listener: TcpListener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind stub server");
+        let port: u16 = listener
+            .local_addr()
+            .expect("Stub server has no address")
+            .port();
+        let requests: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![]));
+
+        let recorded = Arc::clone(&requests);
+        let handle: JoinHandle<()> = tokio::spawn(async move {
+            let mut responses = responses.into_iter();
+            while let Ok((stream, _)) = listener.accept().await {
+                let response: StubResponse = responses.next().unwrap_or_else(|| {
+                    StubResponse::json(500, r#"{"error":"no stub response left"}"#)
+                });
+                serve(stream, response, &recorded).await;
+            }
+        });
+
+        Self {
+            port,
+            requests,
+            handle,
+        }
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+
+    pub fn hits(&self) -> usize {
+        self.requests
+            .lock()
+            .expect("Stub requests lock poisoned")
+            .len()
+    }
+
+    // Bodies of the requests received so far
+    pub fn requests(&self) -> Vec<String> {
+        self.requests
+            .lock()
+            .expect("Stub requests lock poisoned")
+            .clone()
+    }
+}
+
+impl Drop for StubServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn serve(mut stream: TcpStream, response: StubResponse, recorded: &Arc<Mutex<Vec<String>>>) {
+    let mut raw: Vec<u8> = vec![];
+    let mut buffer = [0u8; 4096];
+
+    // Read headers, then as much body as Content-Length announces
+    let header_end: usize = loop {
+        let read: usize = stream.read(&mut buffer).await.unwrap_or(0);
+        if read == 0 {
+            return;
+        }
+        raw.extend_from_slice(&buffer[..read]);
+        if let Some(position) = raw.windows(4).position(|window| window == b"\r\n\r\n") {
+            break position + 4;
+        }
+    };
+
+    let head: String = String::from_utf8_lossy(&raw[..header_end]).to_lowercase();
+    let content_length: usize = head
+        .lines()
+        .find_map(|line| line.strip_prefix("content-length:"))
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(0);
+
+    while raw.len() < header_end + content_length {
+        let read: usize = stream.read(&mut buffer).await.unwrap_or(0);
+        if read == 0 {
+            break;
+        }
+        raw.extend_from_slice(&buffer[..read]);
+    }
+
+    let body: String = String::from_utf8_lossy(&raw[header_end..]).to_string();
+    recorded
+        .lock()
+        .expect("Stub requests lock poisoned")
+        .push(body);
+
+    let mut reply: String = format!("HTTP/1.1 {} STUB\r\n", response.status);
+    for (name, value) in &response.headers {
+        reply.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    reply.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.body.len(),
+        response.body
+    ));
+
+    let _ = stream.write_all(reply.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
@@ -0,0 +1,444 @@
+use crate::helpers::prompt_registry::BUILTIN_VERSION;
+use crate::models::general::llm::Usage;
+
+use dotenv::dotenv;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Rough chars-per-token ratio used when a provider does not report usage
+const CHARS_PER_TOKEN: u64 = 4;
+
+// Dollar price per million tokens for one model
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    pub prompt_per_million: f64,
+    pub completion_per_million: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PriceTable {
+    prices: HashMap<String, ModelPrice>,
+}
+
+impl Default for PriceTable {
+    fn default() -> Self {
+        let mut prices: HashMap<String, ModelPrice> = HashMap::new();
+        let gpt_4_1 = ModelPrice {
+            prompt_per_million: 2.0,
+            completion_per_million: 8.0,
+        };
+        let gpt_4_1_mini = ModelPrice {
+            prompt_per_million: 0.4,
+            completion_per_million: 1.6,
+        };
+        prices.insert("openai/gpt-4.1".to_string(), gpt_4_1);
+        prices.insert("gpt-4.1".to_string(), gpt_4_1);
+        prices.insert("openai/gpt-4.1-mini".to_string(), gpt_4_1_mini);
+        prices.insert("gpt-4.1-mini".to_string(), gpt_4_1_mini);
+        Self { prices }
+    }
+}
+
+impl PriceTable {
+    // Defaults extended by the JSON file named in LLM_PRICE_TABLE ({"model": {"prompt_per_million": .., "completion_per_million": ..}})
+    pub fn from_env() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut table: PriceTable = PriceTable::default();
+        if let Ok(path) = env::var("LLM_PRICE_TABLE") {
+            let contents: String = fs::read_to_string(&path)?;
+            let prices: HashMap<String, ModelPrice> = serde_json::from_str(&contents)?;
+            table.prices.extend(prices);
+        }
+        Ok(table)
+    }
+
+    // Unknown models (e.g. local ones) are free
+    pub fn cost(&self, model: &str, prompt_tokens: u64, completion_tokens: u64) -> f64 {
+        match self.prices.get(model) {
+            Some(price) => {
+                (prompt_tokens as f64 * price.prompt_per_million
+                    + completion_tokens as f64 * price.completion_per_million)
+                    / 1_000_000.0
+            }
+            None => 0.0,
+        }
+    }
+}
+
+// Hard limits for one project run
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Budget {
+    pub max_tokens: Option<u64>,
+    pub max_dollars: Option<f64>,
+}
+
+impl Budget {
+    // Read LLM_BUDGET_TOKENS and LLM_BUDGET_USD
+    pub fn from_env() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let max_tokens: Option<u64> = match env::var("LLM_BUDGET_TOKENS") {
+            Ok(value) => Some(value.trim().parse()?),
+            Err(_) => None,
+        };
+        let max_dollars: Option<f64> = match env::var("LLM_BUDGET_USD") {
+            Ok(value) => Some(value.trim().parse()?),
+            Err(_) => None,
+        };
+        Ok(Self {
+            max_tokens,
+            max_dollars,
+        })
+    }
+}
+
+// One LLM call attributed to the agent and ai_function that made it
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageRecord {
+    pub agent_position: String,
+    pub ai_function: String,
+    pub model: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+    pub estimated: bool,
+    // Prompt template (name@version, or "builtin") that produced the call
+    pub prompt_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct UsageTotals {
+    pub calls: usize,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+}
+
+impl UsageTotals {
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    fn add(&mut self, record: &UsageRecord) {
+        self.calls += 1;
+        self.prompt_tokens += record.prompt_tokens;
+        self.completion_tokens += record.completion_tokens;
+        self.cost += record.cost;
+    }
+}
+
+// Usage collected over a whole project run
+#[derive(Debug)]
+pub struct UsageLedger {
+    budget: Budget,
+    prices: PriceTable,
+    records: Mutex<Vec<UsageRecord>>,
+    // Time calls spent queued behind the rate limiter
+    queue_wait: Mutex<Duration>,
+    // Prompt version currently used by each (agent position, ai_function)
+    prompt_versions: Mutex<HashMap<(String, String), String>>,
+}
+
+pub fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(CHARS_PER_TOKEN)
+}
+
+impl UsageLedger {
+    pub fn new(budget: Budget, prices: PriceTable) -> Self {
+        Self {
+            budget,
+            prices,
+            records: Mutex::new(vec![]),
+            queue_wait: Mutex::new(Duration::ZERO),
+            prompt_versions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_env() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        dotenv().ok();
+        Ok(Self::new(Budget::from_env()?, PriceTable::from_env()?))
+    }
+
+    // Store one call, estimating tokens from the text when the provider sent no usage
+    pub fn record(
+        &self,
+        agent_position: &str,
+        ai_function: &str,
+        model: &str,
+        usage: Option<Usage>,
+        prompt: &str,
+        completion: &str,
+    ) -> UsageRecord {
+        let (prompt_tokens, completion_tokens, estimated) = match usage {
+            Some(usage) => (usage.prompt_tokens, usage.completion_tokens, false),
+            None => (estimate_tokens(prompt), estimate_tokens(completion), true),
+        };
+
+        let record: UsageRecord = UsageRecord {
+            agent_position: agent_position.to_string(),
+            ai_function: ai_function.to_string(),
+            model: model.to_string(),
+            prompt_tokens,
+            completion_tokens,
+            cost: self.prices.cost(model, prompt_tokens, completion_tokens),
+            estimated,
+            prompt_version: self
+                .prompt_versions
+                .lock()
+                .expect("Usage ledger lock poisoned")
+                .get(&(agent_position.to_string(), ai_function.to_string()))
+                .cloned(),
+        };
+
+        self.records
+            .lock()
+            .expect("Usage ledger lock poisoned")
+            .push(record.clone());
+        record
+    }
+
+    // Attribute the following calls of this agent and ai_function to a prompt version
+    pub fn note_prompt_version(&self, agent_position: &str, ai_function: &str, version: &str) {
+        self.prompt_versions
+            .lock()
+            .expect("Usage ledger lock poisoned")
+            .insert(
+                (agent_position.to_string(), ai_function.to_string()),
+                version.to_string(),
+            );
+    }
+
+    pub fn record_queue_wait(&self, waited: Duration) {
+        *self.queue_wait.lock().expect("Usage ledger lock poisoned") += waited;
+    }
+
+    pub fn queue_wait(&self) -> Duration {
+        *self.queue_wait.lock().expect("Usage ledger lock poisoned")
+    }
+
+    pub fn records(&self) -> Vec<UsageRecord> {
+        self.records
+            .lock()
+            .expect("Usage ledger lock poisoned")
+            .clone()
+    }
+
+    pub fn totals(&self) -> UsageTotals {
+        let mut totals: UsageTotals = UsageTotals::default();
+        for record in self.records().iter() {
+            totals.add(record);
+        }
+        totals
+    }
+
+    // Totals per (agent position, ai_function), in first-call order
+    pub fn totals_by_function(&self) -> Vec<(String, String, UsageTotals)> {
+        let mut grouped: Vec<(String, String, UsageTotals)> = vec![];
+        for record in self.records().iter() {
+            match grouped.iter_mut().find(|(position, function, _)| {
+                *position == record.agent_position && *function == record.ai_function
+            }) {
+                Some((_, _, totals)) => totals.add(record),
+                None => {
+                    let mut totals: UsageTotals = UsageTotals::default();
+                    totals.add(record);
+                    grouped.push((
+                        record.agent_position.clone(),
+                        record.ai_function.clone(),
+                        totals,
+                    ));
+                }
+            }
+        }
+        grouped
+    }
+
+    // Totals per prompt version, for comparing templates; calls without a noted version are skipped
+    pub fn totals_by_prompt_version(&self) -> Vec<(String, UsageTotals)> {
+        let mut grouped: Vec<(String, UsageTotals)> = vec![];
+        for record in self.records().iter() {
+            let Some(version) = &record.prompt_version else {
+                continue;
+            };
+            match grouped
+                .iter_mut()
+                .find(|(grouped_version, _)| grouped_version == version)
+            {
+                Some((_, totals)) => totals.add(record),
+                None => {
+                    let mut totals: UsageTotals = UsageTotals::default();
+                    totals.add(record);
+                    grouped.push((version.clone(), totals));
+                }
+            }
+        }
+        grouped
+    }
+
+    // Describe the exceeded limit, if any
+    pub fn budget_exceeded(&self) -> Option<String> {
+        let totals: UsageTotals = self.totals();
+        if let Some(max_tokens) = self.budget.max_tokens
+            && totals.total_tokens() > max_tokens
+        {
+            return Some(format!(
+                "token budget exceeded: {} used of {}",
+                totals.total_tokens(),
+                max_tokens
+            ));
+        }
+        if let Some(max_dollars) = self.budget.max_dollars
+            && totals.cost > max_dollars
+        {
+            return Some(format!(
+                "dollar budget exceeded: ${:.4} used of ${:.4}",
+                totals.cost, max_dollars
+            ));
+        }
+        None
+    }
+
+    pub fn print_report(&self) {
+        println!("\nLLM usage for this run:");
+        for (position, function, totals) in self.totals_by_function() {
+            println!(
+                "  {} / {}: {} call(s), {} prompt + {} completion tokens, ${:.4}",
+                position,
+                function,
+                totals.calls,
+                totals.prompt_tokens,
+                totals.completion_tokens,
+                totals.cost
+            );
+        }
+        let totals: UsageTotals = self.totals();
+        println!(
+            "  Total: {} call(s), {} tokens, ${:.4}",
+            totals.calls,
+            totals.total_tokens(),
+            totals.cost
+        );
+        let by_prompt: Vec<(String, UsageTotals)> = self.totals_by_prompt_version();
+        if by_prompt
+            .iter()
+            .any(|(version, _)| version != BUILTIN_VERSION)
+        {
+            println!("  By prompt version:");
+            for (version, totals) in by_prompt {
+                println!(
+                    "    {}: {} call(s), {} tokens, ${:.4}",
+                    version,
+                    totals.calls,
+                    totals.total_tokens(),
+                    totals.cost
+                );
+            }
+        }
+        let queue_wait: Duration = self.queue_wait();
+        if !queue_wait.is_zero() {
+            println!(
+                "  Rate limiter queue wait: {:.1}s",
+                queue_wait.as_secs_f32()
+            );
+        }
+    }
+}
+
+tokio::task_local! {
+    static RUN_LEDGER: Arc<UsageLedger>;
+}
+
+// Run a future with every LLM call inside it recorded on the given ledger
+pub async fn with_usage_ledger<F: Future>(ledger: Arc<UsageLedger>, future: F) -> F::Output {
+    RUN_LEDGER.scope(ledger, future).await
+}
+
+pub fn current_ledger() -> Option<Arc<UsageLedger>> {
+    RUN_LEDGER.try_with(Arc::clone).ok()
+}
+
+// Exceeded limit of the run in scope, if any
+pub fn run_budget_exceeded() -> Option<String> {
+    current_ledger().and_then(|ledger| ledger.budget_exceeded())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gpt_usage(prompt_tokens: u64, completion_tokens: u64) -> Option<Usage> {
+        Some(Usage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        })
+    }
+
+    #[test]
+    fn tests_ledger_sums_and_prices_calls() {
+        let ledger: UsageLedger = UsageLedger::new(Budget::default(), PriceTable::default());
+        ledger.record(
+            "Solution Architect",
+            "print_project_scope",
+            "openai/gpt-4.1",
+            gpt_usage(1_000, 500),
+            "",
+            "",
+        );
+        ledger.record(
+            "Solution Architect",
+            "print_project_scope",
+            "openai/gpt-4.1",
+            gpt_usage(1_000, 500),
+            "",
+            "",
+        );
+        ledger.record(
+            "Backend Developer",
+            "print_fixed_code",
+            "llama3.1",
+            None,
+            "abcdefgh",
+            "abcd",
+        );
+
+        let totals: UsageTotals = ledger.totals();
+        assert_eq!(totals.calls, 3);
+        assert_eq!(totals.prompt_tokens, 2_002);
+        assert_eq!(totals.completion_tokens, 1_001);
+        assert!((totals.cost - 0.012).abs() < 1e-9);
+        assert_eq!(ledger.totals_by_function().len(), 2);
+        assert!(ledger.records()[2].estimated);
+    }
+
+    #[test]
+    fn tests_ledger_reports_exceeded_budget() {
+        let budget: Budget = Budget {
+            max_tokens: Some(100),
+            max_dollars: None,
+        };
+        let ledger: UsageLedger = UsageLedger::new(budget, PriceTable::default());
+        ledger.record(
+            "Project Manager",
+            "convert_user_input_to_goal",
+            "gpt-4.1",
+            gpt_usage(60, 30),
+            "",
+            "",
+        );
+        assert!(ledger.budget_exceeded().is_none());
+
+        ledger.record(
+            "Project Manager",
+            "convert_user_input_to_goal",
+            "gpt-4.1",
+            gpt_usage(10, 10),
+            "",
+            "",
+        );
+        assert!(ledger.budget_exceeded().is_some());
+    }
+}
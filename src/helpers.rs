@@ -1,3 +1,10 @@
 pub mod command_line;
+pub mod extraction;
 pub mod general;
-pub mod hammer_loader;
\ No newline at end of file
+pub mod hammer_loader;
+pub mod image_input;
+pub mod prompt_eval;
+pub mod prompt_registry;
+pub mod security_scan;
+pub mod structured_output;
+pub mod tools;
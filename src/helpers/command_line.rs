@@ -3,11 +3,26 @@ use crossterm::{
     style::{Color, ResetColor, SetForegroundColor},
 };
 
+use crate::helpers::image_input::ImageInput;
+use crate::helpers::security_scan::SecurityReport;
+use crate::models::agents::agent_traits::BackendLanguage;
+
 use std::io::{Stdout, Write, stdin, stdout};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// When set, confirm_safe_code answers "All good" without prompting (unattended test runs)
+static AUTO_CONFIRM: AtomicBool = AtomicBool::new(false);
+
+// When set ("--block-high-risk" or SECURITY_BLOCK_HIGH=1), high severity review findings stop the run
+static BLOCK_HIGH_RISK: AtomicBool = AtomicBool::new(false);
+
+// When set ("--stream" or LLM_STREAM=1), generated code is printed live as it streams in
+static STREAM_OUTPUT: AtomicBool = AtomicBool::new(false);
 
 #[derive(PartialEq, Debug)]
 pub enum PrintCommand {
     AICall,
+    CacheHit,
     UnitTest,
     Issue,
 }
@@ -18,6 +33,7 @@ impl PrintCommand {
 
         let statement_color: Color = match self {
             Self::AICall => Color::Cyan,
+            Self::CacheHit => Color::DarkCyan,
             Self::UnitTest => Color::Magenta,
             Self::Issue => Color::Red,
         };
@@ -33,13 +49,22 @@ impl PrintCommand {
     }
 }
 
+// Echo a chunk of streamed model output without a newline
+pub fn print_live_output(chunk: &str) {
+    let mut stdout: Stdout = stdout();
+    stdout.execute(SetForegroundColor(Color::DarkGrey)).unwrap();
+    print!("{}", chunk);
+    stdout.execute(ResetColor).unwrap();
+    stdout.flush().unwrap();
+}
+
 // get user request input for the prompt
 pub fn get_user_response(question: &str) -> String {
     let mut stdout: std::io::Stdout = std::io::stdout();
 
     //Print the question in a specific color this one is blue
     stdout.execute(SetForegroundColor(Color::Green)).unwrap();
-    println!("");
+    println!();
     println!("{}: ", question);
 
     // Reset the color to default
@@ -52,7 +77,21 @@ pub fn get_user_response(question: &str) -> String {
         .expect("Failed to read user response");
 
     // Trim the input to remove any trailing newline characters
-    return user_response.trim().to_string();
+    user_response.trim().to_string()
+}
+
+// Ask for optional wireframes or diagrams (paths or data URLs separated by spaces, Enter skips)
+pub fn get_user_images(question: &str) -> Vec<ImageInput> {
+    loop {
+        let response: String = get_user_response(question);
+        let images: Result<Vec<ImageInput>, _> =
+            response.split_whitespace().map(ImageInput::parse).collect();
+
+        match images {
+            Ok(images) => return images,
+            Err(e) => println!("{}, please try again.", e),
+        }
+    }
 }
 
 pub fn display_andreanator_logo() {
@@ -99,7 +138,7 @@ pub fn display_andreanator_logo() {
     println!("{}", logo);
 }
 
-pub fn languages_options() -> String {
+pub fn languages_options() -> BackendLanguage {
     loop {
         println!("\nPlease select a language for the webserver:");
         println!("1. Rust");
@@ -111,33 +150,72 @@ pub fn languages_options() -> String {
         stdin()
             .read_line(&mut input)
             .expect("Failed to read choice");
-        let choice = input.trim();
 
-        match choice {
-            "1" => {
-                println!("You selected Rust.");
-                return "Rust".to_string();
+        match BackendLanguage::parse(&input) {
+            Some(language) => {
+                println!("You selected {}.", language.label());
+                return language;
             }
-            "2" => {
-                println!("You selected Java (Spring Boot).");
-                return "Java".to_string();
-            }
-            _ => {
+            None => {
                 println!("Invalid choice, please try again.");
             }
         }
     }
 }
 
+#[cfg(test)]
+pub fn set_auto_confirm(enabled: bool) {
+    AUTO_CONFIRM.store(enabled, Ordering::Relaxed);
+}
+
+pub fn set_block_high_risk(enabled: bool) {
+    BLOCK_HIGH_RISK.store(enabled, Ordering::Relaxed);
+}
+
+pub fn block_high_risk_enabled() -> bool {
+    BLOCK_HIGH_RISK.load(Ordering::Relaxed)
+        || std::env::var("SECURITY_BLOCK_HIGH")
+            .is_ok_and(|value| matches!(value.trim(), "1" | "true"))
+}
+
+// Show the security review before asking; blocking refuses high severity findings even unattended
+pub async fn confirm_reviewed_code(report: &SecurityReport, reviewer_position: &str) -> bool {
+    report.print(reviewer_position);
+    if report.has_high() && block_high_risk_enabled() {
+        PrintCommand::Issue.print_agent_msg(
+            reviewer_position,
+            "High severity findings, refusing to run the code",
+        );
+        return false;
+    }
+    // Waiting on stdin would stall every agent polled on the scheduler's task
+    tokio::task::spawn_blocking(confirm_safe_code)
+        .await
+        .unwrap_or(false)
+}
+
+pub fn set_stream_output(enabled: bool) {
+    STREAM_OUTPUT.store(enabled, Ordering::Relaxed);
+}
+
+pub fn stream_output_enabled() -> bool {
+    STREAM_OUTPUT.load(Ordering::Relaxed)
+        || std::env::var("LLM_STREAM").is_ok_and(|value| matches!(value.trim(), "1" | "true"))
+}
+
 // Get user response that code is safe to run
 pub fn confirm_safe_code() -> bool {
     let mut stdout: std::io::Stdout = std::io::stdout();
 
-    loop {
+    if AUTO_CONFIRM.load(Ordering::Relaxed) {
+        println!("Auto-confirming AI written code (unattended run).");
+        return true;
+    }
 
+    loop {
         // {Print question in a specific color this one in Blue}
         stdout.execute(SetForegroundColor(Color::Blue)).unwrap();
-        println!("");
+        println!();
         println!("WARNING: you are about to run code written entirely by AI. ");
         println!("Review your code and confirm you wish to continue. ");
 
@@ -152,21 +230,26 @@ pub fn confirm_safe_code() -> bool {
         stdout.execute(ResetColor).unwrap();
 
         let mut human_response = String::new();
-        stdin()
+        let bytes_read: usize = stdin()
             .read_line(&mut human_response)
             .expect("Failed to read human response");
 
+        // Closed stdin means nobody can confirm, so stop rather than loop forever
+        if bytes_read == 0 {
+            return false;
+        }
+
         // Trim the input to remove any trailing newline characters
         let human_response: String = human_response.trim().to_string();
 
         match human_response.as_str() {
             "1" | "ok" | "y" => {
                 return true;
-            },
+            }
             "2" | "no" | "n" => {
                 return false;
-            },
-            _=> {
+            }
+            _ => {
                 println!("Invalid input, please enter 1 or 2.");
             }
         }
@@ -0,0 +1,274 @@
+use serde_json::Value;
+
+// Fenced block found in an LLM reply
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeBlock {
+    pub language: Option<String>,
+    pub code: String,
+}
+
+// All ``` fenced blocks in order; an unclosed last fence (truncated reply) runs to the end
+pub fn code_blocks(reply: &str) -> Vec<CodeBlock> {
+    let mut blocks: Vec<CodeBlock> = vec![];
+    let mut open: Option<(Option<String>, Vec<&str>)> = None;
+
+    for line in reply.lines() {
+        let trimmed: &str = line.trim();
+        match open.take() {
+            None => {
+                if let Some(tag) = trimmed.strip_prefix("```") {
+                    let language: Option<String> = tag
+                        .split_whitespace()
+                        .next()
+                        .map(|word| word.trim_matches(|c| c == '{' || c == '}' || c == '.'))
+                        .filter(|word| !word.is_empty())
+                        .map(|word| word.to_lowercase());
+                    open = Some((language, vec![]));
+                }
+            }
+            Some((language, lines)) if trimmed == "```" => {
+                blocks.push(CodeBlock {
+                    language,
+                    code: lines.join("\n"),
+                });
+            }
+            Some((language, mut lines)) => {
+                lines.push(line);
+                open = Some((language, lines));
+            }
+        }
+    }
+
+    if let Some((language, lines)) = open
+        && !lines.is_empty()
+    {
+        blocks.push(CodeBlock {
+            language,
+            code: lines.join("\n"),
+        });
+    }
+
+    blocks
+}
+
+fn language_matches(block: &CodeBlock, language: &str) -> bool {
+    let aliases: &[&str] = match language {
+        "rust" | "rs" => &["rust", "rs"],
+        "json" => &["json", "jsonc", "json5"],
+        "typescript" | "ts" => &["typescript", "ts", "tsx"],
+        "javascript" | "js" => &["javascript", "js", "jsx"],
+        "python" | "py" => &["python", "py"],
+        other => return block.language.as_deref() == Some(other),
+    };
+    block
+        .language
+        .as_deref()
+        .is_some_and(|tag| aliases.contains(&tag))
+}
+
+// Source code from a reply: the largest block tagged with the language, else the largest block,
+// else the reply itself without leading and trailing prose
+pub fn extract_code(reply: &str, language: &str) -> String {
+    let blocks: Vec<CodeBlock> = code_blocks(reply);
+    let largest = |matching: &dyn Fn(&CodeBlock) -> bool| {
+        blocks
+            .iter()
+            .filter(|block| matching(block))
+            .max_by_key(|block| block.code.trim().len())
+            .map(|block| block.code.trim().to_string())
+    };
+
+    largest(&|block| language_matches(block, language))
+        .or_else(|| largest(&|_| true))
+        .unwrap_or_else(|| strip_prose(reply))
+}
+
+// Sentences such as "Here is the updated code:" framing an unfenced reply
+fn is_prose_line(line: &str) -> bool {
+    let trimmed: &str = line.trim();
+    if trimmed.is_empty() {
+        return true;
+    }
+    let starts_like_sentence: bool = trimmed.chars().next().is_some_and(char::is_uppercase);
+    let ends_like_sentence: bool = trimmed.ends_with(':')
+        || trimmed.ends_with('.')
+        || trimmed.ends_with('!')
+        || trimmed.ends_with('?');
+    starts_like_sentence && ends_like_sentence && trimmed.contains(' ')
+}
+
+fn strip_prose(reply: &str) -> String {
+    let lines: Vec<&str> = reply.lines().collect();
+    let start: usize = lines
+        .iter()
+        .position(|line| !is_prose_line(line))
+        .unwrap_or(lines.len());
+    let end: usize = lines
+        .iter()
+        .rposition(|line| !is_prose_line(line))
+        .map_or(start, |position| position + 1);
+    lines[start..end.max(start)].join("\n")
+}
+
+// End of the balanced object or array opening at `start`, skipping brackets inside strings
+fn balanced_end(text: &str, start: usize) -> Option<usize> {
+    let mut depth: usize = 0;
+    let mut in_string: bool = false;
+    let mut escaped: bool = false;
+
+    for (offset, c) in text[start..].char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => depth += 1,
+            '}' | ']' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(start + offset + c.len_utf8());
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn balanced_json_values(text: &str) -> Vec<&str> {
+    let mut values: Vec<&str> = vec![];
+    let mut search_from: usize = 0;
+
+    while let Some(found) = text[search_from..].find(['{', '[']) {
+        let start: usize = search_from + found;
+        match balanced_end(text, start) {
+            Some(end) if serde_json::from_str::<Value>(&text[start..end]).is_ok() => {
+                values.push(&text[start..end]);
+                search_from = end;
+            }
+            _ => search_from = start + 1,
+        }
+    }
+    values
+}
+
+// Every valid JSON object or array in a reply, fenced json blocks first
+pub fn json_candidates(reply: &str) -> Vec<String> {
+    let blocks: Vec<CodeBlock> = code_blocks(reply);
+    let mut candidates: Vec<String> = blocks
+        .iter()
+        .filter(|block| language_matches(block, "json"))
+        .chain(
+            blocks
+                .iter()
+                .filter(|block| !language_matches(block, "json")),
+        )
+        .flat_map(|block| balanced_json_values(&block.code))
+        .map(str::to_string)
+        .collect();
+
+    for value in balanced_json_values(reply) {
+        if !candidates.iter().any(|candidate| candidate == value) {
+            candidates.push(value.to_string());
+        }
+    }
+    candidates
+}
+
+// First JSON value in a reply
+#[cfg(test)]
+pub fn extract_json(reply: &str) -> Option<String> {
+    json_candidates(reply).into_iter().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const CORPUS_DIR: &str = "fixtures/replies";
+
+    fn corpus(name: &str) -> String {
+        fs::read_to_string(format!("{}/{}", CORPUS_DIR, name))
+            .unwrap_or_else(|_| panic!("Missing reply fixture {}", name))
+    }
+
+    #[test]
+    fn tests_extracts_rust_code_from_messy_replies() {
+        // (fixture, line the extracted code must start with)
+        let cases: [(&str, &str); 5] = [
+            ("rust_fenced_with_prose.md", "use actix_web::"),
+            ("rust_untagged_fence.md", "use actix_web::"),
+            ("rust_multiple_blocks.md", "use actix_web::"),
+            ("rust_unfenced_with_prose.md", "use actix_web::"),
+            ("rust_truncated_fence.md", "use actix_web::"),
+        ];
+
+        for (fixture, expected_start) in cases {
+            let code: String = extract_code(&corpus(fixture), "rust");
+            assert!(
+                code.starts_with(expected_start),
+                "{}: unexpected start {:?}",
+                fixture,
+                code.lines().next()
+            );
+            assert!(!code.contains("```"), "{}: fence left in code", fixture);
+            assert!(
+                code.contains("async fn main()"),
+                "{}: main missing",
+                fixture
+            );
+            assert!(!code.contains("Here is"), "{}: prose left in code", fixture);
+        }
+    }
+
+    #[test]
+    fn tests_prefers_language_block_over_larger_block() {
+        let code: String = extract_code(&corpus("rust_multiple_blocks.md"), "rust");
+        assert!(!code.contains("cargo run"));
+        assert!(code.contains("HttpServer::new"));
+    }
+
+    #[test]
+    fn tests_extracts_json_from_messy_replies() {
+        // (fixture, expected JSON value)
+        let cases: [(&str, Value); 3] = [
+            (
+                "json_fenced_scope.md",
+                serde_json::json!({"is_crud_required": true, "is_user_login_and_logout": false, "is_external_urls_required": false}),
+            ),
+            (
+                "json_inline_urls.md",
+                serde_json::json!([
+                    "https://api.exchangerate.host/latest",
+                    "https://api.coindesk.com/v1/bpi/currentprice.json"
+                ]),
+            ),
+            (
+                "json_brackets_in_strings.md",
+                serde_json::json!([{"is_route_dynamic": "true", "method": "get", "request_body": null, "response": {"note": "returns } and ] literally"}, "route": "/item/{id}"}]),
+            ),
+        ];
+
+        for (fixture, expected) in cases {
+            let json: String = extract_json(&corpus(fixture))
+                .unwrap_or_else(|| panic!("{}: no JSON found", fixture));
+            let value: Value = serde_json::from_str(&json).expect("Extracted JSON invalid");
+            assert_eq!(value, expected, "{}", fixture);
+        }
+    }
+
+    #[test]
+    fn tests_no_json_in_plain_prose() {
+        assert_eq!(
+            extract_json("I could not find any endpoints [sorry]."),
+            None
+        );
+    }
+}
@@ -1,20 +1,43 @@
-use crate::helpers::command_line::PrintCommand;
-use crate::{
-    apis::call_request::call_gpt,
-    models::general::llm::{self, Content, Message},
+use crate::ai_functions::ai_function_traits::AiFunction;
+use crate::ai_functions::aifunc_memory::summarise_conversation;
+use crate::apis::llm_config::active_config;
+use crate::apis::llm_error::LlmError;
+use crate::apis::llm_provider::{LlmProvider, OnDelta, provider_for};
+use crate::apis::rate_limiter::{RateLimiter, RatePermit, active_limiter};
+use crate::apis::response_cache::{ResponseCache, active_cache};
+use crate::apis::retry::{RetryPolicy, chat_stream_with_retry, chat_with_retry};
+use crate::apis::usage_tracker::{current_ledger, estimate_tokens, run_budget_exceeded};
+use crate::helpers::command_line::{PrintCommand, print_live_output};
+use crate::helpers::image_input::ImageInput;
+use crate::helpers::prompt_registry::{
+    BUILTIN_VERSION, PromptInput, PromptTemplate, active_prompts,
 };
+use crate::helpers::structured_output::{
+    decode_attempts, decode_llm_json, json_schema_format, repair_instruction,
+};
+use crate::helpers::tools::ToolRegistry;
+use crate::models::agent_basic::agent_memory::{
+    MemoryConfig, SUMMARY_PREFIX, exceeds_context, history_for_request,
+};
+use crate::models::general::llm::{ChatOptions, Content, LlmReply, Message, ResponseFormat};
 
-use std::error::Error;
 use std::fs;
-use std::vec;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 use reqwest::Client;
+use schemars::JsonSchema;
 use serde::de::DeserializeOwned;
 
 // Make sure the path is correct relative to this file, or create the file if missing.
 const CODE_TEMPLATE_RUST_PATH: &str = "web_template_rust/src/code_template.rs";
-const EXEC_MAIN_RUST_PATH: &str = "web_template_rust/src/main.rs";
+// Server entry points, relative to the project directory
+const EXEC_MAIN_RUST_PATH: &str = "src/main.rs";
 pub const WEBSERVER_RUST_PATH: &str = "web_template_rust/";
+const CODE_TEMPLATE_SPRINGBOOT_PATH: &str = "web_template_springboot/templates/CodeTemplate.java";
+const EXEC_MAIN_SPRINGBOOT_PATH: &str = "src/main/java/com/andreanator/webapi/Application.java";
+pub const WEBSERVER_SPRINGBOOT_PATH: &str = "web_template_springboot/";
 const API_SCHEMA_PATH: &str = "schemas/api_schema.json";
 
 pub fn extend_ai_funtion(ai_func: fn(&str) -> &'static str, func_input: &str) -> Message {
@@ -32,48 +55,582 @@ pub fn extend_ai_funtion(ai_func: fn(&str) -> &'static str, func_input: &str) ->
         content: vec![Content {
             r#type: "text".to_string(),
             text: msg,
+            image_url: None,
         }],
+        tool_calls: None,
+        tool_call_id: None,
+    }
+}
+
+// Name an #[ai_function] was declared with, read from the source it prints
+pub fn ai_function_name(ai_func: fn(&str) -> &'static str) -> &'static str {
+    let source: &'static str = ai_func("");
+    let Some(start) = source.find("fn ").map(|index| index + 3) else {
+        return "";
+    };
+    let name: &'static str = source[start..].trim_start();
+    let end: usize = name
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(name.len());
+    &name[..end]
+}
+
+// Prompt for an ai_function: the selected template version when the registry has one,
+// else the compiled-in doc comment prompt; also returns the version label for the ledger
+fn prompt_message(
+    function_pass: fn(&str) -> &'static str,
+    input: &PromptInput,
+    agent_position: &str,
+) -> (Message, String) {
+    let template: Option<PromptTemplate> = active_prompts()
+        .and_then(|registry| registry.select(ai_function_name(function_pass)).cloned());
+
+    if let Some(template) = template {
+        match template.render(input) {
+            Ok(rendered) => {
+                let version: String = format!("{}@{}", template.name, template.version);
+                return (text_message("system", &rendered), version);
+            }
+            Err(e) => {
+                let fallback_msg: String = format!("{}, using the compiled-in prompt", e);
+                PrintCommand::Issue.print_agent_msg(agent_position, &fallback_msg);
+            }
+        }
+    }
+    (
+        extend_ai_funtion(function_pass, &input.context),
+        BUILTIN_VERSION.to_string(),
+    )
+}
+
+// Announce the call and note which prompt version the ledger should attribute it to
+fn announce_prompt(agent_position: &str, agent_operation: &str, prompt_version: &str) {
+    if prompt_version == BUILTIN_VERSION {
+        PrintCommand::AICall.print_agent_msg(agent_position, agent_operation);
+    } else {
+        let call_msg: String = format!("{} (prompt {})", agent_operation, prompt_version);
+        PrintCommand::AICall.print_agent_msg(agent_position, &call_msg);
     }
+    if let Some(ledger) = current_ledger() {
+        ledger.note_prompt_version(agent_position, agent_operation, prompt_version);
+    }
+}
+
+pub fn text_message(role: &str, text: &str) -> Message {
+    Message {
+        role: role.to_string(),
+        content: vec![Content {
+            r#type: "text".to_string(),
+            text: text.to_string(),
+            image_url: None,
+        }],
+        tool_calls: None,
+        tool_call_id: None,
+    }
+}
+
+// All text of a conversation, e.g. for token estimates
+pub fn messages_text(messages: &[Message]) -> String {
+    messages
+        .iter()
+        .flat_map(|message| message.content.iter())
+        .map(|content| content.text.as_str())
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+// Resolve the provider in scope as a typed error
+fn resolve_provider(
+    agent_position: &str,
+    agent_operation: &str,
+) -> Result<Arc<dyn LlmProvider>, LlmError> {
+    let provider_name: Option<String> =
+        active_config().provider_override(agent_position, agent_operation);
+    provider_for(provider_name.as_deref()).map_err(|e| LlmError::Config(e.to_string()))
 }
 
-// Performs calls to LLM GPT
+// Queue waits shorter than this are not worth a line in the run log
+const REPORTED_QUEUE_WAIT: Duration = Duration::from_millis(100);
+
+// Send a prepared conversation with response cache, budget guard, rate limits, retries and usage accounting
+async fn send_llm_messages(
+    provider: &dyn LlmProvider,
+    messages: &[Message],
+    options: &ChatOptions,
+    agent_position: &str,
+    agent_operation: &str,
+    on_delta: Option<&mut OnDelta<'_>>,
+) -> Result<LlmReply, LlmError> {
+    // Model, temperature and max_tokens configured for this agent and ai_function
+    let options: &ChatOptions = &active_config()
+        .settings_for(agent_position, agent_operation)
+        .apply(options);
+    let model: &str = options.model.as_deref().unwrap_or(provider.model());
+    let temperature: f32 = options.temperature.unwrap_or(provider.temperature());
+
+    // Identical requests are answered from disk for free while caching is on
+    let cache: Option<(Arc<ResponseCache>, String)> = active_cache().map(|cache| {
+        let key: String =
+            ResponseCache::key(provider.name(), model, temperature, messages, options);
+        (cache, key)
+    });
+    if let Some((cache, key)) = &cache
+        && let Some(cached_reply) = cache.get(key)
+    {
+        let hit_msg: String = format!("{} (cache hit)", agent_operation);
+        PrintCommand::CacheHit.print_agent_msg(agent_position, &hit_msg);
+        if let Some(on_delta) = on_delta {
+            on_delta(&cached_reply.content);
+        }
+        return Ok(cached_reply);
+    }
+
+    // Refuse new calls once the run budget is spent
+    if let Some(reason) = run_budget_exceeded() {
+        return Err(LlmError::BudgetExceeded(reason));
+    }
+
+    // Queue behind the shared RPM/TPM limits and in-flight cap; retries reuse the slot
+    let limiter: Arc<RateLimiter> = active_limiter();
+    let prompt_text: String = messages_text(messages);
+    let estimated_tokens: u64 =
+        estimate_tokens(&prompt_text) + u64::from(options.max_tokens.unwrap_or(0));
+    let permit: RatePermit = limiter.acquire(estimated_tokens).await;
+    if permit.waited >= REPORTED_QUEUE_WAIT {
+        let queue_msg: String = format!(
+            "{} waited {:.1}s for the rate limiter",
+            agent_operation,
+            permit.waited.as_secs_f32()
+        );
+        PrintCommand::Issue.print_agent_msg(agent_position, &queue_msg);
+    }
+    if let Some(ledger) = current_ledger() {
+        ledger.record_queue_wait(permit.waited);
+    }
+
+    // Get LLM response, retrying rate limits and transient failures
+    let policy: RetryPolicy = RetryPolicy::from_env();
+    let on_retry = |attempt: u32, error: &LlmError, delay: Duration| {
+        let retry_msg: String = format!(
+            "Retrying {} in {:.1}s after attempt {} failed: {}",
+            agent_operation,
+            delay.as_secs_f32(),
+            attempt,
+            error
+        );
+        PrintCommand::Issue.print_agent_msg(agent_position, &retry_msg);
+    };
+    let llm_reply: LlmReply = match on_delta {
+        Some(on_delta) => {
+            chat_stream_with_retry(provider, messages, options, &policy, on_retry, on_delta).await?
+        }
+        None => chat_with_retry(provider, messages, options, &policy, on_retry).await?,
+    };
+    let used_tokens: u64 = match llm_reply.usage {
+        Some(usage) => usage.prompt_tokens + usage.completion_tokens,
+        None => estimate_tokens(&prompt_text) + estimate_tokens(&llm_reply.content),
+    };
+    limiter.settle(&permit, used_tokens);
+    drop(permit);
+
+    if let Some((cache, key)) = &cache
+        && let Err(e) = cache.put(key, model, &llm_reply)
+    {
+        let cache_msg: String = format!("Could not cache {} reply: {}", agent_operation, e);
+        PrintCommand::Issue.print_agent_msg(agent_position, &cache_msg);
+    }
+
+    // Attribute token usage to this agent and ai_function for the current run
+    if let Some(ledger) = current_ledger() {
+        ledger.record(
+            agent_position,
+            agent_operation,
+            model,
+            llm_reply.usage,
+            &prompt_text,
+            &llm_reply.content,
+        );
+    }
+
+    Ok(llm_reply)
+}
+
+// Upper bound on model turns in one tool loop, from LLM_MAX_TOOL_ROUNDS
+fn max_tool_rounds() -> u32 {
+    std::env::var("LLM_MAX_TOOL_ROUNDS")
+        .ok()
+        .and_then(|value| value.trim().parse::<u32>().ok())
+        .filter(|rounds| *rounds > 0)
+        .unwrap_or(8)
+}
+
+// Offer the tools to the model and run the calls it asks for until it gives a final answer
+async fn run_tool_loop(
+    provider: &dyn LlmProvider,
+    messages: &mut Vec<Message>,
+    tools: &ToolRegistry,
+    agent_position: &str,
+    agent_operation: &str,
+) -> Result<LlmReply, LlmError> {
+    let options: ChatOptions = ChatOptions {
+        tools: Some(tools.definitions()),
+        ..ChatOptions::default()
+    };
+
+    let max_rounds: u32 = max_tool_rounds();
+    for _ in 0..max_rounds {
+        let llm_reply: LlmReply = send_llm_messages(
+            provider,
+            messages,
+            &options,
+            agent_position,
+            agent_operation,
+            None,
+        )
+        .await?;
+        if llm_reply.tool_calls.is_empty() {
+            return Ok(llm_reply);
+        }
+
+        // Echo the request back, followed by one "tool" message per call
+        let mut assistant_msg: Message = text_message("assistant", &llm_reply.content);
+        if llm_reply.content.is_empty() {
+            assistant_msg.content.clear();
+        }
+        assistant_msg.tool_calls = Some(llm_reply.tool_calls.clone());
+        messages.push(assistant_msg);
+
+        for call in &llm_reply.tool_calls {
+            let tool_msg: String = format!("{} calls tool {}", agent_operation, call.function.name);
+            PrintCommand::AICall.print_agent_msg(agent_position, &tool_msg);
+            messages.push(tools.dispatch(call).await);
+        }
+    }
+
+    Err(LlmError::ToolLoop(format!(
+        "{} still calling tools after {} round(s)",
+        agent_operation, max_rounds
+    )))
+}
+
+// Performs calls to LLM GPT through the active provider
 pub async fn ai_task_request(
-    msg_context: String,
+    msg_context: impl Into<PromptInput>,
+    agent_position: &str,
+    agent_operation: &str,
+    function_pass: for<'a> fn(&'a str) -> &'static str,
+) -> Result<String, LlmError> {
+    let provider: Arc<dyn LlmProvider> = resolve_provider(agent_position, agent_operation)?;
+    ai_task_request_with_provider(
+        provider.as_ref(),
+        msg_context,
+        agent_position,
+        agent_operation,
+        function_pass,
+    )
+    .await
+}
+
+// Performs calls to LLM GPT with an explicit provider
+pub async fn ai_task_request_with_provider(
+    provider: &dyn LlmProvider,
+    msg_context: impl Into<PromptInput>,
+    agent_position: &str,
+    agent_operation: &str,
+    function_pass: for<'a> fn(&'a str) -> &'static str,
+) -> Result<String, LlmError> {
+    ai_task_request_with_images_and_provider(
+        provider,
+        msg_context,
+        agent_position,
+        agent_operation,
+        function_pass,
+        &[],
+    )
+    .await
+}
+
+// Performs calls to LLM GPT with images (wireframes, diagrams...) attached to the prompt
+pub async fn ai_task_request_with_images(
+    msg_context: impl Into<PromptInput>,
     agent_position: &str,
     agent_operation: &str,
     function_pass: for<'a> fn(&'a str) -> &'static str,
-) -> String {
-    //Extend Ai function
-    let func_msg: Message = extend_ai_funtion(function_pass, &msg_context);
+    images: &[ImageInput],
+) -> Result<String, LlmError> {
+    let provider: Arc<dyn LlmProvider> = resolve_provider(agent_position, agent_operation)?;
+    ai_task_request_with_images_and_provider(
+        provider.as_ref(),
+        msg_context,
+        agent_position,
+        agent_operation,
+        function_pass,
+        images,
+    )
+    .await
+}
+
+// Performs calls to LLM GPT with images attached and an explicit provider
+pub async fn ai_task_request_with_images_and_provider(
+    provider: &dyn LlmProvider,
+    msg_context: impl Into<PromptInput>,
+    agent_position: &str,
+    agent_operation: &str,
+    function_pass: for<'a> fn(&'a str) -> &'static str,
+    images: &[ImageInput],
+) -> Result<String, LlmError> {
+    //Extend Ai function, or render its prompt template
+    let (mut func_msg, prompt_version) =
+        prompt_message(function_pass, &msg_context.into(), agent_position);
+    // Chat APIs only accept images in user turns
+    if !images.is_empty() {
+        func_msg.role = "user".to_string();
+        func_msg
+            .content
+            .extend(images.iter().map(ImageInput::to_content));
+    }
     // Print current status
-    PrintCommand::AICall.print_agent_msg(agent_position, agent_operation);
+    announce_prompt(agent_position, agent_operation, &prompt_version);
 
-    // Get LLM response
-    let llm_response_result: Result<String, Box<dyn Error + Send + Sync + 'static>> =
-        call_gpt(vec![func_msg.clone()]).await;
+    let llm_reply: LlmReply = send_llm_messages(
+        provider,
+        &[func_msg],
+        &ChatOptions::default(),
+        agent_position,
+        agent_operation,
+        None,
+    )
+    .await?;
+    Ok(llm_reply.content)
+}
 
-    match llm_response_result {
-        Ok(response) => response,
-        Err(_) => call_gpt(vec![func_msg.clone()])
-            .await
-            .expect("Failed to call GPT"),
+// Performs calls to LLM GPT, replaying the agent's recent history and remembering the exchange;
+// the model may call the given tools before answering, and with live_output the reply is
+// printed to the terminal (streamed as it is generated when no tools are offered)
+pub async fn ai_task_request_with_memory(
+    msg_context: impl Into<PromptInput>,
+    agent_position: &str,
+    agent_operation: &str,
+    function_pass: for<'a> fn(&'a str) -> &'static str,
+    memory: &mut Vec<Message>,
+    tools: &ToolRegistry,
+    live_output: bool,
+) -> Result<String, LlmError> {
+    let provider: Arc<dyn LlmProvider> = resolve_provider(agent_position, agent_operation)?;
+    ai_task_request_with_memory_and_provider(
+        provider.as_ref(),
+        &MemoryConfig::from_env(),
+        memory,
+        msg_context,
+        agent_position,
+        agent_operation,
+        function_pass,
+        tools,
+        live_output,
+    )
+    .await
+}
+
+// Performs calls to LLM GPT with memory, an explicit provider and memory settings
+#[allow(clippy::too_many_arguments)]
+pub async fn ai_task_request_with_memory_and_provider(
+    provider: &dyn LlmProvider,
+    config: &MemoryConfig,
+    memory: &mut Vec<Message>,
+    msg_context: impl Into<PromptInput>,
+    agent_position: &str,
+    agent_operation: &str,
+    function_pass: for<'a> fn(&'a str) -> &'static str,
+    tools: &ToolRegistry,
+    live_output: bool,
+) -> Result<String, LlmError> {
+    //Extend Ai function, or render its prompt template
+    let (func_msg, prompt_version) =
+        prompt_message(function_pass, &msg_context.into(), agent_position);
+
+    // Compress older turns once history and prompt no longer fit the context size
+    let mut history: Vec<Message> = history_for_request(memory, config);
+    if !history.is_empty() && exceeds_context(&history, &func_msg, config) {
+        summarise_memory(provider, memory, agent_position).await?;
+        history = history_for_request(memory, config);
+    }
+
+    announce_prompt(agent_position, agent_operation, &prompt_version);
+
+    let mut messages: Vec<Message> = history;
+    messages.push(func_msg.clone());
+    let llm_response: String = if tools.is_empty() {
+        let mut print_delta = |delta: &str| print_live_output(delta);
+        send_llm_messages(
+            provider,
+            &messages,
+            &ChatOptions::default(),
+            agent_position,
+            agent_operation,
+            live_output.then_some(&mut print_delta as &mut OnDelta),
+        )
+        .await?
+        .content
+    } else {
+        // Tool traffic stays out of memory; only the final answer is remembered
+        let llm_reply: LlmReply = run_tool_loop(
+            provider,
+            &mut messages,
+            tools,
+            agent_position,
+            agent_operation,
+        )
+        .await?;
+        if live_output {
+            print_live_output(&llm_reply.content);
+        }
+        llm_reply.content
+    };
+    if live_output {
+        println!();
     }
+
+    memory.push(Message {
+        role: "user".to_string(),
+        ..func_msg
+    });
+    memory.push(text_message("assistant", &llm_response));
+    Ok(llm_response)
+}
+
+// Replace an agent's memory with a single summary message
+async fn summarise_memory(
+    provider: &dyn LlmProvider,
+    memory: &mut Vec<Message>,
+    agent_position: &str,
+) -> Result<(), LlmError> {
+    let operation: &str = get_function_string!(summarise_conversation);
+    let summary_msg: String = format!(
+        "Summarising {} messages of history to fit the context size",
+        memory.len()
+    );
+    PrintCommand::Issue.print_agent_msg(agent_position, &summary_msg);
+
+    let history: String = memory
+        .iter()
+        .map(|message| {
+            format!(
+                "{}: {}",
+                message.role,
+                messages_text(std::slice::from_ref(message))
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n\n");
+    let func_msg: Message = extend_ai_funtion(summarise_conversation, &history);
+    let summary: String = send_llm_messages(
+        provider,
+        &[func_msg],
+        &ChatOptions::default(),
+        agent_position,
+        operation,
+        None,
+    )
+    .await?
+    .content;
+
+    *memory = vec![text_message(
+        "system",
+        &format!("{} {}", SUMMARY_PREFIX, summary.trim()),
+    )];
+    Ok(())
+}
+
+// Performs a typed ai_function call: its input builds the prompt and the reply decodes into its output
+pub async fn ai_task_request_typed<F: AiFunction>(
+    input: &F::Input,
+    agent_position: &str,
+) -> Result<F::Output, LlmError> {
+    ai_task_request_decode::<F::Output>(
+        F::prompt_input(input),
+        agent_position,
+        F::name(),
+        F::PROMPT,
+    )
+    .await
 }
 
-// Performs calls to LLM GPT with a decode function
-pub async fn ai_task_request_decode<T: DeserializeOwned>(
-    msg_context: String,
+// Performs calls to LLM GPT through the active provider and decodes the reply
+pub async fn ai_task_request_decode<T: DeserializeOwned + JsonSchema>(
+    msg_context: impl Into<PromptInput>,
     agent_position: &str,
     agent_operation: &str,
     function_pass: for<'a> fn(&'a str) -> &'static str,
-) -> T {
-    let llm_response: String =
-        ai_task_request(msg_context, agent_position, agent_operation, function_pass).await;
+) -> Result<T, LlmError> {
+    let provider: Arc<dyn LlmProvider> = resolve_provider(agent_position, agent_operation)?;
+    ai_task_request_decode_with_provider::<T>(
+        provider.as_ref(),
+        msg_context,
+        agent_position,
+        agent_operation,
+        function_pass,
+    )
+    .await
+}
 
-    let decoded_response: T = serde_json::from_str(llm_response.as_str())
-        .expect("Failed to decode response from LLM with serde");
+// Performs calls to LLM GPT with a decode function and an explicit provider
+pub async fn ai_task_request_decode_with_provider<T: DeserializeOwned + JsonSchema>(
+    provider: &dyn LlmProvider,
+    msg_context: impl Into<PromptInput>,
+    agent_position: &str,
+    agent_operation: &str,
+    function_pass: for<'a> fn(&'a str) -> &'static str,
+) -> Result<T, LlmError> {
+    //Extend Ai function (or render its template) and ask for output matching the type's schema
+    let (func_msg, prompt_version) =
+        prompt_message(function_pass, &msg_context.into(), agent_position);
+    let response_format: ResponseFormat = json_schema_format::<T>();
+    let options: ChatOptions = ChatOptions {
+        response_format: Some(response_format.clone()),
+        ..ChatOptions::default()
+    };
+    announce_prompt(agent_position, agent_operation, &prompt_version);
+
+    let max_attempts: u32 = decode_attempts();
+    let mut messages: Vec<Message> = vec![func_msg];
+    let mut attempt: u32 = 1;
+    loop {
+        let llm_response: String = send_llm_messages(
+            provider,
+            &messages,
+            &options,
+            agent_position,
+            agent_operation,
+            None,
+        )
+        .await?
+        .content;
+
+        match decode_llm_json::<T>(&llm_response) {
+            Ok(decoded_response) => return Ok(decoded_response),
+            Err(error) if attempt < max_attempts => {
+                let repair_msg: String = format!(
+                    "Could not decode {} output ({}), asking for a repair",
+                    agent_operation, error
+                );
+                PrintCommand::Issue.print_agent_msg(agent_position, &repair_msg);
 
-    decoded_response
+                // Show the model its bad output and the serde error
+                messages.push(text_message("assistant", &llm_response));
+                messages.push(text_message(
+                    "user",
+                    &repair_instruction(&error.to_string(), &response_format),
+                ));
+                attempt += 1;
+            }
+            Err(error) => {
+                return Err(LlmError::Decode(format!(
+                    "{} output still invalid after {} attempt(s): {}",
+                    agent_operation, attempt, error
+                )));
+            }
+        }
+    }
 }
 
 // Check whether request url is valid
@@ -83,34 +640,75 @@ pub async fn check_status_code(client: &Client, url: &str) -> Result<u16, reqwes
 }
 
 // Get Code Template
-pub fn read_code_template_contents() -> String {
-    let path: String = String::from(CODE_TEMPLATE_RUST_PATH);
-    fs::read_to_string(path).expect("Failed to read code template")
+pub fn read_code_template_contents() -> std::io::Result<String> {
+    fs::read_to_string(CODE_TEMPLATE_RUST_PATH)
 }
 
-pub fn read_exec_main_contents() -> String {
-    let path: String = String::from(EXEC_MAIN_RUST_PATH);
-    fs::read_to_string(path).expect("Failed to read code template")
+pub fn read_exec_main_contents(project_dir: &Path) -> std::io::Result<String> {
+    fs::read_to_string(project_dir.join(EXEC_MAIN_RUST_PATH))
 }
 
 //Save New BackEnd Code
-pub fn save_rust_backend_code(contents: &String) {
-    let path: String = String::from(EXEC_MAIN_RUST_PATH);
-    fs::write(path, contents).expect("Failed to write main.rs")
+pub fn save_rust_backend_code(project_dir: &Path, contents: &str) -> std::io::Result<()> {
+    fs::write(project_dir.join(EXEC_MAIN_RUST_PATH), contents)
 }
 
-// Save Json Api Endpoint Schema
-pub fn save_api_endpoint(api_endpoints: &String) {
-    let path: String = String::from(API_SCHEMA_PATH);
-    fs::write(path, api_endpoints).expect("Failed to write API endpoints")
+// Get Spring Boot Code Template
+pub fn read_springboot_template_contents() -> std::io::Result<String> {
+    fs::read_to_string(CODE_TEMPLATE_SPRINGBOOT_PATH)
 }
 
+pub fn read_springboot_main_contents(project_dir: &Path) -> std::io::Result<String> {
+    fs::read_to_string(project_dir.join(EXEC_MAIN_SPRINGBOOT_PATH))
+}
 
+//Save New Spring Boot Code
+pub fn save_springboot_backend_code(project_dir: &Path, contents: &str) -> std::io::Result<()> {
+    fs::write(project_dir.join(EXEC_MAIN_SPRINGBOOT_PATH), contents)
+}
+
+// Copy a web template into another directory, leaving out its build output
+pub fn copy_project(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let path: PathBuf = entry?.path();
+        let Some(name) = path.file_name() else {
+            continue;
+        };
+        if name == "target" {
+            continue;
+        }
+        if path.is_dir() {
+            copy_project(&path, &to.join(name))?;
+        } else {
+            fs::copy(&path, to.join(name))?;
+        }
+    }
+    Ok(())
+}
+
+// Save Json Api Endpoint Schema
+pub fn save_api_endpoint(api_endpoints: &str) -> std::io::Result<()> {
+    fs::write(API_SCHEMA_PATH, api_endpoints)
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ai_functions::aifunc_architect::print_project_scope;
+    use crate::ai_functions::aifunc_backend::{print_backend_webserver_code, print_fixed_code};
     use crate::ai_functions::aifunc_managing::convert_user_input_to_goal;
+    use crate::apis::cassette_provider::CassetteProvider;
+    use crate::apis::llm_provider::OpenAiCompatibleProvider;
+    use crate::apis::response_cache::with_response_cache;
+    use crate::apis::scripted_provider::ScriptedProvider;
+    use crate::apis::stub_server::{StubResponse, StubServer};
+    use crate::apis::usage_tracker::{Budget, PriceTable, UsageLedger, with_usage_ledger};
+    use crate::helpers::prompt_registry::{PromptRegistry, with_prompt_registry};
+    use crate::helpers::tools::ReadProjectFile;
+    use crate::models::agent_basic::agent_memory::is_summary;
+    use crate::models::agents::agent_traits::ProjectScope;
+    use crate::models::general::llm::ToolDefinition;
 
     #[test]
     fn tests_extending_ai_function() {
@@ -122,13 +720,18 @@ mod tests {
     async fn test_ai_task_request() {
         let ai_func_param: String =
             "Build me a webserver for making stock price api requests".to_string();
-        let response: String = ai_task_request(
+        let cassette: Arc<dyn LlmProvider> = Arc::new(
+            CassetteProvider::from_env("ai_task_request").expect("Failed to load cassette"),
+        );
+        let response: String = ai_task_request_with_provider(
+            cassette.as_ref(),
             ai_func_param,
             "Managing Agent",
             "Define user requirements",
             convert_user_input_to_goal,
         )
-        .await;
+        .await
+        .expect("Failed to call GPT");
 
         assert!(!response.is_empty(), "Response should not be empty");
         assert!(
@@ -136,4 +739,310 @@ mod tests {
             "Response should be longer than 30 characters"
         );
     }
+
+    #[tokio::test]
+    async fn tests_decode_repairs_invalid_reply() {
+        let scripted: ScriptedProvider = ScriptedProvider::new()
+            .reply_next("Sure! Here is the scope: is_crud_required = true")
+            .reply_next(
+                r#"{"is_crud_required": true, "is_user_login_and_logout": false, "is_external_urls_required": false}"#,
+            );
+
+        let scope: ProjectScope = ai_task_request_decode_with_provider::<ProjectScope>(
+            &scripted,
+            "Build a todo app".to_string(),
+            "Solution Architect",
+            "print_project_scope",
+            print_project_scope,
+        )
+        .await
+        .expect("Repair turn should produce a valid scope");
+
+        assert!(scope.is_crud_required);
+
+        let calls: Vec<Vec<Message>> = scripted.calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[1].len(), 3);
+        assert_eq!(calls[1][1].role, "assistant");
+        assert!(calls[1][2].content[0].text.contains("could not be parsed"));
+
+        let options: Vec<ChatOptions> = scripted.call_options();
+        let format: &ResponseFormat = options[0]
+            .response_format
+            .as_ref()
+            .expect("Decode calls should send a response format");
+        assert_eq!(format.json_schema.name, "ProjectScope");
+    }
+
+    #[tokio::test]
+    async fn tests_decode_gives_up_after_attempts() {
+        let scripted: ScriptedProvider = ScriptedProvider::new()
+            .reply_next("no json")
+            .reply_next("still no json")
+            .reply_next("never json");
+
+        let error: LlmError = ai_task_request_decode_with_provider::<ProjectScope>(
+            &scripted,
+            "Build a todo app".to_string(),
+            "Solution Architect",
+            "print_project_scope",
+            print_project_scope,
+        )
+        .await
+        .expect_err("Undecodable replies should fail");
+
+        assert!(matches!(error, LlmError::Decode(_)));
+        assert_eq!(scripted.calls().len(), decode_attempts() as usize);
+    }
+
+    #[tokio::test]
+    async fn tests_memory_replays_previous_attempts() {
+        let scripted: ScriptedProvider = ScriptedProvider::new()
+            .reply_to_function("print_backend_webserver_code", "fn main() { broken }")
+            .reply_to_function("print_fixed_code", "fn main() {}");
+        let config: MemoryConfig = MemoryConfig::default();
+        let mut memory: Vec<Message> = vec![];
+
+        for (context, function_pass) in [
+            (
+                "PROJECT_DESCRIPTION: hello server",
+                print_backend_webserver_code as fn(&str) -> &'static str,
+            ),
+            ("ERROR_BUGS: cannot find value `broken`", print_fixed_code),
+        ] {
+            ai_task_request_with_memory_and_provider(
+                &scripted,
+                &config,
+                &mut memory,
+                context.to_string(),
+                "Backend Developer",
+                "write code",
+                function_pass,
+                &ToolRegistry::new(),
+                false,
+            )
+            .await
+            .expect("Memory call failed");
+        }
+
+        let calls: Vec<Vec<Message>> = scripted.calls();
+        assert_eq!(calls[0].len(), 1);
+        assert_eq!(calls[1].len(), 3);
+        assert_eq!(calls[1][1].content[0].text, "fn main() { broken }");
+        assert_eq!(memory.len(), 4);
+        assert_eq!(memory[3].role, "assistant");
+    }
+
+    #[tokio::test]
+    async fn tests_memory_summarises_when_context_is_full() {
+        let scripted: ScriptedProvider = ScriptedProvider::new()
+            .reply_to_function(
+                "summarise_conversation",
+                "First attempt failed on `broken`.",
+            )
+            .reply_to_function("print_fixed_code", "fn main() {}");
+        let config: MemoryConfig = MemoryConfig {
+            history_window: 6,
+            context_tokens: 50,
+        };
+        let mut memory: Vec<Message> = vec![
+            text_message("user", &"write the server ".repeat(40)),
+            text_message("assistant", "fn main() { broken }"),
+        ];
+
+        ai_task_request_with_memory_and_provider(
+            &scripted,
+            &config,
+            &mut memory,
+            "ERROR_BUGS: cannot find value `broken`".to_string(),
+            "Backend Developer",
+            "fix code",
+            print_fixed_code,
+            &ToolRegistry::new(),
+            false,
+        )
+        .await
+        .expect("Summarised call failed");
+
+        let calls: Vec<Vec<Message>> = scripted.calls();
+        assert_eq!(calls.len(), 2);
+        assert!(is_summary(&calls[1][0]));
+        assert!(is_summary(&memory[0]));
+        assert_eq!(memory.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn tests_tool_calls_run_until_final_answer() {
+        let scripted: ScriptedProvider = ScriptedProvider::new()
+            .call_tool_next("call_1", "read_project_file", r#"{"path": "Cargo.toml"}"#)
+            .reply_next("fn main() {}");
+        let tools: ToolRegistry =
+            ToolRegistry::new().with_tool(ReadProjectFile::new(WEBSERVER_RUST_PATH));
+        let mut memory: Vec<Message> = vec![];
+
+        let response: String = ai_task_request_with_memory_and_provider(
+            &scripted,
+            &MemoryConfig::default(),
+            &mut memory,
+            "ERROR_BUGS: unresolved import `actix_web`".to_string(),
+            "Backend Developer",
+            "print_fixed_code",
+            print_fixed_code,
+            &tools,
+            false,
+        )
+        .await
+        .expect("Tool loop failed");
+        assert_eq!(response, "fn main() {}");
+
+        let calls: Vec<Vec<Message>> = scripted.calls();
+        assert_eq!(calls.len(), 2);
+        let tool_request: &Message = &calls[1][1];
+        assert_eq!(tool_request.role, "assistant");
+        assert_eq!(
+            tool_request.tool_calls.as_ref().map(|calls| calls.len()),
+            Some(1)
+        );
+        let tool_result: &Message = &calls[1][2];
+        assert_eq!(tool_result.role, "tool");
+        assert_eq!(tool_result.tool_call_id.as_deref(), Some("call_1"));
+        assert!(tool_result.content[0].text.contains("[dependencies]"));
+
+        let options: Vec<ChatOptions> = scripted.call_options();
+        let offered: &Vec<ToolDefinition> =
+            options[0].tools.as_ref().expect("Tools should be offered");
+        assert_eq!(offered[0].function.name, "read_project_file");
+
+        // Only the prompt and the final answer are remembered
+        assert_eq!(memory.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn tests_live_output_assembles_streamed_code() {
+        let server: StubServer = StubServer::start(vec![StubResponse::sse(&[
+            r#"{"choices":[{"delta":{"content":"```rust\nfn main() "}}]}"#,
+            r#"{"choices":[{"delta":{"content":"{}\n```"}}]}"#,
+            "[DONE]",
+        ])])
+        .await;
+        let provider: OpenAiCompatibleProvider =
+            OpenAiCompatibleProvider::new(server.base_url(), None, "gpt-4.1".to_string());
+        let mut memory: Vec<Message> = vec![];
+
+        let response: String = ai_task_request_with_memory_and_provider(
+            &provider,
+            &MemoryConfig::default(),
+            &mut memory,
+            "PROJECT_DESCRIPTION: hello server".to_string(),
+            "Backend Developer",
+            "print_backend_webserver_code",
+            print_backend_webserver_code,
+            &ToolRegistry::new(),
+            true,
+        )
+        .await
+        .expect("Streamed call failed");
+
+        assert_eq!(response, "```rust\nfn main() {}\n```");
+        assert_eq!(memory[1].content[0].text, response);
+    }
+
+    #[tokio::test]
+    async fn tests_identical_requests_are_served_from_cache() {
+        let cache_dir: std::path::PathBuf =
+            std::env::temp_dir().join(format!("llm_cache_requests_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&cache_dir);
+        let cache: Arc<ResponseCache> = Arc::new(ResponseCache::new(
+            cache_dir.clone(),
+            Duration::from_secs(60),
+        ));
+        let scripted: ScriptedProvider = ScriptedProvider::new()
+            .reply_next("build a webserver that says hi")
+            .reply_next("build a webserver that says hello");
+
+        let mut goals: Vec<String> = vec![];
+        for _ in 0..2 {
+            let goal: String = with_response_cache(
+                Arc::clone(&cache),
+                ai_task_request_with_provider(
+                    &scripted,
+                    "say hi".to_string(),
+                    "Managing Agent",
+                    "convert_user_input_to_goal",
+                    convert_user_input_to_goal,
+                ),
+            )
+            .await
+            .expect("Cached call failed");
+            goals.push(goal);
+        }
+
+        assert_eq!(goals[0], goals[1]);
+        assert_eq!(scripted.calls().len(), 1);
+        assert_eq!(scripted.remaining_replies(), 1);
+
+        let _ = fs::remove_dir_all(&cache_dir);
+    }
+
+    #[tokio::test]
+    async fn tests_prompt_template_replaces_builtin_and_is_recorded() {
+        let registry: Arc<PromptRegistry> = Arc::new(
+            PromptRegistry::load(Path::new("prompts.example"))
+                .expect("Example prompts invalid"),
+        );
+        let ledger: Arc<UsageLedger> =
+            Arc::new(UsageLedger::new(Budget::default(), PriceTable::default()));
+        let scripted: ScriptedProvider = ScriptedProvider::new()
+            .reply_next("fn main() {}")
+            .reply_next(r#"{"is_crud_required": true, "is_user_login_and_logout": false, "is_external_urls_required": false}"#);
+
+        let input: PromptInput = PromptInput::new("BROKEN_CODE: fn main() { broken }")
+            .var("BROKEN_CODE", "fn main() { broken }")
+            .var("ERROR_BUGS", "expected `;`");
+        let calls = async {
+            ai_task_request_with_provider(
+                &scripted,
+                input,
+                "Backend Developer",
+                "print_fixed_code",
+                print_fixed_code,
+            )
+            .await
+            .expect("Templated call failed");
+            ai_task_request_decode_with_provider::<ProjectScope>(
+                &scripted,
+                "Build a todo app".to_string(),
+                "Solution Architect",
+                "print_project_scope",
+                print_project_scope,
+            )
+            .await
+            .expect("Builtin call failed");
+        };
+        with_usage_ledger(Arc::clone(&ledger), with_prompt_registry(registry, calls)).await;
+
+        let calls: Vec<Vec<Message>> = scripted.calls();
+        let templated: &str = &calls[0][0].content[0].text;
+        assert!(templated.starts_with("You are a senior Rust engineer"));
+        assert!(templated.contains("fn main() { broken }\n\nERROR_BUGS:\nexpected `;`"));
+        assert!(
+            calls[1][0].content[0]
+                .text
+                .contains("FUNCTION pub fn print_project_scope")
+        );
+
+        let versions: Vec<Option<String>> = ledger
+            .records()
+            .into_iter()
+            .map(|record| record.prompt_version)
+            .collect();
+        assert_eq!(
+            versions,
+            vec![
+                Some("print_fixed_code@v2".to_string()),
+                Some(BUILTIN_VERSION.to_string())
+            ]
+        );
+        assert_eq!(ai_function_name(print_fixed_code), "print_fixed_code");
+    }
 }
@@ -5,12 +5,22 @@ use tokio::time::sleep;
 
 pub struct HammerLoader {
     is_running: Arc<AtomicBool>,
+    animate: bool,
 }
 
 impl HammerLoader {
     pub fn new() -> Self {
         Self {
             is_running: Arc::new(AtomicBool::new(false)),
+            animate: true,
+        }
+    }
+
+    // Print the message once and leave the terminal to whatever the task writes
+    pub fn without_animation() -> Self {
+        Self {
+            is_running: Arc::new(AtomicBool::new(false)),
+            animate: false,
         }
     }
 
@@ -22,6 +32,11 @@ impl HammerLoader {
     where
         F: std::future::Future<Output = Result<T, E>>,
     {
+        if !self.animate {
+            println!("🔨 {}", message);
+            return future.await;
+        }
+
         let is_running = Arc::clone(&self.is_running);
         is_running.store(true, Ordering::Relaxed);
 
@@ -0,0 +1,169 @@
+use crate::models::general::llm::{Content, ImageUrl};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+// Providers reject larger images (OpenAI's limit is 20 MB per image)
+const MAX_IMAGE_BYTES: usize = 20 * 1024 * 1024;
+
+// Wireframe, diagram or sketch handed to the agents alongside the text request
+#[derive(Clone, PartialEq)]
+pub struct ImageInput {
+    pub source: String,
+    pub media_type: String,
+    // Base64 encoded image bytes
+    pub data: String,
+}
+
+// The base64 payload would swamp any prompt that formats a FactSheet with {:?}
+impl fmt::Debug for ImageInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ImageInput({}, {}, {} base64 chars)",
+            self.source,
+            self.media_type,
+            self.data.len()
+        )
+    }
+}
+
+fn media_type_for(path: &Path) -> Option<&'static str> {
+    let extension: String = path.extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+impl ImageInput {
+    // Read and base64-encode a local png, jpeg, gif or webp file
+    pub fn from_path(path: &Path) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let media_type: &str = media_type_for(path)
+            .ok_or_else(|| format!("{} is not a png, jpeg, gif or webp image", path.display()))?;
+        let bytes: Vec<u8> = fs::read(path)
+            .map_err(|e| format!("Failed to read image {}: {}", path.display(), e))?;
+        if bytes.len() > MAX_IMAGE_BYTES {
+            return Err(format!(
+                "Image {} is larger than {} MB",
+                path.display(),
+                MAX_IMAGE_BYTES / (1024 * 1024)
+            )
+            .into());
+        }
+
+        Ok(Self {
+            source: path.display().to_string(),
+            media_type: media_type.to_string(),
+            data: STANDARD.encode(bytes),
+        })
+    }
+
+    // "data:image/png;base64,...." as produced by browsers and screenshot tools
+    pub fn from_data_url(url: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let (header, data) = url
+            .strip_prefix("data:")
+            .and_then(|rest| rest.split_once(','))
+            .ok_or("Data URL must look like data:image/png;base64,...")?;
+        let media_type: &str = header
+            .strip_suffix(";base64")
+            .ok_or("Only base64 data URLs are supported")?;
+        if !media_type.starts_with("image/") {
+            return Err(format!("Data URL holds {}, not an image", media_type).into());
+        }
+
+        let data: String = data.trim().to_string();
+        let decoded_len: usize = STANDARD
+            .decode(&data)
+            .map_err(|e| format!("Data URL is not valid base64: {}", e))?
+            .len();
+        if decoded_len > MAX_IMAGE_BYTES {
+            return Err(format!(
+                "Data URL image is larger than {} MB",
+                MAX_IMAGE_BYTES / (1024 * 1024)
+            )
+            .into());
+        }
+
+        Ok(Self {
+            source: "data URL".to_string(),
+            media_type: media_type.to_string(),
+            data,
+        })
+    }
+
+    // A data URL or a path to an image file
+    pub fn parse(input: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let input: &str = input.trim();
+        if input.starts_with("data:") {
+            Self::from_data_url(input)
+        } else {
+            Self::from_path(Path::new(input))
+        }
+    }
+
+    pub fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.media_type, self.data)
+    }
+
+    // Image content part for a chat message
+    pub fn to_content(&self) -> Content {
+        Content {
+            r#type: "image_url".to_string(),
+            text: String::new(),
+            image_url: Some(ImageUrl {
+                url: self.data_url(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::path::PathBuf;
+
+    // Smallest valid PNG: a single transparent pixel
+    const PIXEL_PNG_BASE64: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+
+    #[test]
+    fn tests_reads_files_and_data_urls() {
+        let path: PathBuf = env::temp_dir().join(format!("wireframe_{}.png", std::process::id()));
+        fs::write(
+            &path,
+            STANDARD.decode(PIXEL_PNG_BASE64).expect("Bad fixture"),
+        )
+        .expect("Failed to write image");
+
+        let from_file: ImageInput =
+            ImageInput::parse(path.to_str().expect("Temp path not UTF-8")).expect("Image rejected");
+        assert_eq!(from_file.media_type, "image/png");
+        assert_eq!(from_file.data, PIXEL_PNG_BASE64);
+        let _ = fs::remove_file(&path);
+
+        let from_url: ImageInput =
+            ImageInput::parse(&from_file.data_url()).expect("Data URL rejected");
+        assert_eq!(from_url.data, from_file.data);
+
+        let content: Content = from_url.to_content();
+        assert_eq!(content.r#type, "image_url");
+        let json: String = serde_json::to_string(&content).expect("Content not serializable");
+        assert!(!json.contains("\"text\""));
+        assert!(!format!("{:?}", from_url).contains(PIXEL_PNG_BASE64));
+    }
+
+    #[test]
+    fn tests_rejects_non_images() {
+        assert!(ImageInput::parse("notes.txt").is_err());
+        assert!(ImageInput::parse("data:text/plain;base64,aGk=").is_err());
+        assert!(ImageInput::parse("data:image/png;base64,%%%").is_err());
+    }
+}
@@ -0,0 +1,367 @@
+use crate::ai_functions::aifunc_architect::{print_project_scope, print_site_urls};
+use crate::ai_functions::aifunc_backend::{print_backend_webserver_code, print_rest_api_endpoints};
+use crate::apis::llm_error::LlmError;
+use crate::apis::llm_provider::LlmProvider;
+use crate::helpers::extraction::extract_code;
+use crate::helpers::general::{
+    WEBSERVER_RUST_PATH, ai_function_name, ai_task_request_with_provider, copy_project,
+};
+use crate::helpers::prompt_registry::PromptInput;
+use crate::helpers::structured_output::decode_llm_json;
+use crate::helpers::tools::{CargoCheck, Tool};
+use crate::models::agents::agent_traits::{ProjectScope, RouteObject};
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const EVAL_FIXTURES_DIR: &str = "fixtures/prompt_eval";
+// Recorded replies the suite replays by default, under fixtures/cassettes
+pub const PROMPT_EVAL_CASSETTE: &str = "prompt_eval";
+const EVAL_POSITION: &str = "Prompt Eval";
+const ROUTE_METHODS: [&str; 5] = ["get", "post", "put", "patch", "delete"];
+
+// What an ai_function's output must look like to pass
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputCheck {
+    ProjectScope,
+    SiteUrls,
+    RustCode,
+    Routes,
+}
+
+impl OutputCheck {
+    // Validate a reply and normalise it for the golden comparison
+    pub fn check(&self, reply: &str) -> Result<String, String> {
+        match self {
+            OutputCheck::ProjectScope => {
+                let scope: ProjectScope =
+                    decode_llm_json(reply).map_err(|e| format!("not a project scope: {}", e))?;
+                if !(scope.is_crud_required
+                    || scope.is_user_login_and_logout
+                    || scope.is_external_urls_required)
+                {
+                    return Err("scope has every flag false".to_string());
+                }
+                pretty_json(&scope)
+            }
+            OutputCheck::SiteUrls => {
+                let urls: Vec<String> =
+                    decode_llm_json(reply).map_err(|e| format!("not a url list: {}", e))?;
+                if let Some(url) = urls
+                    .iter()
+                    .find(|url| !(url.starts_with("https://") || url.starts_with("http://")))
+                {
+                    return Err(format!("{} is not an http(s) url", url));
+                }
+                pretty_json(&urls)
+            }
+            OutputCheck::RustCode => {
+                let code: String = extract_code(reply, "rust");
+                syn::parse_file(&code).map_err(|e| format!("code does not parse: {}", e))?;
+                Ok(code)
+            }
+            OutputCheck::Routes => {
+                let routes: Vec<RouteObject> =
+                    decode_llm_json(reply).map_err(|e| format!("not a route list: {}", e))?;
+                if routes.is_empty() {
+                    return Err("no routes found".to_string());
+                }
+                for route in &routes {
+                    check_route(route)?;
+                }
+                pretty_json(&routes)
+            }
+        }
+    }
+}
+
+fn pretty_json<T: serde::Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(|e| e.to_string())
+}
+
+fn check_route(route: &RouteObject) -> Result<(), String> {
+    if !route.route.starts_with('/') {
+        return Err(format!("route {} does not start with /", route.route));
+    }
+    if !ROUTE_METHODS.contains(&route.method.to_lowercase().as_str()) {
+        return Err(format!("route {} has method {}", route.route, route.method));
+    }
+    let is_dynamic: bool = route.route.contains('{');
+    if route.is_route_dynamic != is_dynamic.to_string() {
+        return Err(format!(
+            "route {} has is_route_dynamic {:?}, expected \"{}\"",
+            route.route, route.is_route_dynamic, is_dynamic
+        ));
+    }
+    Ok(())
+}
+
+// One ai_function under evaluation; its cases live in fixtures/prompt_eval/<name>/
+#[derive(Debug, Clone, Copy)]
+pub struct EvalTarget {
+    pub function: fn(&str) -> &'static str,
+    pub check: OutputCheck,
+}
+
+impl EvalTarget {
+    pub fn name(&self) -> &'static str {
+        ai_function_name(self.function)
+    }
+}
+
+pub fn eval_targets() -> Vec<EvalTarget> {
+    vec![
+        EvalTarget {
+            function: print_project_scope,
+            check: OutputCheck::ProjectScope,
+        },
+        EvalTarget {
+            function: print_site_urls,
+            check: OutputCheck::SiteUrls,
+        },
+        EvalTarget {
+            function: print_backend_webserver_code,
+            check: OutputCheck::RustCode,
+        },
+        EvalTarget {
+            function: print_rest_api_endpoints,
+            check: OutputCheck::Routes,
+        },
+    ]
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaseOutcome {
+    Passed,
+    GoldenWritten,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseResult {
+    pub function: String,
+    pub case: String,
+    pub outcome: CaseOutcome,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EvalReport {
+    pub results: Vec<CaseResult>,
+}
+
+impl EvalReport {
+    pub fn failures(&self) -> Vec<&CaseResult> {
+        self.results
+            .iter()
+            .filter(|result| matches!(result.outcome, CaseOutcome::Failed(_)))
+            .collect()
+    }
+
+    pub fn print(&self) {
+        println!("\nPrompt evaluation:");
+        for result in &self.results {
+            match &result.outcome {
+                CaseOutcome::Passed => println!("  ok      {}/{}", result.function, result.case),
+                CaseOutcome::GoldenWritten => {
+                    println!("  written {}/{}", result.function, result.case)
+                }
+                CaseOutcome::Failed(reason) => {
+                    println!("  FAILED  {}/{}: {}", result.function, result.case, reason)
+                }
+            }
+        }
+        println!(
+            "  {} case(s), {} failed",
+            self.results.len(),
+            self.failures().len()
+        );
+    }
+}
+
+// Line diff of the golden output against a new one, "-" for golden lines and "+" for new ones
+pub fn line_diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+
+    // Longest common subsequence table, filled from the end
+    let mut common: Vec<Vec<usize>> = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff: Vec<String> = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            i += 1;
+            j += 1;
+        } else if i < expected.len() && (j == actual.len() || common[i + 1][j] >= common[i][j + 1])
+        {
+            diff.push(format!("- {}", expected[i]));
+            i += 1;
+        } else {
+            diff.push(format!("+ {}", actual[j]));
+            j += 1;
+        }
+    }
+    diff.join("\n")
+}
+
+// Opt-in `cargo check` of generated code in a scratch copy of the web template
+async fn compile_check(code: &str) -> Result<(), String> {
+    let scratch: PathBuf = env::temp_dir().join(format!("prompt_eval_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&scratch);
+    copy_project(Path::new(WEBSERVER_RUST_PATH), &scratch)
+        .map_err(|e| format!("cannot copy web template: {}", e))?;
+    fs::write(scratch.join("src/main.rs"), code)
+        .map_err(|e| format!("cannot write main.rs: {}", e))?;
+
+    let output: Result<String, String> =
+        CargoCheck::new(&scratch).call(serde_json::json!({})).await;
+    let _ = fs::remove_dir_all(&scratch);
+    match output? {
+        passed if passed == "cargo check passed" => Ok(()),
+        failed => Err(failed),
+    }
+}
+
+async fn run_case(
+    provider: &dyn LlmProvider,
+    target: &EvalTarget,
+    input_path: &Path,
+    update_goldens: bool,
+) -> Result<CaseOutcome, Box<dyn Error + Send + Sync>> {
+    let input: PromptInput = serde_json::from_str(&fs::read_to_string(input_path)?)
+        .map_err(|e| format!("bad eval input {}: {}", input_path.display(), e))?;
+
+    let reply: String = match ai_task_request_with_provider(
+        provider,
+        input,
+        EVAL_POSITION,
+        target.name(),
+        target.function,
+    )
+    .await
+    {
+        Ok(reply) => reply,
+        Err(LlmError::Config(reason)) => return Ok(CaseOutcome::Failed(reason)),
+        Err(e) => return Err(e.into()),
+    };
+
+    let output: String = match target.check.check(&reply) {
+        Ok(output) => output,
+        Err(reason) => return Ok(CaseOutcome::Failed(reason)),
+    };
+    if target.check == OutputCheck::RustCode
+        && env::var("PROMPT_EVAL_COMPILE").is_ok_and(|value| value.trim() == "1")
+        && let Err(reason) = compile_check(&output).await
+    {
+        return Ok(CaseOutcome::Failed(reason));
+    }
+
+    let golden_path: PathBuf = input_path.with_extension("golden");
+    if update_goldens {
+        fs::write(&golden_path, output + "\n")?;
+        return Ok(CaseOutcome::GoldenWritten);
+    }
+    let Ok(golden) = fs::read_to_string(&golden_path) else {
+        return Ok(CaseOutcome::Failed(format!(
+            "no golden output at {} (write it with PROMPT_EVAL_UPDATE=1)",
+            golden_path.display()
+        )));
+    };
+    if golden.trim_end() == output.trim_end() {
+        Ok(CaseOutcome::Passed)
+    } else {
+        Ok(CaseOutcome::Failed(format!(
+            "output differs from golden:\n{}",
+            line_diff(golden.trim_end(), output.trim_end())
+        )))
+    }
+}
+
+// Run every ai_function over its <case>.json inputs and compare with <case>.golden;
+// update_goldens rewrites the goldens from the current outputs instead
+pub async fn run_prompt_eval(
+    provider: &dyn LlmProvider,
+    fixtures_dir: &Path,
+    update_goldens: bool,
+) -> Result<EvalReport, Box<dyn Error + Send + Sync>> {
+    let mut report: EvalReport = EvalReport::default();
+    for target in eval_targets() {
+        let case_dir: PathBuf = fixtures_dir.join(target.name());
+        if !case_dir.is_dir() {
+            continue;
+        }
+
+        let mut inputs: Vec<PathBuf> = fs::read_dir(&case_dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "json")
+            })
+            .collect();
+        inputs.sort();
+
+        for input_path in inputs {
+            let outcome: CaseOutcome =
+                run_case(provider, &target, &input_path, update_goldens).await?;
+            report.results.push(CaseResult {
+                function: target.name().to_string(),
+                case: input_path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                outcome,
+            });
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::cassette_provider::CassetteProvider;
+
+    #[test]
+    fn tests_checks_reject_malformed_outputs() {
+        let routes: &str = r#"[{"route": "/item/{id}", "is_route_dynamic": "false", "method": "get", "request_body": "None", "response": "None"}]"#;
+        assert!(
+            OutputCheck::Routes
+                .check(routes)
+                .expect_err("Dynamic route flagged static")
+                .contains("is_route_dynamic")
+        );
+        assert!(OutputCheck::RustCode.check("fn main() {").is_err());
+        assert!(
+            OutputCheck::ProjectScope
+                .check(r#"{"is_crud_required": false, "is_user_login_and_logout": false, "is_external_urls_required": false}"#)
+                .is_err()
+        );
+
+        assert_eq!(line_diff("a\nb\nc", "a\nx\nc"), "- b\n+ x");
+    }
+
+    #[tokio::test]
+    async fn tests_prompt_eval_suite_matches_goldens() {
+        let cassette: CassetteProvider =
+            CassetteProvider::from_env(PROMPT_EVAL_CASSETTE).expect("Failed to load cassette");
+        let report: EvalReport = run_prompt_eval(&cassette, Path::new(EVAL_FIXTURES_DIR), false)
+            .await
+            .expect("Prompt eval failed to run");
+
+        report.print();
+        assert_eq!(report.results.len(), eval_targets().len());
+        assert!(report.failures().is_empty());
+    }
+}
@@ -0,0 +1,319 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+const DEFAULT_PROMPTS_DIR: &str = "prompts";
+const TEMPLATE_EXTENSIONS: [&str; 2] = ["md", "txt"];
+
+// Version label recorded when the compiled-in ai_function prompt is used
+pub const BUILTIN_VERSION: &str = "builtin";
+
+// Text handed to an ai_function: the free-form context plus named values for template placeholders
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PromptInput {
+    pub context: String,
+    #[serde(default)]
+    pub vars: BTreeMap<String, String>,
+}
+
+impl PromptInput {
+    pub fn new(context: impl Into<String>) -> Self {
+        Self {
+            context: context.into(),
+            vars: BTreeMap::new(),
+        }
+    }
+
+    // Value for a {NAME} placeholder
+    pub fn var(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.vars.insert(name.to_string(), value.into());
+        self
+    }
+}
+
+impl From<String> for PromptInput {
+    fn from(context: String) -> Self {
+        Self::new(context)
+    }
+}
+
+// One version of a prompt, e.g. prompts/print_fixed_code/v2.md
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptTemplate {
+    pub name: String,
+    pub version: String,
+    pub body: String,
+}
+
+// {UPPER_SNAKE} placeholders; lowercase braces such as Rust's {id} routes are left alone
+fn placeholders(body: &str) -> Vec<(usize, usize, &str)> {
+    let mut found: Vec<(usize, usize, &str)> = vec![];
+    let mut rest: usize = 0;
+    while let Some(open) = body[rest..].find('{').map(|offset| rest + offset) {
+        let Some(close) = body[open..].find('}').map(|offset| open + offset) else {
+            break;
+        };
+        let name: &str = &body[open + 1..close];
+        let is_placeholder: bool = name.starts_with(|c: char| c.is_ascii_uppercase())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+        if is_placeholder {
+            found.push((open, close + 1, name));
+            rest = close + 1;
+        } else {
+            rest = open + 1;
+        }
+    }
+    found
+}
+
+impl PromptTemplate {
+    // Fill every placeholder; {INPUT} is the whole context. Missing values are an error.
+    pub fn render(&self, input: &PromptInput) -> Result<String, String> {
+        let mut rendered: String = String::with_capacity(self.body.len() + input.context.len());
+        let mut missing: Vec<&str> = vec![];
+        let mut copied: usize = 0;
+
+        for (start, end, name) in placeholders(&self.body) {
+            let value: Option<&str> = match name {
+                "INPUT" => Some(input.context.as_str()),
+                _ => input.vars.get(name).map(String::as_str),
+            };
+            match value {
+                Some(value) => {
+                    rendered.push_str(&self.body[copied..start]);
+                    rendered.push_str(value);
+                    copied = end;
+                }
+                None => missing.push(name),
+            }
+        }
+        rendered.push_str(&self.body[copied..]);
+
+        if missing.is_empty() {
+            Ok(rendered)
+        } else {
+            Err(format!(
+                "prompt {}@{} has no value for {}",
+                self.name,
+                self.version,
+                missing.join(", ")
+            ))
+        }
+    }
+}
+
+// Orders v2 before v10 by comparing the digit runs numerically
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let numbers = |version: &str| -> Vec<u64> {
+        version
+            .split(|c: char| !c.is_ascii_digit())
+            .filter_map(|part| part.parse().ok())
+            .collect()
+    };
+    numbers(a).cmp(&numbers(b)).then_with(|| a.cmp(b))
+}
+
+// PROMPT_PRINT_FIXED_CODE_VERSION style variable pinning the version of one ai_function
+fn version_env_var(name: &str) -> String {
+    format!("PROMPT_{}_VERSION", name.to_ascii_uppercase())
+}
+
+// Versioned prompt templates read from <dir>/<ai_function>/<version>.md
+#[derive(Debug, Clone, Default)]
+pub struct PromptRegistry {
+    pub dir: Option<PathBuf>,
+    templates: BTreeMap<String, Vec<PromptTemplate>>,
+    pins: BTreeMap<String, String>,
+}
+
+impl PromptRegistry {
+    pub fn load(dir: &Path) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut templates: BTreeMap<String, Vec<PromptTemplate>> = BTreeMap::new();
+
+        for function_dir in fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read prompts dir {}: {}", dir.display(), e))?
+        {
+            let function_dir: PathBuf = function_dir?.path();
+            let Some(name) = function_dir.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if !function_dir.is_dir() {
+                continue;
+            }
+
+            let mut versions: Vec<PromptTemplate> = vec![];
+            for file in fs::read_dir(&function_dir)? {
+                let path: PathBuf = file?.path();
+                let is_template: bool = path
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    .is_some_and(|extension| TEMPLATE_EXTENSIONS.contains(&extension));
+                let Some(version) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                    continue;
+                };
+                if !is_template {
+                    continue;
+                }
+
+                let body: String = fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read prompt {}: {}", path.display(), e))?;
+                versions.push(PromptTemplate {
+                    name: name.to_string(),
+                    version: version.to_string(),
+                    body: body.trim_end().to_string(),
+                });
+            }
+
+            versions.sort_by(|a, b| compare_versions(&a.version, &b.version));
+            if !versions.is_empty() {
+                templates.insert(name.to_string(), versions);
+            }
+        }
+
+        let pins: Vec<(String, String)> = templates
+            .keys()
+            .filter_map(|name| {
+                env::var(version_env_var(name))
+                    .ok()
+                    .map(|version| (name.clone(), version.trim().to_string()))
+            })
+            .collect();
+
+        let registry: Self = Self {
+            dir: Some(dir.to_path_buf()),
+            templates,
+            pins: BTreeMap::new(),
+        };
+        Ok(pins.iter().fold(registry, |registry, (name, version)| {
+            registry.pin(name, version)
+        }))
+    }
+
+    // Directory from PROMPTS_DIR, else ./prompts when present, else compiled-in prompts only
+    pub fn from_env() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        match env::var("PROMPTS_DIR") {
+            Ok(dir) => Self::load(Path::new(&dir)),
+            Err(_) if Path::new(DEFAULT_PROMPTS_DIR).is_dir() => {
+                Self::load(Path::new(DEFAULT_PROMPTS_DIR))
+            }
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    // Use this version of an ai_function instead of the latest one
+    pub fn pin(mut self, name: &str, version: &str) -> Self {
+        self.pins.insert(name.to_string(), version.to_string());
+        self
+    }
+
+    // Pinned version, else the highest one; None falls back to the compiled-in prompt
+    pub fn select(&self, name: &str) -> Option<&PromptTemplate> {
+        let versions: &Vec<PromptTemplate> = self.templates.get(name)?;
+        match self.pins.get(name) {
+            Some(pinned) if pinned == BUILTIN_VERSION => None,
+            Some(pinned) => versions.iter().find(|template| &template.version == pinned),
+            None => versions.last(),
+        }
+    }
+
+    pub fn print_summary(&self) {
+        let Some(dir) = &self.dir else {
+            println!("Prompt templates: compiled-in only");
+            return;
+        };
+        println!("Prompt templates from {}:", dir.display());
+        for name in self.templates.keys() {
+            let version: &str = self
+                .select(name)
+                .map(|template| template.version.as_str())
+                .unwrap_or(BUILTIN_VERSION);
+            println!("  {}: {}", name, version);
+        }
+    }
+}
+
+static DEFAULT_PROMPTS: OnceLock<Arc<PromptRegistry>> = OnceLock::new();
+
+tokio::task_local! {
+    static SCOPED_PROMPTS: Arc<PromptRegistry>;
+}
+
+// Install the templates for the whole process (main does this at startup)
+pub fn set_prompt_registry(registry: Arc<PromptRegistry>) -> bool {
+    DEFAULT_PROMPTS.set(registry).is_ok()
+}
+
+// Run a future with prompts inside it taken from the given registry
+#[cfg(test)]
+pub async fn with_prompt_registry<F: Future>(
+    registry: Arc<PromptRegistry>,
+    future: F,
+) -> F::Output {
+    SCOPED_PROMPTS.scope(registry, future).await
+}
+
+// Registry in scope, else the process one; None means compiled-in prompts only
+pub fn active_prompts() -> Option<Arc<PromptRegistry>> {
+    SCOPED_PROMPTS
+        .try_with(Arc::clone)
+        .ok()
+        .or_else(|| DEFAULT_PROMPTS.get().cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_renders_placeholders_and_reports_missing() {
+        let template: PromptTemplate = PromptTemplate {
+            name: "print_backend_webserver_code".to_string(),
+            version: "v2".to_string(),
+            body: "Rewrite {CODE_TEMPLATE} for {PROJECT_DESCRIPTION}; keep route /items/{id}."
+                .to_string(),
+        };
+        let input: PromptInput = PromptInput::new("ignored")
+            .var("CODE_TEMPLATE", "fn main() {}")
+            .var("PROJECT_DESCRIPTION", "a todo api");
+
+        assert_eq!(
+            template.render(&input).expect("Render failed"),
+            "Rewrite fn main() {} for a todo api; keep route /items/{id}."
+        );
+        let error: String = template
+            .render(&PromptInput::new("only context"))
+            .expect_err("Missing vars should fail");
+        assert!(error.contains("CODE_TEMPLATE, PROJECT_DESCRIPTION"));
+    }
+
+    #[test]
+    fn tests_selects_latest_or_pinned_version() {
+        let registry: PromptRegistry =
+            PromptRegistry::load(Path::new("prompts.example")).expect("Example prompts invalid");
+
+        let latest: &PromptTemplate = registry
+            .select("print_fixed_code")
+            .expect("Example template missing");
+        assert_eq!(latest.version, "v2");
+        assert!(registry.select("print_project_scope").is_none());
+
+        let pinned: PromptRegistry = registry.pin("print_fixed_code", "v1");
+        assert_eq!(
+            pinned
+                .select("print_fixed_code")
+                .map(|template| template.version.as_str()),
+            Some("v1")
+        );
+        let builtin: PromptRegistry = pinned.pin("print_fixed_code", BUILTIN_VERSION);
+        assert!(builtin.select("print_fixed_code").is_none());
+
+        assert_eq!(compare_versions("v10", "v2"), Ordering::Greater);
+    }
+}
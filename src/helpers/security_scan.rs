@@ -0,0 +1,372 @@
+use crate::helpers::command_line::PrintCommand;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+#[derive(
+    Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Low => "LOW",
+            Severity::Medium => "MEDIUM",
+            Severity::High => "HIGH",
+        }
+    }
+}
+
+// One risky spot in the generated code; the LLM review fills severity, line and message only
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct SecurityFinding {
+    pub severity: Severity,
+    #[serde(default)]
+    pub rule: String,
+    #[serde(default)]
+    pub file: String,
+    #[serde(default)]
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+const PROCESS_PATTERNS: [&str; 4] = [
+    "std::process::Command",
+    "Command::new(",
+    "Runtime.getRuntime().exec",
+    "ProcessBuilder(",
+];
+
+const FS_WRITE_PATTERNS: [&str; 10] = [
+    "fs::write(",
+    "File::create(",
+    "OpenOptions::new()",
+    "fs::remove_file(",
+    "fs::remove_dir_all(",
+    "fs::create_dir_all(",
+    "Files.write(",
+    "Files.delete(",
+    "new FileOutputStream(",
+    "new FileWriter(",
+];
+
+const LISTENER_PATTERNS: [&str; 3] = [".bind(", "InetSocketAddress(", "server.address"];
+
+const SECRET_NAMES: [&str; 7] = [
+    "api_key",
+    "apikey",
+    "secret",
+    "token",
+    "password",
+    "passwd",
+    "private_key",
+];
+
+const SECRET_PREFIXES: [&str; 4] = ["sk-", "AKIA", "ghp_", "xoxb-"];
+
+const PASSWORD_HASHING: [&str; 8] = [
+    "hash_password",
+    "password_hash",
+    "hashed",
+    "bcrypt",
+    "argon2",
+    "scrypt",
+    "pbkdf2",
+    "passwordencoder",
+];
+
+const BUILD_SCRIPT_EXEC: [&str; 4] = [
+    "exec-maven-plugin",
+    "maven-antrun-plugin",
+    "commandLine",
+    "exec {",
+];
+
+// First "..." literal on the line
+fn first_literal(line: &str) -> Option<&str> {
+    let start: usize = line.find('"')? + 1;
+    let end: usize = line[start..].find('"')? + start;
+    Some(&line[start..end])
+}
+
+fn is_outside_project(path: &str) -> bool {
+    let is_windows_drive: bool =
+        path.len() > 1 && path.as_bytes()[1] == b':' && path.as_bytes()[0].is_ascii_alphabetic();
+    path.starts_with('/') || path.starts_with('~') || path.contains("..") || is_windows_drive
+}
+
+fn is_loopback(host: &str) -> bool {
+    let host: &str = host.trim_start_matches('[').trim_end_matches(']');
+    host.starts_with("127.") || host == "localhost" || host == "::1"
+}
+
+// Host of "host:port", "[::]:port" or "server.address=host"
+fn listener_host(line: &str) -> Option<String> {
+    if let Some(address) = line.trim().strip_prefix("server.address") {
+        return Some(
+            address
+                .trim_start_matches([' ', '=', ':'])
+                .trim()
+                .to_string(),
+        );
+    }
+    let address: &str = first_literal(line)?;
+    let host: &str = match address.rfind(':') {
+        Some(index) if address.starts_with('[') || address.matches(':').count() == 1 => {
+            &address[..index]
+        }
+        _ => address,
+    };
+    Some(host.to_string())
+}
+
+fn is_hardcoded_secret(line: &str) -> bool {
+    // Reading the value from the environment is the fix, not the problem
+    if line.contains("env::var") || line.contains("getenv") || line.contains("${") {
+        return false;
+    }
+    let Some(literal) = first_literal(line) else {
+        return false;
+    };
+    let name: String = line[..line.find('"').unwrap_or_default()].to_lowercase();
+    let has_secret_name: bool = SECRET_NAMES.iter().any(|secret| name.contains(secret))
+        && (name.contains('=') || name.contains(':'))
+        && literal.len() >= 8
+        && !literal.contains(' ');
+    let has_secret_prefix: bool = literal.len() >= 16
+        && SECRET_PREFIXES
+            .iter()
+            .any(|prefix| literal.starts_with(prefix));
+    has_secret_name || has_secret_prefix
+}
+
+// Rule based scan of one generated source or build file
+pub fn scan_source(file: &str, code: &str) -> Vec<SecurityFinding> {
+    let mut findings: Vec<SecurityFinding> = vec![];
+    let mut finding = |severity: Severity, rule: &str, line: usize, message: String| {
+        findings.push(SecurityFinding {
+            severity,
+            rule: rule.to_string(),
+            file: file.to_string(),
+            line: Some(line),
+            message,
+        });
+    };
+    let is_rust: bool = file.ends_with(".rs");
+    let is_build_script: bool = matches!(
+        file.rsplit('/').next().unwrap_or_default(),
+        "build.rs" | "build.gradle" | "build.gradle.kts" | "pom.xml"
+    );
+    if file.ends_with("build.rs") {
+        finding(
+            Severity::Medium,
+            "build_script",
+            1,
+            "build.rs runs with your permissions during cargo build".to_string(),
+        );
+    }
+
+    for (index, line) in code.lines().enumerate() {
+        let line_number: usize = index + 1;
+        let trimmed: &str = line.trim_start();
+        if trimmed.starts_with("//") || trimmed.starts_with('*') || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if PROCESS_PATTERNS
+            .iter()
+            .any(|pattern| line.contains(pattern))
+        {
+            finding(
+                Severity::High,
+                "process_spawn",
+                line_number,
+                "starts an external process".to_string(),
+            );
+        }
+        if is_build_script
+            && BUILD_SCRIPT_EXEC
+                .iter()
+                .any(|pattern| line.contains(pattern))
+        {
+            finding(
+                Severity::High,
+                "build_script_exec",
+                line_number,
+                "build script executes commands".to_string(),
+            );
+        }
+        if is_rust
+            && (line.contains("unsafe {")
+                || line.contains("unsafe fn")
+                || line.contains("unsafe impl"))
+        {
+            finding(
+                Severity::High,
+                "unsafe_code",
+                line_number,
+                "uses unsafe Rust".to_string(),
+            );
+        }
+        if FS_WRITE_PATTERNS
+            .iter()
+            .any(|pattern| line.contains(pattern))
+        {
+            match first_literal(line) {
+                Some(path) if is_outside_project(path) => finding(
+                    Severity::High,
+                    "write_outside_project",
+                    line_number,
+                    format!("writes to {} outside the project", path),
+                ),
+                Some(_) => {}
+                None => finding(
+                    Severity::Low,
+                    "write_dynamic_path",
+                    line_number,
+                    "writes to a path built at runtime".to_string(),
+                ),
+            }
+        }
+        if LISTENER_PATTERNS
+            .iter()
+            .any(|pattern| line.contains(pattern))
+            && let Some(host) = listener_host(line)
+            && !host.is_empty()
+            && !is_loopback(&host)
+        {
+            finding(
+                Severity::Medium,
+                "public_listener",
+                line_number,
+                format!("listens on non-loopback address {}", host),
+            );
+        }
+        if is_hardcoded_secret(line) {
+            finding(
+                Severity::High,
+                "hardcoded_secret",
+                line_number,
+                "secret value written into the code".to_string(),
+            );
+        }
+    }
+
+    // Passwords kept as they were sent, with no hashing anywhere in the file
+    let lower_code: String = code.to_lowercase();
+    let is_hashed: bool = PASSWORD_HASHING
+        .iter()
+        .any(|hashing| lower_code.contains(hashing));
+    if !is_hashed
+        && let Some(index) = code
+            .lines()
+            .position(|line| line.to_lowercase().contains("password"))
+    {
+        finding(
+            Severity::Medium,
+            "plaintext_password",
+            index + 1,
+            "stores passwords without hashing them".to_string(),
+        );
+    }
+    findings
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SecurityReport {
+    pub findings: Vec<SecurityFinding>,
+    // False when the LLM review could not run and only the rules were applied
+    pub llm_reviewed: bool,
+}
+
+impl SecurityReport {
+    pub fn highest_severity(&self) -> Option<Severity> {
+        self.findings.iter().map(|finding| finding.severity).max()
+    }
+
+    pub fn has_high(&self) -> bool {
+        self.highest_severity() == Some(Severity::High)
+    }
+
+    pub fn print(&self, agent_position: &str) {
+        if self.findings.is_empty() {
+            PrintCommand::UnitTest.print_agent_msg(agent_position, "Security review: no findings");
+        }
+        let mut findings: Vec<&SecurityFinding> = self.findings.iter().collect();
+        findings.sort_by_key(|finding| Reverse(finding.severity));
+        for finding in findings {
+            // LLM findings name a line but not always the file
+            let location: String = match (finding.file.is_empty(), finding.line) {
+                (false, Some(line)) => format!("{}:{}", finding.file, line),
+                (true, Some(line)) => format!("line {}", line),
+                _ => finding.file.clone(),
+            };
+            let finding_msg: String = format!(
+                "[{}] {} {}: {}",
+                finding.severity.label(),
+                location,
+                finding.rule,
+                finding.message
+            );
+            match finding.severity {
+                Severity::Low => {
+                    PrintCommand::UnitTest.print_agent_msg(agent_position, &finding_msg)
+                }
+                _ => PrintCommand::Issue.print_agent_msg(agent_position, &finding_msg),
+            }
+        }
+        if !self.llm_reviewed {
+            PrintCommand::Issue.print_agent_msg(
+                agent_position,
+                "LLM review unavailable, rule based scan only",
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_scan_flags_risky_generated_code() {
+        let code: &str = r#"use std::process::Command;
+struct User { username: String, password: String }
+const API_KEY: &str = "9f8e7d6c5b4a39281706";
+fn save(users: &str) {
+    fs::write("database.json", users).unwrap();
+    fs::write("/etc/passwd", users).unwrap();
+    let output = Command::new("sh").arg("-c").arg("ls").output();
+    let pointer = unsafe { *raw };
+}
+// Reads the key from the environment instead: let key = env::var("API_KEY");
+fn main() { HttpServer::new(app).bind("0.0.0.0:8080"); }
+"#;
+        let rules: Vec<(String, Option<usize>)> = scan_source("src/main.rs", code)
+            .into_iter()
+            .map(|finding| (finding.rule, finding.line))
+            .collect();
+        assert_eq!(
+            rules,
+            vec![
+                ("process_spawn".to_string(), Some(1)),
+                ("hardcoded_secret".to_string(), Some(3)),
+                ("write_outside_project".to_string(), Some(6)),
+                ("process_spawn".to_string(), Some(7)),
+                ("unsafe_code".to_string(), Some(8)),
+                ("public_listener".to_string(), Some(11)),
+                ("plaintext_password".to_string(), Some(2)),
+            ]
+        );
+
+        let template: &str = "let db = fs::write(\"database.json\", data);\nlet stored = hash_password(password);\nHttpServer::new(app).bind(\"127.0.0.1:8080\");";
+        assert!(scan_source("src/main.rs", template).is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+use crate::helpers::extraction::json_candidates;
+use crate::models::general::llm::{JsonSchemaFormat, ResponseFormat};
+
+use schemars::{JsonSchema, SchemaGenerator};
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::env;
+
+// Key used to wrap non-object outputs, since providers expect an object at the schema root
+const WRAPPED_ITEMS_KEY: &str = "items";
+const DEFAULT_DECODE_ATTEMPTS: u32 = 3;
+
+// JSON schema response format generated from the Rust type the reply decodes into
+pub fn json_schema_format<T: JsonSchema>() -> ResponseFormat {
+    let mut schema: Value = SchemaGenerator::default()
+        .into_root_schema_for::<T>()
+        .to_value();
+
+    if let Some(root) = schema.as_object_mut() {
+        root.remove("$schema");
+        root.remove("title");
+    }
+
+    if schema.get("type") != Some(&json!("object")) {
+        let definitions: Option<Value> =
+            schema.as_object_mut().and_then(|root| root.remove("$defs"));
+        let mut wrapper: Value = json!({
+            "type": "object",
+            "properties": { WRAPPED_ITEMS_KEY: schema },
+            "required": [WRAPPED_ITEMS_KEY],
+        });
+        if let Some(definitions) = definitions {
+            wrapper["$defs"] = definitions;
+        }
+        schema = wrapper;
+    }
+
+    // Provider schema names only allow [a-zA-Z0-9_-]
+    let name: String = T::schema_name()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    ResponseFormat {
+        r#type: "json_schema".to_string(),
+        json_schema: JsonSchemaFormat {
+            name,
+            schema,
+            // serde_json::Value fields (route bodies) cannot satisfy strict mode
+            strict: false,
+        },
+    }
+}
+
+// Decode a reply, unwrapping {"items": ...} when the schema had to wrap the type
+fn decode_json_value<T: DeserializeOwned>(json: &str) -> Result<T, serde_json::Error> {
+    match serde_json::from_str::<T>(json) {
+        Ok(decoded) => Ok(decoded),
+        Err(error) => {
+            let value: Value = serde_json::from_str(json).map_err(|_| error)?;
+            match value {
+                Value::Object(mut object)
+                    if object.len() == 1 && object.contains_key(WRAPPED_ITEMS_KEY) =>
+                {
+                    serde_json::from_value(object.remove(WRAPPED_ITEMS_KEY).unwrap_or_default())
+                }
+                other => serde_json::from_value(other),
+            }
+        }
+    }
+}
+
+// Decode the reply as is, else the first JSON value found in fences or prose that fits the type
+pub fn decode_llm_json<T: DeserializeOwned>(llm_response: &str) -> Result<T, serde_json::Error> {
+    let error: serde_json::Error = match decode_json_value::<T>(llm_response.trim()) {
+        Ok(decoded) => return Ok(decoded),
+        Err(error) => error,
+    };
+
+    json_candidates(llm_response)
+        .iter()
+        .find_map(|candidate| decode_json_value::<T>(candidate).ok())
+        .ok_or(error)
+}
+
+// Follow-up turn asking the model to fix output that failed to decode
+pub fn repair_instruction(decode_error: &str, response_format: &ResponseFormat) -> String {
+    format!(
+        "Your previous output could not be parsed as JSON for the required type. \
+        Parser error: {}. Print ONLY valid JSON matching this schema, with no commentary or code fences: {}",
+        decode_error, response_format.json_schema.schema
+    )
+}
+
+// Number of decode attempts (first answer plus repairs), from LLM_DECODE_ATTEMPTS
+pub fn decode_attempts() -> u32 {
+    env::var("LLM_DECODE_ATTEMPTS")
+        .ok()
+        .and_then(|value| value.trim().parse::<u32>().ok())
+        .map(|attempts| attempts.max(1))
+        .unwrap_or(DEFAULT_DECODE_ATTEMPTS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::agents::agent_traits::{ProjectScope, RouteObject};
+
+    #[test]
+    fn tests_object_schema_is_sent_as_is() {
+        let format: ResponseFormat = json_schema_format::<ProjectScope>();
+        let schema: &Value = &format.json_schema.schema;
+
+        assert_eq!(format.json_schema.name, "ProjectScope");
+        assert_eq!(schema["type"], "object");
+        assert!(schema["properties"]["is_crud_required"].is_object());
+    }
+
+    #[test]
+    fn tests_array_schema_is_wrapped_with_definitions() {
+        let format: ResponseFormat = json_schema_format::<Vec<RouteObject>>();
+        let schema: &Value = &format.json_schema.schema;
+
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["properties"]["items"]["type"], "array");
+        assert!(schema["$defs"]["RouteObject"].is_object());
+    }
+
+    #[test]
+    fn tests_decodes_plain_and_wrapped_replies() {
+        let plain: Vec<String> =
+            decode_llm_json(r#"["https://api.example.com"]"#).expect("Plain list failed");
+        let wrapped: Vec<String> = decode_llm_json(r#"{"items": ["https://api.example.com"]}"#)
+            .expect("Wrapped list failed");
+
+        assert_eq!(plain, wrapped);
+        assert!(decode_llm_json::<Vec<String>>("not json").is_err());
+    }
+
+    #[test]
+    fn tests_decodes_value_wrapped_in_prose() {
+        let reply: String =
+            std::fs::read_to_string("fixtures/replies/json_footnote_before_value.md")
+                .expect("Missing reply fixture");
+        let scope: ProjectScope = decode_llm_json(&reply).expect("Scope in prose failed");
+
+        assert!(scope.is_user_login_and_logout);
+        assert!(scope.is_external_urls_required);
+    }
+}
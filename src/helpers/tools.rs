@@ -0,0 +1,326 @@
+use crate::models::general::llm::{Content, FunctionDefinition, Message, ToolCall, ToolDefinition};
+
+use async_trait::async_trait;
+use reqwest::Client;
+use schemars::{JsonSchema, SchemaGenerator};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::process::Command;
+
+// Keep tool results small enough to send back without blowing the context
+const MAX_TOOL_OUTPUT_CHARS: usize = 20_000;
+
+// Rust function the model may call while answering
+#[async_trait]
+pub trait Tool: Send + Sync {
+    fn name(&self) -> &str;
+    fn description(&self) -> &str;
+    // JSON schema of the arguments object
+    fn parameters(&self) -> Value;
+    // Result text for the model; Err is reported back to it as a tool error
+    async fn call(&self, arguments: Value) -> Result<String, String>;
+}
+
+// Arguments schema generated from the Rust type the tool decodes them into
+pub fn tool_parameters<T: JsonSchema>() -> Value {
+    let mut schema: Value = SchemaGenerator::default()
+        .into_root_schema_for::<T>()
+        .to_value();
+    if let Some(root) = schema.as_object_mut() {
+        root.remove("$schema");
+        root.remove("title");
+    }
+    schema
+}
+
+fn decode_arguments<T: DeserializeOwned>(arguments: Value) -> Result<T, String> {
+    serde_json::from_value(arguments).map_err(|e| format!("invalid arguments: {}", e))
+}
+
+fn truncate_output(output: String) -> String {
+    if output.chars().count() <= MAX_TOOL_OUTPUT_CHARS {
+        return output;
+    }
+    let kept: String = output.chars().take(MAX_TOOL_OUTPUT_CHARS).collect();
+    format!("{}\n[output truncated]", kept)
+}
+
+// Tools an agent exposes for one conversation
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    tools: Vec<Arc<dyn Tool>>,
+}
+
+impl fmt::Debug for ToolRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.tools.iter().map(|tool| tool.name()))
+            .finish()
+    }
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_tool<T: Tool + 'static>(mut self, tool: T) -> Self {
+        self.tools.push(Arc::new(tool));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    pub fn definitions(&self) -> Vec<ToolDefinition> {
+        self.tools
+            .iter()
+            .map(|tool| ToolDefinition {
+                r#type: "function".to_string(),
+                function: FunctionDefinition {
+                    name: tool.name().to_string(),
+                    description: tool.description().to_string(),
+                    parameters: tool.parameters(),
+                },
+            })
+            .collect()
+    }
+
+    // Run the requested tool and wrap its result (or error) as a "tool" message
+    pub async fn dispatch(&self, call: &ToolCall) -> Message {
+        let result: Result<String, String> = match self
+            .tools
+            .iter()
+            .find(|tool| tool.name() == call.function.name)
+        {
+            None => Err(format!("unknown tool {}", call.function.name)),
+            Some(tool) => match serde_json::from_str::<Value>(&call.function.arguments) {
+                Ok(arguments) => tool.call(arguments).await,
+                Err(e) => Err(format!("arguments are not valid JSON: {}", e)),
+            },
+        };
+
+        let text: String = match result {
+            Ok(output) => truncate_output(output),
+            Err(error) => format!("ERROR: {}", error),
+        };
+        Message {
+            role: "tool".to_string(),
+            content: vec![Content {
+                r#type: "text".to_string(),
+                text,
+                image_url: None,
+            }],
+            tool_calls: None,
+            tool_call_id: Some(call.id.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ReadFileArgs {
+    // Path relative to the project root, e.g. "src/main.rs"
+    path: String,
+}
+
+// Reads files inside one project directory
+pub struct ReadProjectFile {
+    root: PathBuf,
+}
+
+impl ReadProjectFile {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+#[async_trait]
+impl Tool for ReadProjectFile {
+    fn name(&self) -> &str {
+        "read_project_file"
+    }
+
+    fn description(&self) -> &str {
+        "Read a file of the generated project, by path relative to the project root"
+    }
+
+    fn parameters(&self) -> Value {
+        tool_parameters::<ReadFileArgs>()
+    }
+
+    async fn call(&self, arguments: Value) -> Result<String, String> {
+        let args: ReadFileArgs = decode_arguments(arguments)?;
+        let root: PathBuf = self
+            .root
+            .canonicalize()
+            .map_err(|e| format!("project root unavailable: {}", e))?;
+        let path: PathBuf = root
+            .join(Path::new(&args.path))
+            .canonicalize()
+            .map_err(|e| format!("cannot open {}: {}", args.path, e))?;
+
+        // Refuse ../ escapes and symlinks pointing out of the project
+        if !path.starts_with(&root) {
+            return Err(format!("{} is outside the project", args.path));
+        }
+        fs::read_to_string(&path).map_err(|e| format!("cannot read {}: {}", args.path, e))
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct NoArgs {}
+
+// Runs `cargo check` in the generated project and reports the compiler output
+pub struct CargoCheck {
+    project_dir: PathBuf,
+}
+
+impl CargoCheck {
+    pub fn new(project_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            project_dir: project_dir.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for CargoCheck {
+    fn name(&self) -> &str {
+        "cargo_check"
+    }
+
+    fn description(&self) -> &str {
+        "Run cargo check on the generated project and return the compiler errors, if any"
+    }
+
+    fn parameters(&self) -> Value {
+        tool_parameters::<NoArgs>()
+    }
+
+    async fn call(&self, arguments: Value) -> Result<String, String> {
+        let _: NoArgs = decode_arguments(arguments)?;
+        let output = Command::new("cargo")
+            .args(["check", "--quiet", "--message-format", "short"])
+            .current_dir(&self.project_dir)
+            .output()
+            .await
+            .map_err(|e| format!("failed to run cargo check: {}", e))?;
+
+        if output.status.success() {
+            Ok("cargo check passed".to_string())
+        } else {
+            Ok(format!(
+                "cargo check failed:\n{}",
+                String::from_utf8_lossy(&output.stderr)
+            ))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct FetchUrlArgs {
+    // Absolute http(s) URL
+    url: String,
+}
+
+// GETs a URL so the model can inspect an external API's response
+pub struct FetchUrl {
+    client: Client,
+}
+
+impl FetchUrl {
+    pub fn new() -> Self {
+        let client: Client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("Failed to build HTTP client");
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl Tool for FetchUrl {
+    fn name(&self) -> &str {
+        "fetch_url"
+    }
+
+    fn description(&self) -> &str {
+        "HTTP GET a URL and return the status code and response body"
+    }
+
+    fn parameters(&self) -> Value {
+        tool_parameters::<FetchUrlArgs>()
+    }
+
+    async fn call(&self, arguments: Value) -> Result<String, String> {
+        let args: FetchUrlArgs = decode_arguments(arguments)?;
+        if !args.url.starts_with("http://") && !args.url.starts_with("https://") {
+            return Err(format!("{} is not an http(s) URL", args.url));
+        }
+
+        let response = self
+            .client
+            .get(&args.url)
+            .send()
+            .await
+            .map_err(|e| format!("request failed: {}", e))?;
+        let status: u16 = response.status().as_u16();
+        let body: String = response
+            .text()
+            .await
+            .map_err(|e| format!("reading body failed: {}", e))?;
+        Ok(format!("status: {}\n{}", status, body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::general::llm::ToolCallFunction;
+
+    fn tool_call(name: &str, arguments: &str) -> ToolCall {
+        ToolCall {
+            id: "call_1".to_string(),
+            r#type: "function".to_string(),
+            function: ToolCallFunction {
+                name: name.to_string(),
+                arguments: arguments.to_string(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn tests_dispatch_reads_files_inside_project_only() {
+        let tools: ToolRegistry =
+            ToolRegistry::new().with_tool(ReadProjectFile::new("web_template_rust"));
+
+        let definitions: Vec<ToolDefinition> = tools.definitions();
+        assert_eq!(definitions[0].function.name, "read_project_file");
+        assert_eq!(definitions[0].function.parameters["required"][0], "path");
+
+        let read: Message = tools
+            .dispatch(&tool_call("read_project_file", r#"{"path": "Cargo.toml"}"#))
+            .await;
+        assert_eq!(read.role, "tool");
+        assert_eq!(read.tool_call_id.as_deref(), Some("call_1"));
+        assert!(read.content[0].text.contains("[dependencies]"));
+
+        let escape: Message = tools
+            .dispatch(&tool_call(
+                "read_project_file",
+                r#"{"path": "../Cargo.toml"}"#,
+            ))
+            .await;
+        assert!(escape.content[0].text.starts_with("ERROR:"));
+
+        let unknown: Message = tools.dispatch(&tool_call("rm_rf", "{}")).await;
+        assert!(unknown.content[0].text.contains("unknown tool"));
+    }
+}
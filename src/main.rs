@@ -9,19 +9,160 @@ mod apis;
 mod helpers;
 mod models;
 
-use helpers::command_line::{display_andreanator_logo, get_user_response, languages_options};
-use models::agents_manager::managing_agent::ManagingAgent;
+use apis::cassette_provider::CassetteProvider;
+use apis::llm_config::{LlmConfig, active_config, set_llm_config};
+use apis::llm_provider::{LlmProvider, active_provider, provider_from_name, set_default_provider};
+use apis::rate_limiter::active_limiter;
+use apis::response_cache::{ResponseCache, active_cache, set_default_cache};
+use dotenv::dotenv;
+use helpers::command_line::{
+    display_andreanator_logo, get_user_images, get_user_response, languages_options,
+    set_block_high_risk, set_stream_output,
+};
+use helpers::general::save_api_endpoint;
+use helpers::image_input::ImageInput;
+use helpers::prompt_eval::{EVAL_FIXTURES_DIR, EvalReport, PROMPT_EVAL_CASSETTE, run_prompt_eval};
+use helpers::prompt_registry::{PromptRegistry, set_prompt_registry};
+use models::agents::agent_traits::BackendLanguage;
+use models::agents_manager::agent_scheduler::FailurePolicy;
+use models::agents_manager::managing_agent::{ManagingAgent, project_positions};
+use models::agents_manager::project_report::ProjectReport;
+use std::path::Path;
+use std::sync::Arc;
 
 #[tokio::main]
 async fn main() {
+    // Keys and LLM_* settings kept in .env must be visible before anything reads the environment
+    dotenv().ok();
+
+    // Per-agent and per-ai_function model settings (llm_config.toml or LLM_CONFIG, plus env)
+    let llm_config: LlmConfig = LlmConfig::load().expect("Failed to load LLM config");
+
+    // Optional "--provider <openrouter|openai|anthropic|local>" overrides the config default and LLM_PROVIDER
+    let args: Vec<String> = std::env::args().collect();
+    let provider_name: Option<String> = match args.iter().position(|arg| arg == "--provider") {
+        Some(index) => Some(
+            args.get(index + 1)
+                .expect("Missing value for --provider")
+                .clone(),
+        ),
+        None => llm_config.default_settings().provider,
+    };
+    if let Some(provider_name) = provider_name {
+        let provider: Arc<dyn LlmProvider> =
+            provider_from_name(&provider_name).expect("Failed to configure LLM provider");
+        set_default_provider(provider);
+    }
+
+    // Optional "--block-high-risk" refuses to run code with high severity security findings
+    if args.iter().any(|arg| arg == "--block-high-risk") {
+        set_block_high_risk(true);
+    }
+
+    // Optional "--stream" prints backend code live while it is generated
+    if args.iter().any(|arg| arg == "--stream") {
+        set_stream_output(true);
+    }
+
+    // Replies are cached on disk only with "--cache" or LLM_CACHE=1, so runs never reuse stale code by default;
+    // "--no-cache" wins over both for a single run
+    let cache_enabled: bool = !args.iter().any(|arg| arg == "--no-cache")
+        && (args.iter().any(|arg| arg == "--cache")
+            || std::env::var("LLM_CACHE").is_ok_and(|value| matches!(value.trim(), "1" | "true")));
+    if cache_enabled {
+        set_default_cache(Arc::new(ResponseCache::from_env()));
+    }
+
+    // "--eval-prompts" replays the committed prompt eval cassette unless "--live" asks for real calls
+    let eval_prompts: bool = args.iter().any(|arg| arg == "--eval-prompts");
+    let eval_live: bool = args.iter().any(|arg| arg == "--live");
+
     display_andreanator_logo();
+    let provider: Arc<dyn LlmProvider> = if eval_prompts && !eval_live {
+        Arc::new(
+            CassetteProvider::from_env(PROMPT_EVAL_CASSETTE)
+                .expect("Failed to load prompt eval cassette"),
+        )
+    } else {
+        active_provider().expect("Failed to configure LLM provider")
+    };
+    println!("Using LLM provider: {} ({})", provider.name(), provider.model());
+    match active_cache() {
+        Some(cache) => println!(
+            "Response cache: {} (ttl {}s)",
+            cache.dir().display(),
+            cache.ttl().as_secs()
+        ),
+        None => println!("Response cache: off"),
+    }
+    println!("Rate limits: {}", active_limiter().limits().describe());
+    set_llm_config(llm_config);
+
+    // Versioned prompt templates from ./prompts or PROMPTS_DIR override the compiled-in ones
+    let prompts: PromptRegistry = PromptRegistry::from_env().expect("Failed to load prompt templates");
+    prompts.print_summary();
+    set_prompt_registry(Arc::new(prompts));
+
+    // "--eval-prompts" runs the ai_function golden-file suite against the provider and exits
+    if eval_prompts {
+        active_config().print_summary(&[]);
+        let update_goldens: bool =
+            std::env::var("PROMPT_EVAL_UPDATE").is_ok_and(|value| value.trim() == "1");
+        let report: EvalReport =
+            run_prompt_eval(provider.as_ref(), Path::new(EVAL_FIXTURES_DIR), update_goldens)
+                .await
+                .expect("Failed to run prompt evaluation");
+        report.print();
+        std::process::exit(if report.failures().is_empty() { 0 } else { 1 });
+    }
+
     let usr_req: String =
         get_user_response("Sup Dawg what webserver you want to ask Andreanator to build ?");
-    let lang_opt: String = languages_options();
-    let mut manage_agent: ManagingAgent = ManagingAgent::new(usr_req)
+    // Optional "--image <path|data URL>" (repeatable) attaches wireframes without prompting
+    let image_args: Vec<&String> = args
+        .iter()
+        .zip(args.iter().skip(1))
+        .filter(|(flag, _)| *flag == "--image")
+        .map(|(_, value)| value)
+        .collect();
+    let reference_images: Vec<ImageInput> = if image_args.is_empty() {
+        get_user_images("Attach a wireframe or diagram? Image paths or data URLs (Enter to skip)")
+    } else {
+        image_args
+            .into_iter()
+            .map(|value| ImageInput::parse(value).expect("Failed to load --image"))
+            .collect()
+    };
+    // Optional "--language <rust|java>" picks the backend without the menu
+    let language: BackendLanguage = match args.iter().position(|arg| arg == "--language") {
+        Some(index) => args
+            .get(index + 1)
+            .and_then(|value| BackendLanguage::parse(value))
+            .expect("--language must be rust or java"),
+        None => languages_options(),
+    };
+    // Settings for the agents this project will actually run
+    active_config().print_summary(&project_positions(language));
+    let mut manage_agent: ManagingAgent = ManagingAgent::new(usr_req, reference_images, language)
         .await
         .expect("Failed to create managing agent");
+    // Optional "--on-failure <abort|skip|retry|retry:N>" overrides AGENT_FAILURE_POLICY
+    if let Some(index) = args.iter().position(|arg| arg == "--on-failure") {
+        let failure_policy: FailurePolicy = args
+            .get(index + 1)
+            .and_then(|value| FailurePolicy::parse(value))
+            .expect("--on-failure must be abort, skip, retry or retry:<times>");
+        manage_agent.set_failure_policy(failure_policy);
+    }
 
-    manage_agent.execute_project().await;
+    let report: ProjectReport = manage_agent.execute_project().await;
+    if let Some(api_endpoints) = manage_agent.api_endpoint_schema() {
+        let schema: String =
+            serde_json::to_string_pretty(api_endpoints).expect("Failed to encode API endpoints");
+        save_api_endpoint(&schema).expect("Failed to write API endpoints");
+    }
+    if !report.succeeded() {
+        std::process::exit(1);
+    }
 
 }
@@ -0,0 +1,104 @@
+use crate::apis::usage_tracker::estimate_tokens;
+use crate::helpers::general::messages_text;
+use crate::models::general::llm::Message;
+
+use std::env;
+
+// Marks the message standing in for summarised older history
+pub const SUMMARY_PREFIX: &str = "CONVERSATION SUMMARY SO FAR:";
+
+// How much of an agent's memory is replayed on follow-up calls
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryConfig {
+    // Most recent messages sent along (a prompt and its reply are two messages)
+    pub history_window: usize,
+    // Estimated tokens allowed for history plus the new prompt before summarising
+    pub context_tokens: u64,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            history_window: 6,
+            context_tokens: 32_000,
+        }
+    }
+}
+
+impl MemoryConfig {
+    // Defaults overridden by AGENT_HISTORY_WINDOW and AGENT_CONTEXT_TOKENS
+    pub fn from_env() -> Self {
+        let defaults: MemoryConfig = MemoryConfig::default();
+        let read = |name: &str| {
+            env::var(name)
+                .ok()
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+
+        Self {
+            history_window: read("AGENT_HISTORY_WINDOW")
+                .map(|window| window as usize)
+                .unwrap_or(defaults.history_window),
+            context_tokens: read("AGENT_CONTEXT_TOKENS")
+                .map(|tokens| tokens.max(1))
+                .unwrap_or(defaults.context_tokens),
+        }
+    }
+}
+
+pub fn is_summary(message: &Message) -> bool {
+    message
+        .content
+        .first()
+        .is_some_and(|content| content.text.starts_with(SUMMARY_PREFIX))
+}
+
+// Summary of older turns (if any) followed by the most recent messages inside the window
+pub fn history_for_request(memory: &[Message], config: &MemoryConfig) -> Vec<Message> {
+    let (summary, turns): (&[Message], &[Message]) = match memory.first() {
+        Some(first) if is_summary(first) => (&memory[..1], &memory[1..]),
+        _ => (&[], memory),
+    };
+    let recent: &[Message] = &turns[turns.len().saturating_sub(config.history_window)..];
+
+    summary.iter().chain(recent.iter()).cloned().collect()
+}
+
+// Whether sending this history with the new prompt would overflow the context size
+pub fn exceeds_context(history: &[Message], prompt: &Message, config: &MemoryConfig) -> bool {
+    let prompt_tokens: u64 = estimate_tokens(&messages_text(std::slice::from_ref(prompt)));
+    estimate_tokens(&messages_text(history)) + prompt_tokens > config.context_tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::general::text_message;
+
+    #[test]
+    fn tests_history_keeps_summary_and_window() {
+        let config: MemoryConfig = MemoryConfig {
+            history_window: 2,
+            context_tokens: 10,
+        };
+        let memory: Vec<Message> = vec![
+            text_message(
+                "system",
+                &format!("{} wrote a first server", SUMMARY_PREFIX),
+            ),
+            text_message("user", "write code"),
+            text_message("assistant", "fn main() {}"),
+            text_message("user", "fix: missing route"),
+            text_message("assistant", "fn main() { route() }"),
+        ];
+
+        let history: Vec<Message> = history_for_request(&memory, &config);
+        assert_eq!(history.len(), 3);
+        assert!(is_summary(&history[0]));
+        assert_eq!(history[1].content[0].text, "fix: missing route");
+
+        let prompt: Message = text_message("user", "fix: another error in the handler");
+        assert!(exceeds_context(&history, &prompt, &config));
+        assert!(!exceeds_context(&[], &text_message("user", "hi"), &config));
+    }
+}
@@ -1,9 +1,7 @@
-use serde::de;
-
 use crate::models::agent_basic::basic_traits::BasicTraits;
 use crate::models::general::llm::Message;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AgentState {
     Discovery,
     Working,
@@ -1,3 +1,11 @@
 pub mod agent_architect;
 pub mod agent_backend;
+pub mod agent_data_model;
+pub mod agent_docs;
+pub mod agent_frontend;
+pub mod agent_qa;
+pub mod agent_security;
+pub mod agent_springboot;
 pub mod agent_traits;
+#[cfg(test)]
+pub mod test_support;
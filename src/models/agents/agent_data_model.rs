@@ -0,0 +1,398 @@
+use crate::ai_functions::aifunc_data_model::{PrintDataModel, PrintFixedDataModel};
+use crate::apis::llm_error::LlmError;
+use crate::apis::usage_tracker::run_budget_exceeded;
+use crate::helpers::command_line::PrintCommand;
+use crate::helpers::general::ai_task_request_typed;
+use crate::models::agent_basic::basic_agent::{AgentState, BasicAgent};
+use crate::models::agents::agent_traits::{
+    DataModel, DataModelFix, DataModelInput, FactField, FactSheet, SpecialFunctions,
+};
+
+use async_trait::async_trait;
+use std::collections::{HashMap, HashSet};
+
+const FIELD_TYPES: [&str; 5] = ["string", "integer", "number", "bool", "datetime"];
+const RELATIONSHIP_KINDS: [&str; 3] = ["one_to_one", "one_to_many", "many_to_many"];
+
+// Names compared across languages: "due_date", "dueDate" and "DueDate" are the same field
+fn normalise_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn is_field_type(field_type: &str) -> bool {
+    let field_type: String = field_type.trim().to_lowercase();
+    match field_type
+        .strip_prefix("list<")
+        .and_then(|inner| inner.strip_suffix('>'))
+    {
+        Some(inner) => is_field_type(inner),
+        None => FIELD_TYPES.contains(&field_type.as_str()),
+    }
+}
+
+// Problems in the model itself, before any code is written against it
+pub fn design_issues(data_model: &DataModel, is_crud_required: bool) -> Vec<String> {
+    let mut issues: Vec<String> = vec![];
+    if data_model.entities.is_empty() {
+        issues.push("the model has no entities".to_string());
+    }
+
+    let mut entity_names: HashSet<String> = HashSet::new();
+    for entity in &data_model.entities {
+        if !entity_names.insert(normalise_name(&entity.name)) {
+            issues.push(format!("entity {} is defined twice", entity.name));
+        }
+    }
+
+    for entity in &data_model.entities {
+        if entity.fields.is_empty() {
+            issues.push(format!("entity {} has no fields", entity.name));
+        }
+        let mut field_names: HashSet<String> = HashSet::new();
+        for field in &entity.fields {
+            if !field_names.insert(normalise_name(&field.name)) {
+                issues.push(format!("{}.{} is defined twice", entity.name, field.name));
+            }
+            if !is_field_type(&field.field_type) {
+                issues.push(format!(
+                    "{}.{} has unknown type {}",
+                    entity.name, field.name, field.field_type
+                ));
+            }
+        }
+        if is_crud_required && !field_names.contains("id") {
+            issues.push(format!("entity {} has no id field", entity.name));
+        }
+
+        for relationship in &entity.relationships {
+            if !RELATIONSHIP_KINDS.contains(&relationship.kind.as_str()) {
+                issues.push(format!(
+                    "{} has unknown relationship kind {}",
+                    entity.name, relationship.kind
+                ));
+            }
+            if !entity_names.contains(&normalise_name(&relationship.target)) {
+                issues.push(format!(
+                    "{} relates to unknown entity {}",
+                    entity.name, relationship.target
+                ));
+            }
+            if !field_names.contains(&normalise_name(&relationship.via_field)) {
+                issues.push(format!(
+                    "{} relationship to {} uses missing field {}",
+                    entity.name, relationship.target, relationship.via_field
+                ));
+            }
+        }
+    }
+    issues
+}
+
+// Entities and fields of the model that no struct in the Rust code declares
+pub fn rust_struct_mismatches(data_model: &DataModel, code: &str) -> Vec<String> {
+    // Code that does not parse is left to the compiler
+    let Ok(file) = syn::parse_file(code) else {
+        return vec![];
+    };
+    let structs: HashMap<String, HashSet<String>> = file
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            syn::Item::Struct(item_struct) => Some(item_struct),
+            _ => None,
+        })
+        .map(|item_struct| {
+            let fields: HashSet<String> = item_struct
+                .fields
+                .iter()
+                .filter_map(|field| field.ident.as_ref())
+                .map(|ident| normalise_name(&ident.to_string()))
+                .collect();
+            (normalise_name(&item_struct.ident.to_string()), fields)
+        })
+        .collect();
+
+    let mut mismatches: Vec<String> = vec![];
+    for entity in &data_model.entities {
+        let Some(fields) = structs.get(&normalise_name(&entity.name)) else {
+            mismatches.push(format!("struct {} is missing", entity.name));
+            continue;
+        };
+        for field in &entity.fields {
+            if !fields.contains(&normalise_name(&field.name)) {
+                mismatches.push(format!(
+                    "struct {} is missing field {}",
+                    entity.name, field.name
+                ));
+            }
+        }
+    }
+    mismatches
+}
+
+// Entities and fields of the model that no class in the Java code declares
+pub fn java_class_mismatches(data_model: &DataModel, code: &str) -> Vec<String> {
+    let words: Vec<&str> = code
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .collect();
+    let classes: HashSet<String> = words
+        .windows(2)
+        .filter(|pair| matches!(pair[0], "class" | "record"))
+        .map(|pair| normalise_name(pair[1]))
+        .collect();
+    let identifiers: HashSet<String> = words.iter().map(|word| normalise_name(word)).collect();
+
+    let mut mismatches: Vec<String> = vec![];
+    for entity in &data_model.entities {
+        if !classes.contains(&normalise_name(&entity.name)) {
+            mismatches.push(format!("class {} is missing", entity.name));
+            continue;
+        }
+        for field in &entity.fields {
+            if !identifiers.contains(&normalise_name(&field.name)) {
+                mismatches.push(format!(
+                    "class {} is missing field {}",
+                    entity.name, field.name
+                ));
+            }
+        }
+    }
+    mismatches
+}
+
+// Model section added to backend prompts; empty until a model was designed
+pub fn data_model_context(factsheet: &FactSheet) -> String {
+    match &factsheet.data_model {
+        Some(data_model) => format!(
+            "DATA_MODEL (every entity MUST be a struct or class with exactly these fields): {} \n",
+            serde_json::to_string(data_model).unwrap_or_default()
+        ),
+        None => String::new(),
+    }
+}
+
+#[derive(Debug)]
+pub struct AgentDataModelDesigner {
+    attributes: BasicAgent,
+    bug_errors: Option<String>,
+    bug_count: u8,
+}
+
+impl AgentDataModelDesigner {
+    pub fn new() -> Self {
+        let attributes = BasicAgent {
+            objective: "Designs the entities, fields and relationships the backend implements"
+                .to_string(),
+            position: "Data Model Designer".to_string(),
+            state: AgentState::Discovery,
+            memory: vec![],
+        };
+
+        Self {
+            attributes,
+            bug_errors: None,
+            bug_count: 0,
+        }
+    }
+
+    async fn call_data_model(
+        &mut self,
+        factsheet: &mut FactSheet,
+        is_crud_required: bool,
+    ) -> Result<(), LlmError> {
+        let input: DataModelInput = DataModelInput {
+            project_description: factsheet.project_description.clone(),
+            is_crud_required,
+        };
+        let data_model: DataModel =
+            ai_task_request_typed::<PrintDataModel>(&input, &self.attributes.position).await?;
+
+        factsheet.data_model = Some(data_model);
+        Ok(())
+    }
+
+    async fn call_fix_data_model(&mut self, factsheet: &mut FactSheet) -> Result<(), LlmError> {
+        let input: DataModelFix = DataModelFix {
+            project_description: factsheet.project_description.clone(),
+            broken_model: serde_json::to_string(&factsheet.data_model).unwrap_or_default(),
+            error_bugs: self.bug_errors.clone().unwrap_or_default(),
+        };
+        let data_model: DataModel =
+            ai_task_request_typed::<PrintFixedDataModel>(&input, &self.attributes.position).await?;
+
+        factsheet.data_model = Some(data_model);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SpecialFunctions for AgentDataModelDesigner {
+    fn get_attributes_from_agent(&self) -> &BasicAgent {
+        &self.attributes
+    }
+
+    fn reads(&self) -> Vec<FactField> {
+        vec![FactField::ProjectDescription, FactField::ProjectScope]
+    }
+
+    fn writes(&self) -> Vec<FactField> {
+        vec![FactField::DataModel]
+    }
+
+    fn bug_fix_iterations(&self) -> u8 {
+        self.bug_count
+    }
+
+    fn reset_for_retry(&mut self) {
+        self.attributes.state = AgentState::Discovery;
+        self.bug_errors = None;
+        self.bug_count = 0;
+    }
+
+    async fn execute(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Without a scope, design for storage rather than miss entities
+        let is_crud_required: bool = factsheet
+            .project_scope
+            .is_none_or(|scope| scope.is_crud_required);
+
+        while self.attributes.state != AgentState::Finished {
+            // Give up as soon as the run budget is spent
+            if let Some(reason) = run_budget_exceeded() {
+                return Err(reason.into());
+            }
+
+            match self.attributes.state {
+                AgentState::Discovery => {
+                    self.call_data_model(factsheet, is_crud_required).await?;
+                    self.attributes.state = AgentState::UnitTesting;
+                }
+                AgentState::Working => {
+                    self.call_fix_data_model(factsheet).await?;
+                    self.attributes.state = AgentState::UnitTesting;
+                }
+                AgentState::UnitTesting => {
+                    PrintCommand::UnitTest.print_agent_msg(
+                        &self.attributes.position,
+                        "Checking entities, fields and relationships",
+                    );
+                    let issues: Vec<String> = factsheet
+                        .data_model
+                        .as_ref()
+                        .map(|data_model| design_issues(data_model, is_crud_required))
+                        .unwrap_or_default();
+
+                    if issues.is_empty() {
+                        let entities: Vec<&str> = factsheet
+                            .data_model
+                            .iter()
+                            .flat_map(|data_model| data_model.entities.iter())
+                            .map(|entity| entity.name.as_str())
+                            .collect();
+                        let model_msg: String = format!("Data model: {}", entities.join(", "));
+                        PrintCommand::UnitTest
+                            .print_agent_msg(&self.attributes.position, &model_msg);
+                        self.attributes.state = AgentState::Finished;
+                        continue;
+                    }
+
+                    let bug_msg: String = issues.join("; ");
+                    PrintCommand::Issue.print_agent_msg(&self.attributes.position, &bug_msg);
+                    self.bug_count += 1;
+                    self.bug_errors = Some(bug_msg);
+                    if self.bug_count > 2 {
+                        return Err("Data model still has design issues".into());
+                    }
+                    self.attributes.state = AgentState::Working;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::llm_provider::{LlmProvider, with_provider};
+    use crate::apis::scripted_provider::ScriptedProvider;
+    use crate::models::agents::agent_traits::ProjectScope;
+    use crate::models::agents::test_support::factsheet;
+    use std::sync::Arc;
+
+    const TODO_MODEL_JSON: &str = r#"{"entities": [
+        {"name": "User", "fields": [
+            {"name": "id", "field_type": "integer"},
+            {"name": "username", "field_type": "string"}
+        ]},
+        {"name": "Task", "fields": [
+            {"name": "id", "field_type": "integer"},
+            {"name": "title", "field_type": "string"},
+            {"name": "due_date", "field_type": "datetime", "is_optional": true},
+            {"name": "user_id", "field_type": "integer"}
+        ], "relationships": [{"kind": "one_to_many", "target": "User", "via_field": "user_id"}]}
+    ]}"#;
+
+    #[test]
+    fn tests_validates_generated_structs_against_model() {
+        let data_model: DataModel = serde_json::from_str(TODO_MODEL_JSON).unwrap();
+        assert!(design_issues(&data_model, true).is_empty());
+
+        let rust_code: &str = "struct User { id: u64, username: String }\nstruct Task { id: u64, title: String, user_id: u64 }";
+        assert_eq!(
+            rust_struct_mismatches(&data_model, rust_code),
+            vec!["struct Task is missing field due_date"]
+        );
+
+        let java_code: &str = "public class Task { private Long id; private String title; private LocalDate dueDate; private Long userId; }";
+        assert_eq!(
+            java_class_mismatches(&data_model, java_code),
+            vec!["class User is missing"]
+        );
+    }
+
+    #[tokio::test]
+    async fn tests_data_model_designer_fixes_design_issues() {
+        let mut factsheet: FactSheet = factsheet("todo list per user");
+        factsheet.project_scope = Some(ProjectScope {
+            is_crud_required: true,
+            is_user_login_and_logout: false,
+            is_external_urls_required: false,
+        });
+
+        let provider: Arc<ScriptedProvider> = Arc::new(
+            ScriptedProvider::new()
+                .reply_to_function(
+                    "print_data_model",
+                    r#"{"entities": [{"name": "Task", "fields": [{"name": "title", "field_type": "text"}], "relationships": [{"kind": "one_to_many", "target": "Owner", "via_field": "owner_id"}]}]}"#,
+                )
+                .reply_to_function("print_fixed_data_model", TODO_MODEL_JSON),
+        );
+        let mut agent: AgentDataModelDesigner = AgentDataModelDesigner::new();
+        with_provider(
+            provider.clone() as Arc<dyn LlmProvider>,
+            agent.execute(&mut factsheet),
+        )
+        .await
+        .expect("Data model agent failed");
+
+        assert_eq!(provider.remaining_replies(), 0);
+        assert_eq!(agent.bug_count, 1);
+        let fix_request: &str = &provider.calls()[1][0].content[0].text;
+        assert!(fix_request.contains("Task.title has unknown type text"));
+        assert!(fix_request.contains("entity Task has no id field"));
+        assert!(fix_request.contains("Task relates to unknown entity Owner"));
+        assert_eq!(
+            factsheet.data_model,
+            Some(serde_json::from_str(TODO_MODEL_JSON).unwrap())
+        );
+    }
+}
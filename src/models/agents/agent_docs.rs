@@ -0,0 +1,376 @@
+use crate::ai_functions::aifunc_docs::{print_fixed_readme, print_project_readme};
+use crate::apis::llm_error::LlmError;
+use crate::apis::usage_tracker::run_budget_exceeded;
+use crate::helpers::command_line::PrintCommand;
+use crate::helpers::extraction::extract_code;
+use crate::helpers::general::ai_task_request;
+use crate::helpers::prompt_registry::PromptInput;
+use crate::models::agent_basic::basic_agent::{AgentState, BasicAgent};
+use crate::models::agents::agent_frontend::{normalise_target, route_matches};
+use crate::models::agents::agent_traits::{
+    BackendLanguage, FactField, FactSheet, RouteObject, SpecialFunctions,
+};
+
+use async_trait::async_trait;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const HTTP_METHODS: [&str; 5] = ["GET", "POST", "PUT", "PATCH", "DELETE"];
+
+// Routes the README documents: "METHOD /route" headings and the urls of curl examples
+pub fn documented_routes(readme: &str) -> Vec<String> {
+    let mut routes: Vec<String> = vec![];
+    for line in readme.lines() {
+        let tokens: Vec<&str> = line
+            .split(|c: char| c.is_whitespace() || matches!(c, '`' | '#' | '*' | '|'))
+            .filter(|token| !token.is_empty())
+            .collect();
+        for pair in tokens.windows(2) {
+            if HTTP_METHODS.contains(&pair[0]) && pair[1].starts_with('/') {
+                routes.push(normalise_target(pair[1], "/"));
+            }
+        }
+        if line.contains("curl") {
+            for token in &tokens {
+                let url: &str = token.trim_matches(['\'', '"']);
+                if url.contains("://") {
+                    routes.push(normalise_target(url, "/"));
+                }
+            }
+        }
+    }
+    routes.sort();
+    routes.dedup();
+    routes
+}
+
+fn url_host(url: &str) -> &str {
+    let without_scheme: &str = url.split_once("://").map_or(url, |(_, rest)| rest);
+    without_scheme.split(['/', '?']).next().unwrap_or_default()
+}
+
+// Differences between the README and the fact sheet it was written from
+pub fn documentation_issues(
+    readme: &str,
+    routes: &[RouteObject],
+    external_urls: &[String],
+) -> Vec<String> {
+    let documented: Vec<String> = documented_routes(readme);
+    let mut issues: Vec<String> = vec![];
+
+    let unknown: Vec<&str> = documented
+        .iter()
+        .filter(|doc_route| {
+            !routes
+                .iter()
+                .any(|route| route_matches(doc_route, &route.route))
+        })
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        issues.push(format!(
+            "documents routes the API does not have: {}",
+            unknown.join(", ")
+        ));
+    }
+
+    let undocumented: Vec<String> = routes
+        .iter()
+        .filter(|route| {
+            !documented
+                .iter()
+                .any(|doc_route| route_matches(doc_route, &route.route))
+        })
+        .map(|route| format!("{} {}", route.method.to_uppercase(), route.route))
+        .collect();
+    if !undocumented.is_empty() {
+        issues.push(format!("leaves out routes: {}", undocumented.join(", ")));
+    }
+
+    let unlisted: Vec<&str> = external_urls
+        .iter()
+        .map(|url| url_host(url))
+        .filter(|host| !host.is_empty() && !readme.contains(host))
+        .collect();
+    if !unlisted.is_empty() {
+        issues.push(format!(
+            "does not list third-party APIs: {}",
+            unlisted.join(", ")
+        ));
+    }
+    issues
+}
+
+#[derive(Debug)]
+pub struct AgentDocumentationWriter {
+    attributes: BasicAgent,
+    bug_errors: Option<String>,
+    bug_count: u8,
+    readme_path: PathBuf,
+    run_command: &'static str,
+}
+
+impl AgentDocumentationWriter {
+    pub fn new(language: BackendLanguage, project_dir: &Path) -> Self {
+        let attributes = BasicAgent {
+            objective: "Documents how to run the generated server and call its API".to_string(),
+            position: "Documentation Writer".to_string(),
+            state: AgentState::Discovery,
+            memory: vec![],
+        };
+
+        let run_command: &'static str = match language {
+            BackendLanguage::Rust => "cargo run",
+            BackendLanguage::Java => "mvn spring-boot:run",
+        };
+
+        Self {
+            attributes,
+            bug_errors: None,
+            bug_count: 0,
+            readme_path: project_dir.join("README.md"),
+            run_command,
+        }
+    }
+
+    fn save_readme(&self, ai_response: &str, factsheet: &mut FactSheet) -> std::io::Result<()> {
+        // Unfenced Markdown is the README itself; prose stripping would eat its headings
+        let readme: String = if ai_response.trim_start().starts_with("```") {
+            extract_code(ai_response, "markdown")
+        } else {
+            ai_response.trim().to_string()
+        };
+        fs::write(&self.readme_path, format!("{}\n", readme))?;
+        factsheet.documentation = Some(readme);
+        Ok(())
+    }
+
+    async fn call_readme(
+        &mut self,
+        factsheet: &FactSheet,
+        endpoints_json: &str,
+    ) -> Result<String, LlmError> {
+        let project_scope: String =
+            serde_json::to_string(&factsheet.project_scope).unwrap_or_default();
+        let external_urls: String =
+            serde_json::to_string(&factsheet.external_urls).unwrap_or_default();
+        let msg_context: PromptInput = PromptInput::new(format!(
+            "PROJECT_DESCRIPTION: {} \n PROJECT_SCOPE: {} \n EXTERNAL_URLS: {} \n API_ENDPOINTS: {} \n BACKEND_LANGUAGE: {} \n RUN_COMMAND: {} \n",
+            factsheet.project_description,
+            project_scope,
+            external_urls,
+            endpoints_json,
+            factsheet.backend_language.label(),
+            self.run_command
+        ))
+        .var("PROJECT_DESCRIPTION", factsheet.project_description.clone())
+        .var("PROJECT_SCOPE", project_scope)
+        .var("EXTERNAL_URLS", external_urls)
+        .var("API_ENDPOINTS", endpoints_json)
+        .var("BACKEND_LANGUAGE", factsheet.backend_language.label())
+        .var("RUN_COMMAND", self.run_command);
+
+        ai_task_request(
+            msg_context,
+            &self.attributes.position,
+            get_function_string!(print_project_readme),
+            print_project_readme,
+        )
+        .await
+    }
+
+    async fn call_fix_readme(
+        &mut self,
+        factsheet: &FactSheet,
+        endpoints_json: &str,
+    ) -> Result<String, LlmError> {
+        let readme: String = factsheet.documentation.clone().unwrap_or_default();
+        let external_urls: String =
+            serde_json::to_string(&factsheet.external_urls).unwrap_or_default();
+        let bug_errors: String = self.bug_errors.clone().unwrap_or_default();
+        let msg_context: PromptInput = PromptInput::new(format!(
+            "BROKEN_README: {} \n API_ENDPOINTS: {} \n EXTERNAL_URLS: {} \n ERROR_BUGS: {} \n",
+            readme, endpoints_json, external_urls, bug_errors
+        ))
+        .var("BROKEN_README", readme)
+        .var("API_ENDPOINTS", endpoints_json)
+        .var("EXTERNAL_URLS", external_urls)
+        .var("ERROR_BUGS", bug_errors);
+
+        ai_task_request(
+            msg_context,
+            &self.attributes.position,
+            get_function_string!(print_fixed_readme),
+            print_fixed_readme,
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl SpecialFunctions for AgentDocumentationWriter {
+    fn get_attributes_from_agent(&self) -> &BasicAgent {
+        &self.attributes
+    }
+
+    fn reads(&self) -> Vec<FactField> {
+        vec![
+            FactField::ProjectDescription,
+            FactField::BackendLanguage,
+            FactField::ProjectScope,
+            FactField::ExternalUrls,
+            FactField::ApiEndpointSchema,
+        ]
+    }
+
+    fn writes(&self) -> Vec<FactField> {
+        vec![FactField::Documentation, FactField::ServerReadme]
+    }
+
+    fn bug_fix_iterations(&self) -> u8 {
+        self.bug_count
+    }
+
+    fn reset_for_retry(&mut self) {
+        self.attributes.state = AgentState::Discovery;
+        self.bug_errors = None;
+        self.bug_count = 0;
+    }
+
+    async fn execute(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let routes: Vec<RouteObject> = factsheet.api_endpoint_schema.clone().unwrap_or_default();
+        let external_urls: Vec<String> = factsheet.external_urls.clone().unwrap_or_default();
+        let endpoints_json: String = serde_json::to_string_pretty(&routes)?;
+
+        while self.attributes.state != AgentState::Finished {
+            // Give up as soon as the run budget is spent
+            if let Some(reason) = run_budget_exceeded() {
+                return Err(reason.into());
+            }
+
+            match self.attributes.state {
+                AgentState::Discovery => {
+                    let ai_response: String = self.call_readme(factsheet, &endpoints_json).await?;
+                    self.save_readme(&ai_response, factsheet)?;
+                    self.attributes.state = AgentState::UnitTesting;
+                }
+                AgentState::Working => {
+                    let ai_response: String =
+                        self.call_fix_readme(factsheet, &endpoints_json).await?;
+                    self.save_readme(&ai_response, factsheet)?;
+                    self.attributes.state = AgentState::UnitTesting;
+                }
+                AgentState::UnitTesting => {
+                    PrintCommand::UnitTest.print_agent_msg(
+                        &self.attributes.position,
+                        "Checking documented routes against the API schema",
+                    );
+                    let readme: &str = factsheet.documentation.as_deref().unwrap_or_default();
+                    let issues: Vec<String> = documentation_issues(readme, &routes, &external_urls);
+
+                    if issues.is_empty() {
+                        let docs_msg: String =
+                            format!("README written to {}", self.readme_path.display());
+                        PrintCommand::UnitTest
+                            .print_agent_msg(&self.attributes.position, &docs_msg);
+                        self.attributes.state = AgentState::Finished;
+                        continue;
+                    }
+
+                    let bug_msg: String = format!("README {}", issues.join("; "));
+                    PrintCommand::Issue.print_agent_msg(&self.attributes.position, &bug_msg);
+                    self.bug_count += 1;
+                    self.bug_errors = Some(bug_msg);
+                    if self.bug_count > 2 {
+                        return Err("README still does not match the API".into());
+                    }
+                    self.attributes.state = AgentState::Working;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::llm_provider::{LlmProvider, with_provider};
+    use crate::apis::scripted_provider::ScriptedProvider;
+    use crate::models::agents::test_support::{factsheet, route, temp_dir};
+    use std::sync::Arc;
+
+    const FIXED_README: &str = "# Todo API\n\n## API reference\n\n### `GET /task`\n\n    curl http://localhost:8080/task\n\n### `DELETE /task/{id}`\n\n    curl -X DELETE 'http://localhost:8080/task/1'\n\n## Third-party APIs\n\n- http://worldtimeapi.org/api/timezone for the current time";
+
+    #[test]
+    fn tests_checks_readme_against_schema() {
+        assert_eq!(
+            documented_routes(FIXED_README),
+            vec!["/task", "/task/1", "/task/{id}"]
+        );
+
+        let routes: Vec<RouteObject> = vec![route("get", "/task"), route("delete", "/task/{id}")];
+        let external_urls: Vec<String> = vec!["http://worldtimeapi.org/api/timezone".to_string()];
+        assert!(documentation_issues(FIXED_README, &routes, &external_urls).is_empty());
+
+        let broken: &str = "### POST /tasks\n\n    curl -X POST http://localhost:8080/tasks";
+        assert_eq!(
+            documentation_issues(broken, &routes, &external_urls),
+            vec![
+                "documents routes the API does not have: /tasks",
+                "leaves out routes: GET /task, DELETE /task/{id}",
+                "does not list third-party APIs: worldtimeapi.org"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn tests_documentation_writer_fixes_unknown_routes() {
+        let project_dir: PathBuf = temp_dir("docs_writer");
+        let mut agent: AgentDocumentationWriter =
+            AgentDocumentationWriter::new(BackendLanguage::Rust, &project_dir);
+
+        let mut factsheet: FactSheet = factsheet("todo list");
+        factsheet.external_urls = Some(vec!["http://worldtimeapi.org/api/timezone".to_string()]);
+        factsheet.api_endpoint_schema =
+            Some(vec![route("get", "/task"), route("delete", "/task/{id}")]);
+
+        let provider: Arc<ScriptedProvider> = Arc::new(
+            ScriptedProvider::new()
+                .reply_to_function(
+                    "print_project_readme",
+                    "```markdown\n# Todo API\n\n### GET /tasks\n```",
+                )
+                .reply_to_function("print_fixed_readme", FIXED_README),
+        );
+        with_provider(
+            provider.clone() as Arc<dyn LlmProvider>,
+            agent.execute(&mut factsheet),
+        )
+        .await
+        .expect("Documentation agent failed");
+
+        assert_eq!(provider.remaining_replies(), 0);
+        assert_eq!(agent.bug_count, 1);
+        assert!(
+            provider.calls()[0][0].content[0]
+                .text
+                .contains("RUN_COMMAND: cargo run")
+        );
+        assert!(
+            provider.calls()[1][0].content[0]
+                .text
+                .contains("documents routes the API does not have: /tasks")
+        );
+        assert_eq!(
+            fs::read_to_string(project_dir.join("README.md")).unwrap(),
+            format!("{}\n", FIXED_README)
+        );
+        let _ = fs::remove_dir_all(&project_dir);
+    }
+}
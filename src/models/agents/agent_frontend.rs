@@ -0,0 +1,566 @@
+use crate::ai_functions::aifunc_frontend::{print_fixed_frontend_code, print_frontend_code};
+use crate::apis::llm_error::LlmError;
+use crate::apis::usage_tracker::run_budget_exceeded;
+use crate::helpers::command_line::PrintCommand;
+use crate::helpers::extraction::extract_code;
+use crate::helpers::general::{ai_task_request, ai_task_request_with_images};
+use crate::helpers::prompt_registry::PromptInput;
+use crate::models::agent_basic::basic_agent::{AgentState, BasicAgent};
+use crate::models::agents::agent_traits::{
+    BackendLanguage, FactField, FactSheet, RouteObject, SpecialFunctions,
+};
+
+use async_trait::async_trait;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// actix serves the UI under /ui so the static files never shadow API routes
+const ACTIX_STATIC_SERVICE: &str =
+    ".service(actix_files::Files::new(\"/ui\", \"./static\").index_file(\"index.html\"))";
+
+// Url path of a fetch() call with template expressions such as ${id} replaced by {param};
+// relative paths are resolved against the path the page is served from
+pub fn normalise_target(raw: &str, base: &str) -> String {
+    let mut path: String = String::new();
+    let mut rest: &str = raw;
+    while let Some(start) = rest.find("${") {
+        path.push_str(&rest[..start]);
+        path.push_str("{param}");
+        rest = match rest[start..].find('}') {
+            Some(end) => &rest[start + end + 1..],
+            None => "",
+        };
+    }
+    path.push_str(rest);
+
+    // Same-origin absolute urls are fine; keep only the path
+    if let Some(scheme_end) = path.find("://") {
+        let after_host: Option<usize> = path[scheme_end + 3..].find('/');
+        path = match after_host {
+            Some(index) => path[scheme_end + 3 + index..].to_string(),
+            None => "/".to_string(),
+        };
+    }
+    let path: &str = path.split(['?', '#']).next().unwrap_or_default();
+    let joined: String = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("{}/{}", base.trim_end_matches('/'), path)
+    };
+
+    let mut segments: Vec<&str> = vec![];
+    for segment in joined.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    format!("/{}", segments.join("/"))
+}
+
+// Urls passed as string literals to fetch(); calls on variables cannot be checked and are skipped
+pub fn fetch_targets(code: &str, base: &str) -> Vec<String> {
+    let mut targets: Vec<String> = vec![];
+    let mut rest: &str = code;
+    while let Some(index) = rest.find("fetch(") {
+        rest = rest[index + "fetch(".len()..].trim_start();
+        let Some(quote) = rest
+            .chars()
+            .next()
+            .filter(|c| matches!(c, '"' | '\'' | '`'))
+        else {
+            continue;
+        };
+        let literal: &str = &rest[1..];
+        if let Some(end) = literal.find(quote) {
+            targets.push(normalise_target(&literal[..end], base));
+            rest = &literal[end + 1..];
+        }
+    }
+    targets
+}
+
+pub fn route_matches(target: &str, route: &str) -> bool {
+    let target_segments: Vec<&str> = target.trim_end_matches('/').split('/').collect();
+    let route_segments: Vec<&str> = route.trim_end_matches('/').split('/').collect();
+    target_segments.len() == route_segments.len()
+        && target_segments.iter().zip(route_segments.iter()).all(
+            |(target_segment, route_segment)| {
+                let is_dynamic: bool = route_segment.starts_with('{');
+                target_segment == route_segment || (is_dynamic && !target_segment.is_empty())
+            },
+        )
+}
+
+// Fetch targets of the page that match no route of the API schema
+pub fn missing_fetch_targets(code: &str, routes: &[RouteObject], base: &str) -> Vec<String> {
+    let mut missing: Vec<String> = fetch_targets(code, base)
+        .into_iter()
+        .filter(|target| {
+            !routes
+                .iter()
+                .any(|route| route_matches(target, &route.route))
+        })
+        .collect();
+    missing.sort();
+    missing.dedup();
+    missing
+}
+
+// Mount ./static on the generated actix App; None when already wired or no App::new() was found
+pub fn wire_static_files(main_rs: &str) -> Option<String> {
+    if main_rs.contains("actix_files") {
+        return None;
+    }
+    let index: usize = main_rs.find("App::new()")? + "App::new()".len();
+    Some(format!(
+        "{}\n            {}{}",
+        &main_rs[..index],
+        ACTIX_STATIC_SERVICE,
+        &main_rs[index..]
+    ))
+}
+
+#[derive(Debug)]
+pub struct AgentFrontendDeveloper {
+    attributes: BasicAgent,
+    bug_errors: Option<String>,
+    bug_count: u8,
+    // Directory the backend serves static files from
+    static_dir: PathBuf,
+    // actix main.rs that needs a static file service; Spring Boot serves static/ by itself
+    server_main: Option<PathBuf>,
+    // Path the page is served from, relative fetch() urls resolve against it
+    page_base: &'static str,
+    ui_url: &'static str,
+}
+
+impl AgentFrontendDeveloper {
+    pub fn new(language: BackendLanguage, project_dir: &Path) -> Self {
+        let attributes = BasicAgent {
+            objective: "Develops a static frontend calling the backend's API endpoints".to_string(),
+            position: "Frontend Developer".to_string(),
+            state: AgentState::Discovery,
+            memory: vec![],
+        };
+
+        let (static_dir, server_main, page_base, ui_url) = match language {
+            BackendLanguage::Rust => (
+                project_dir.join("static"),
+                Some(project_dir.join("src/main.rs")),
+                "/ui/",
+                "http://localhost:8080/ui/",
+            ),
+            BackendLanguage::Java => (
+                project_dir.join("src/main/resources/static"),
+                None,
+                "/",
+                "http://localhost:8080/",
+            ),
+        };
+
+        Self {
+            attributes,
+            bug_errors: None,
+            bug_count: 0,
+            static_dir,
+            server_main,
+            page_base,
+            ui_url,
+        }
+    }
+
+    fn save_code(&self, ai_response: &str, factsheet: &mut FactSheet) -> std::io::Result<()> {
+        let frontend_code: String = extract_code(ai_response, "html");
+        fs::create_dir_all(&self.static_dir)?;
+        fs::write(self.static_dir.join("index.html"), &frontend_code)?;
+        factsheet.frontend_code = Some(frontend_code);
+        Ok(())
+    }
+
+    async fn call_frontend_code(
+        &mut self,
+        factsheet: &mut FactSheet,
+        endpoints_json: &str,
+    ) -> Result<String, LlmError> {
+        let msg_context: PromptInput = PromptInput::new(format!(
+            "PROJECT_DESCRIPTION: {} \n API_ENDPOINTS: {} \n",
+            factsheet.project_description, endpoints_json
+        ))
+        .var("PROJECT_DESCRIPTION", factsheet.project_description.clone())
+        .var("API_ENDPOINTS", endpoints_json);
+
+        // Wireframes the user attached shape the page layout
+        ai_task_request_with_images(
+            msg_context,
+            &self.attributes.position,
+            get_function_string!(print_frontend_code),
+            print_frontend_code,
+            &factsheet.reference_images,
+        )
+        .await
+    }
+
+    async fn call_fix_frontend_code(
+        &mut self,
+        factsheet: &mut FactSheet,
+        endpoints_json: &str,
+    ) -> Result<String, LlmError> {
+        let frontend_code: String = factsheet.frontend_code.clone().unwrap_or_default();
+        let bug_errors: String = self.bug_errors.clone().unwrap_or_default();
+        let msg_context: PromptInput = PromptInput::new(format!(
+            "BROKEN_CODE: {} \n API_ENDPOINTS: {} \n ERROR_BUGS: {} \n",
+            frontend_code, endpoints_json, bug_errors
+        ))
+        .var("BROKEN_CODE", frontend_code)
+        .var("API_ENDPOINTS", endpoints_json)
+        .var("ERROR_BUGS", bug_errors);
+
+        ai_task_request(
+            msg_context,
+            &self.attributes.position,
+            get_function_string!(print_fixed_frontend_code),
+            print_fixed_frontend_code,
+        )
+        .await
+    }
+
+    // Add the static file service to the actix server once the page is verified, then rebuild it
+    // so the tested binary serves the page; a failed build puts the original main.rs back
+    async fn wire_into_server(
+        &self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(server_main) = &self.server_main else {
+            return Ok(());
+        };
+        let main_rs: String = fs::read_to_string(server_main)?;
+        match wire_static_files(&main_rs) {
+            Some(wired) => {
+                fs::write(server_main, &wired)?;
+                let project_dir: &Path = server_main
+                    .parent()
+                    .and_then(Path::parent)
+                    .ok_or("main.rs is not inside a cargo project")?;
+                let build: std::process::Output = tokio::process::Command::new("cargo")
+                    .arg("build")
+                    .current_dir(project_dir)
+                    .output()
+                    .await?;
+                if !build.status.success() {
+                    fs::write(server_main, &main_rs)?;
+                    let error_msg: String = format!(
+                        "Server no longer builds with the static file service: {}",
+                        String::from_utf8_lossy(&build.stderr)
+                    );
+                    return Err(error_msg.into());
+                }
+                factsheet.backend_code = Some(wired);
+            }
+            None if main_rs.contains("actix_files") => {}
+            None => PrintCommand::Issue.print_agent_msg(
+                &self.attributes.position,
+                "No App::new() in main.rs, serve ./static manually",
+            ),
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SpecialFunctions for AgentFrontendDeveloper {
+    fn get_attributes_from_agent(&self) -> &BasicAgent {
+        &self.attributes
+    }
+
+    fn reads(&self) -> Vec<FactField> {
+        vec![
+            FactField::ProjectDescription,
+            FactField::ReferenceImages,
+            FactField::BackendCode,
+            FactField::ApiEndpointSchema,
+        ]
+    }
+
+    // Wires the static files into the backend code and rebuilds the server
+    fn writes(&self) -> Vec<FactField> {
+        vec![
+            FactField::FrontendCode,
+            FactField::BackendCode,
+            FactField::ServerSource,
+            FactField::StaticFiles,
+        ]
+    }
+
+    fn bug_fix_iterations(&self) -> u8 {
+        self.bug_count
+    }
+
+    fn reset_for_retry(&mut self) {
+        self.attributes.state = AgentState::Discovery;
+        self.bug_errors = None;
+        self.bug_count = 0;
+    }
+
+    async fn execute(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let routes: Vec<RouteObject> = factsheet.api_endpoint_schema.clone().unwrap_or_default();
+        let endpoints_json: String = serde_json::to_string_pretty(&routes)?;
+
+        while self.attributes.state != AgentState::Finished {
+            // Give up as soon as the run budget is spent
+            if let Some(reason) = run_budget_exceeded() {
+                return Err(reason.into());
+            }
+
+            match self.attributes.state {
+                AgentState::Discovery => {
+                    if routes.is_empty() {
+                        PrintCommand::Issue.print_agent_msg(
+                            &self.attributes.position,
+                            "No API endpoints on the fact sheet, skipping the frontend",
+                        );
+                        self.attributes.state = AgentState::Finished;
+                        continue;
+                    }
+                    let ai_response: String =
+                        self.call_frontend_code(factsheet, &endpoints_json).await?;
+                    self.save_code(&ai_response, factsheet)?;
+                    self.attributes.state = AgentState::UnitTesting;
+                }
+                AgentState::Working => {
+                    let ai_response: String = self
+                        .call_fix_frontend_code(factsheet, &endpoints_json)
+                        .await?;
+                    self.save_code(&ai_response, factsheet)?;
+                    self.attributes.state = AgentState::UnitTesting;
+                }
+                AgentState::UnitTesting => {
+                    PrintCommand::UnitTest.print_agent_msg(
+                        &self.attributes.position,
+                        "Checking the page's fetch() calls against the API schema",
+                    );
+                    let frontend_code: &str =
+                        factsheet.frontend_code.as_deref().unwrap_or_default();
+                    let missing: Vec<String> =
+                        missing_fetch_targets(frontend_code, &routes, self.page_base);
+
+                    if missing.is_empty() {
+                        self.wire_into_server(factsheet).await?;
+                        let ui_msg: String = format!("Frontend served at {}", self.ui_url);
+                        PrintCommand::UnitTest.print_agent_msg(&self.attributes.position, &ui_msg);
+                        self.attributes.state = AgentState::Finished;
+                        continue;
+                    }
+
+                    let bug_msg: String = format!(
+                        "fetch() calls routes missing from the API schema: {}",
+                        missing.join(", ")
+                    );
+                    PrintCommand::Issue.print_agent_msg(&self.attributes.position, &bug_msg);
+                    self.bug_count += 1;
+                    self.bug_errors = Some(bug_msg);
+                    if self.bug_count > 2 {
+                        return Err("Frontend still calls routes the backend does not have".into());
+                    }
+                    self.attributes.state = AgentState::Working;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::llm_provider::{LlmProvider, with_provider};
+    use crate::apis::scripted_provider::ScriptedProvider;
+    use crate::apis::usage_tracker::{Budget, PriceTable, UsageLedger};
+    use crate::models::agents::test_support::{factsheet, route, temp_dir};
+    use crate::models::agents_manager::agent_scheduler::{
+        AgentOutcome, FailurePolicy, ScheduleReport, run_agents,
+    };
+    use std::sync::Arc;
+
+    #[test]
+    fn tests_finds_fetch_targets_missing_from_schema() {
+        let page: &str = r#"
+            fetch("/task").then(r => r.json());
+            fetch(`/task/${task.id}`, { method: "DELETE" });
+            fetch('http://localhost:8080/task?done=true');
+            fetch(url);
+            fetch("/stats/");
+            fetch("task");
+            fetch("../task");
+        "#;
+        // The actix page lives under /ui/, so relative urls never reach the API routes
+        assert_eq!(
+            fetch_targets(page, "/ui/"),
+            vec![
+                "/task",
+                "/task/{param}",
+                "/task",
+                "/stats",
+                "/ui/task",
+                "/task"
+            ]
+        );
+        assert_eq!(normalise_target("./task", "/"), "/task");
+
+        let routes: Vec<RouteObject> = vec![route("get", "/task"), route("delete", "/task/{id}")];
+        assert_eq!(
+            missing_fetch_targets(page, &routes, "/ui/"),
+            vec!["/stats", "/ui/task"]
+        );
+        assert_eq!(missing_fetch_targets(page, &routes, "/"), vec!["/stats"]);
+
+        let main_rs: &str = "HttpServer::new(move || {\n        App::new()\n            .app_data(db.clone())\n    })";
+        let wired: String = wire_static_files(main_rs).expect("App::new() not found");
+        assert!(wired.contains("App::new()\n            .service(actix_files::Files::new(\"/ui\""));
+        assert!(wire_static_files(&wired).is_none());
+    }
+
+    #[tokio::test]
+    async fn tests_frontend_developer_fixes_unknown_fetch_targets() {
+        let project_dir: PathBuf = temp_dir("frontend_static");
+        let server_main: PathBuf = project_dir.join("src/main.rs");
+        fs::create_dir_all(project_dir.join("src")).expect("Failed to create src dir");
+        // Already serving ./static, so no rebuild is needed
+        let main_rs: String = format!(
+            "fn main() {{ HttpServer::new(|| App::new(){}); }}",
+            ACTIX_STATIC_SERVICE
+        );
+        fs::write(&server_main, &main_rs).expect("Failed to write main.rs");
+
+        let mut agent: AgentFrontendDeveloper =
+            AgentFrontendDeveloper::new(BackendLanguage::Rust, &project_dir);
+
+        let mut factsheet: FactSheet = factsheet("todo list");
+        factsheet.api_endpoint_schema = Some(vec![route("get", "/task")]);
+
+        let provider: Arc<ScriptedProvider> = Arc::new(
+            ScriptedProvider::new()
+                .reply_to_function(
+                    "print_frontend_code",
+                    "```html\n<script>fetch('/tasks')</script>\n```",
+                )
+                .reply_to_function(
+                    "print_fixed_frontend_code",
+                    "```html\n<script>fetch('/task')</script>\n```",
+                ),
+        );
+        with_provider(
+            provider.clone() as Arc<dyn LlmProvider>,
+            agent.execute(&mut factsheet),
+        )
+        .await
+        .expect("Frontend agent failed");
+
+        assert_eq!(provider.remaining_replies(), 0);
+        assert_eq!(agent.bug_count, 1);
+        assert!(provider.calls()[1][0].content[0].text.contains("/tasks"));
+        assert_eq!(
+            fs::read_to_string(project_dir.join("static/index.html")).unwrap(),
+            "<script>fetch('/task')</script>"
+        );
+        assert_eq!(fs::read_to_string(&server_main).unwrap(), main_rs);
+        assert!(factsheet.backend_code.is_none());
+        let _ = fs::remove_dir_all(&project_dir);
+    }
+
+    #[tokio::test]
+    async fn tests_frontend_developer_starts_over_on_retry() {
+        let project_dir: PathBuf = temp_dir("frontend_retry");
+        fs::create_dir_all(project_dir.join("src")).expect("Failed to create src dir");
+        let main_rs: String = format!(
+            "fn main() {{ HttpServer::new(|| App::new(){}); }}",
+            ACTIX_STATIC_SERVICE
+        );
+        fs::write(project_dir.join("src/main.rs"), &main_rs).expect("Failed to write main.rs");
+
+        let agent: AgentFrontendDeveloper =
+            AgentFrontendDeveloper::new(BackendLanguage::Rust, &project_dir);
+        let mut agents: Vec<Box<dyn SpecialFunctions>> = vec![Box::new(agent)];
+
+        let mut factsheet: FactSheet = factsheet("todo list");
+        factsheet.api_endpoint_schema = Some(vec![route("get", "/task")]);
+
+        // The first attempt uses up its bug fix rounds, the retry starts from a new page
+        let provider: Arc<ScriptedProvider> = Arc::new(
+            ScriptedProvider::new()
+                .reply_to_function(
+                    "print_frontend_code",
+                    "```html\n<script>fetch('/tasks')</script>\n```",
+                )
+                .reply_to_function(
+                    "print_fixed_frontend_code",
+                    "```html\n<script>fetch('/todo')</script>\n```",
+                )
+                .reply_to_function(
+                    "print_fixed_frontend_code",
+                    "```html\n<script>fetch('/item')</script>\n```",
+                )
+                .reply_to_function(
+                    "print_frontend_code",
+                    "```html\n<script>fetch('/task')</script>\n```",
+                ),
+        );
+        let usage: Arc<UsageLedger> =
+            Arc::new(UsageLedger::new(Budget::default(), PriceTable::default()));
+        let report: ScheduleReport = with_provider(
+            provider.clone() as Arc<dyn LlmProvider>,
+            run_agents(
+                &mut agents,
+                &mut factsheet,
+                &usage,
+                FailurePolicy::Retry(1),
+                "Project Manager",
+            ),
+        )
+        .await;
+
+        assert_eq!(provider.remaining_replies(), 0);
+        assert_eq!(report.runs[0].outcome, AgentOutcome::Succeeded);
+        assert_eq!(report.runs[0].attempts, 2);
+        assert_eq!(report.runs[0].errors.len(), 1);
+        assert_eq!(report.runs[0].bug_fix_iterations, 0);
+        assert_eq!(
+            factsheet.frontend_code.as_deref(),
+            Some("<script>fetch('/task')</script>")
+        );
+        let _ = fs::remove_dir_all(&project_dir);
+    }
+
+    #[tokio::test]
+    async fn tests_restores_main_rs_when_wired_server_does_not_build() {
+        let project_dir: PathBuf = temp_dir("frontend_server");
+        let server_main: PathBuf = project_dir.join("src/main.rs");
+        fs::create_dir_all(project_dir.join("src")).expect("Failed to create src dir");
+        fs::write(project_dir.join("Cargo.toml"), "[package]\nname = ")
+            .expect("Failed to write Cargo.toml");
+        let main_rs: &str = "fn main() { HttpServer::new(|| App::new()); }";
+        fs::write(&server_main, main_rs).expect("Failed to write main.rs");
+
+        let agent: AgentFrontendDeveloper =
+            AgentFrontendDeveloper::new(BackendLanguage::Rust, &project_dir);
+        let mut factsheet: FactSheet = factsheet("todo list");
+
+        let error: String = agent
+            .wire_into_server(&mut factsheet)
+            .await
+            .expect_err("Broken server build was accepted")
+            .to_string();
+        assert!(error.contains("no longer builds"));
+        assert_eq!(fs::read_to_string(&server_main).unwrap(), main_rs);
+        assert!(factsheet.backend_code.is_none());
+        let _ = fs::remove_dir_all(&project_dir);
+    }
+}
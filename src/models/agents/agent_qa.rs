@@ -0,0 +1,653 @@
+use crate::apis::usage_tracker::run_budget_exceeded;
+use crate::helpers::command_line::PrintCommand;
+use crate::models::agent_basic::basic_agent::{AgentState, BasicAgent};
+use crate::models::agents::agent_springboot::BuildTool;
+use crate::models::agents::agent_traits::{
+    BackendLanguage, FactField, FactSheet, RouteObject, RouteTestResult, SpecialFunctions,
+};
+
+use async_trait::async_trait;
+use reqwest::{Client, Method};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::{Child, Command};
+use tokio::time;
+
+const SERVER_STARTUP_TIMEOUT: Duration = Duration::from_secs(90);
+const SERVER_URL: &str = "http://localhost:8080";
+
+// Port nothing listens on right now, so the tested server never collides with another one
+fn free_port() -> std::io::Result<u16> {
+    Ok(TcpListener::bind("127.0.0.1:0")?.local_addr()?.port())
+}
+
+// Shape values such as "None" or "not_provided" mean the schema says nothing
+fn is_unspecified(shape: &Value) -> bool {
+    match shape {
+        Value::Null => true,
+        Value::String(text) => matches!(
+            text.trim().to_lowercase().as_str(),
+            "" | "none" | "null" | "not_provided"
+        ),
+        _ => false,
+    }
+}
+
+// JSON kind a type name of the schema stands for; None accepts any value
+fn shape_kind(type_name: &str) -> Option<&'static str> {
+    let type_name: String = type_name.trim().to_lowercase();
+    match type_name.as_str() {
+        "string" | "str" | "text" | "datetime" | "date" | "uuid" => Some("string"),
+        "number" | "integer" | "int" | "float" | "double" | "long" | "f32" | "f64" | "i32"
+        | "i64" | "u32" | "u64" | "usize" => Some("number"),
+        "bool" | "boolean" => Some("bool"),
+        _ => None,
+    }
+}
+
+// A request body value matching the schema's shape
+pub fn example_value(shape: &Value) -> Value {
+    match shape {
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, field_shape)| (name.clone(), example_value(field_shape)))
+                .collect::<Map<String, Value>>(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().take(1).map(example_value).collect()),
+        Value::String(type_name) => match type_name.trim().to_lowercase().as_str() {
+            "datetime" => Value::from("2024-01-01T00:00:00Z"),
+            "date" => Value::from("2024-01-01"),
+            _ => match shape_kind(type_name) {
+                Some("number") => Value::from(1),
+                Some("bool") => Value::from(true),
+                _ => Value::from("example"),
+            },
+        },
+        other => other.clone(),
+    }
+}
+
+// Where the response differs from the schema's shape; missing keys and wrong kinds only
+pub fn shape_mismatches(shape: &Value, actual: &Value, path: &str) -> Vec<String> {
+    // Optional fields come back as null
+    if is_unspecified(shape) || actual.is_null() {
+        return vec![];
+    }
+    let at: &str = if path.is_empty() { "response" } else { path };
+    match shape {
+        Value::Object(fields) => {
+            let Some(actual_fields) = actual.as_object() else {
+                return vec![format!("{} is not an object", at)];
+            };
+            fields
+                .iter()
+                .flat_map(|(name, field_shape)| {
+                    let field_path: String = format!("{}.{}", at, name);
+                    match actual_fields.get(name) {
+                        Some(value) => shape_mismatches(field_shape, value, &field_path),
+                        None => vec![format!("{} is missing", field_path)],
+                    }
+                })
+                .collect()
+        }
+        Value::Array(items) => {
+            let Some(actual_items) = actual.as_array() else {
+                return vec![format!("{} is not an array", at)];
+            };
+            match items.first() {
+                Some(item_shape) => actual_items
+                    .iter()
+                    .take(3)
+                    .enumerate()
+                    .flat_map(|(index, item)| {
+                        shape_mismatches(item_shape, item, &format!("{}[{}]", at, index))
+                    })
+                    .collect(),
+                None => vec![],
+            }
+        }
+        Value::String(type_name) => {
+            let matches_kind: bool = match shape_kind(type_name) {
+                Some("string") => actual.is_string(),
+                Some("number") => actual.is_number(),
+                Some("bool") => actual.is_boolean(),
+                _ => true,
+            };
+            if matches_kind {
+                vec![]
+            } else {
+                vec![format!("{} should be {} but was {}", at, type_name, actual)]
+            }
+        }
+        _ => vec![],
+    }
+}
+
+// Static part of the route that dynamic routes of the same resource share
+fn resource_of(route: &str) -> String {
+    let prefix: Vec<&str> = route
+        .split('/')
+        .take_while(|segment| !segment.starts_with('{'))
+        .collect();
+    prefix.join("/").trim_end_matches('/').to_string()
+}
+
+fn method_rank(route_object: &RouteObject) -> u8 {
+    let is_dynamic: bool = route_object.route.contains('{');
+    match (route_object.method.to_lowercase().as_str(), is_dynamic) {
+        ("post", _) => 0,
+        ("get", false) => 1,
+        ("get", true) => 2,
+        ("put", _) | ("patch", _) => 3,
+        ("delete", _) => 4,
+        _ => 5,
+    }
+}
+
+// Routes grouped per resource in create, list, read, update, delete order
+pub fn plan_requests(routes: &[RouteObject]) -> Vec<RouteObject> {
+    let mut resources: Vec<String> = vec![];
+    for route_object in routes {
+        let resource: String = resource_of(&route_object.route);
+        if !resources.contains(&resource) {
+            resources.push(resource);
+        }
+    }
+
+    let mut plan: Vec<RouteObject> = routes.to_vec();
+    plan.sort_by_key(|route_object| {
+        let resource: String = resource_of(&route_object.route);
+        let resource_index: Option<usize> = resources.iter().position(|r| *r == resource);
+        (
+            resource_index.unwrap_or_default(),
+            method_rank(route_object),
+        )
+    });
+    plan
+}
+
+// Path parameters filled with the id the resource's POST or GET returned, "1" before that
+pub fn fill_path(route: &str, id: Option<&str>) -> String {
+    route
+        .split('/')
+        .map(|segment| {
+            if segment.starts_with('{') && segment.ends_with('}') {
+                id.unwrap_or("1").to_string()
+            } else {
+                segment.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+// Id of the created or listed item, so later requests target something that exists
+fn returned_id(response: &Value) -> Option<String> {
+    let item: &Value = match response {
+        Value::Array(items) => items.first()?,
+        other => other,
+    };
+    match item.get("id")? {
+        Value::String(id) => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+pub struct AgentQaEngineer {
+    attributes: BasicAgent,
+    language: BackendLanguage,
+    base_url: String,
+    // Off when the backend is already running, as in tests
+    launch_server: bool,
+    plan: Vec<RouteObject>,
+    // Web template the server is launched from
+    project_dir: PathBuf,
+}
+
+impl AgentQaEngineer {
+    pub fn new(language: BackendLanguage, project_dir: &Path) -> Self {
+        let attributes = BasicAgent {
+            objective: "Runs HTTP integration tests against every backend route".to_string(),
+            position: "QA Engineer".to_string(),
+            state: AgentState::Discovery,
+            memory: vec![],
+        };
+
+        Self {
+            attributes,
+            language,
+            base_url: SERVER_URL.to_string(),
+            launch_server: true,
+            plan: vec![],
+            project_dir: project_dir.to_path_buf(),
+        }
+    }
+
+    // actix templates read PORT, Spring Boot maps SERVER_PORT onto server.port
+    fn server_command(&self, port: u16) -> Option<Command> {
+        let mut command: Command = match self.language {
+            BackendLanguage::Rust => {
+                let mut command: Command = Command::new("cargo");
+                command.arg("run").current_dir(&self.project_dir);
+                command
+            }
+            BackendLanguage::Java => BuildTool::detect(&self.project_dir)?.run(&self.project_dir),
+        };
+        command
+            .env("PORT", port.to_string())
+            .env("SERVER_PORT", port.to_string());
+        Some(command)
+    }
+
+    // Wait until the server answers any HTTP request and return the url that answered; fails early
+    // once the launched server exits. Generated code that ignores PORT is found on the fallback url
+    async fn wait_for_server(
+        &self,
+        client: &Client,
+        mut server: Option<&mut Child>,
+        fallback_url: Option<&str>,
+    ) -> Result<String, String> {
+        let started: time::Instant = time::Instant::now();
+        while started.elapsed() < SERVER_STARTUP_TIMEOUT {
+            if let Some(child) = server.as_deref_mut() {
+                match child.try_wait() {
+                    Ok(Some(status)) => {
+                        return Err(format!("Server exited before answering ({})", status));
+                    }
+                    Ok(None) => {}
+                    Err(e) => return Err(format!("Failed to check the server process: {}", e)),
+                }
+            }
+            for url in std::iter::once(self.base_url.as_str()).chain(fallback_url) {
+                if client.get(url).send().await.is_ok() {
+                    return Ok(url.to_string());
+                }
+            }
+            time::sleep(Duration::from_secs(2)).await;
+        }
+        Err(format!(
+            "Server did not start within {}s",
+            SERVER_STARTUP_TIMEOUT.as_secs()
+        ))
+    }
+
+    async fn run_request(
+        &self,
+        client: &Client,
+        route_object: &RouteObject,
+        ids: &mut HashMap<String, String>,
+    ) -> RouteTestResult {
+        let resource: String = resource_of(&route_object.route);
+        let url_path: String =
+            fill_path(&route_object.route, ids.get(&resource).map(String::as_str));
+        let mut result: RouteTestResult = RouteTestResult {
+            route: route_object.route.clone(),
+            method: route_object.method.to_lowercase(),
+            url_path: url_path.clone(),
+            status: None,
+            passed: false,
+            detail: String::new(),
+        };
+
+        let method: Method = match Method::from_bytes(result.method.to_uppercase().as_bytes()) {
+            Ok(method) => method,
+            Err(_) => {
+                result.detail = format!("unknown method {}", route_object.method);
+                return result;
+            }
+        };
+        let mut request = client.request(method, format!("{}{}", self.base_url, url_path));
+        if !is_unspecified(&route_object.request_body) {
+            request = request.json(&example_value(&route_object.request_body));
+        }
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                result.detail = format!("request failed: {}", e);
+                return result;
+            }
+        };
+        let status: u16 = response.status().as_u16();
+        result.status = Some(status);
+        let body: String = response.text().await.unwrap_or_default();
+
+        if !(200..300).contains(&status) {
+            result.detail = format!("status {}", status);
+            return result;
+        }
+        if is_unspecified(&route_object.response) {
+            result.passed = true;
+            return result;
+        }
+
+        let Ok(response_json) = serde_json::from_str::<Value>(&body) else {
+            result.detail = "response is not JSON".to_string();
+            return result;
+        };
+        if let Some(id) = returned_id(&response_json) {
+            ids.insert(resource, id);
+        }
+        let mismatches: Vec<String> = shape_mismatches(&route_object.response, &response_json, "");
+        result.passed = mismatches.is_empty();
+        result.detail = mismatches.join("; ");
+        result
+    }
+
+    async fn run_plan(&mut self) -> Result<Vec<RouteTestResult>, Box<dyn std::error::Error>> {
+        let mut server: Option<Child> = None;
+        let mut fallback_url: Option<&str> = None;
+        if self.launch_server {
+            let port: u16 = free_port()?;
+            self.base_url = format!("http://localhost:{}", port);
+            let start_msg: String = format!("Starting webserver on port {}...", port);
+            PrintCommand::UnitTest.print_agent_msg(&self.attributes.position, &start_msg);
+            let mut command: Command = self
+                .server_command(port)
+                .ok_or("No build tool found to start the backend")?;
+            let child: Child = command
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .kill_on_drop(true)
+                .spawn()
+                .map_err(|e| format!("Failed to run backend server: {}", e))?;
+            server = Some(child);
+            fallback_url = Some(SERVER_URL);
+        }
+
+        let client: Client = Client::builder().timeout(Duration::from_secs(10)).build()?;
+        let mut results: Vec<RouteTestResult> = vec![];
+        let started: Result<String, String> = self
+            .wait_for_server(&client, server.as_mut(), fallback_url)
+            .await;
+        if let Ok(url) = &started
+            && *url != self.base_url
+        {
+            let port_msg: String = format!("Server ignored PORT, testing it on {}", url);
+            PrintCommand::Issue.print_agent_msg(&self.attributes.position, &port_msg);
+            self.base_url = url.clone();
+        }
+        if started.is_ok() {
+            let mut ids: HashMap<String, String> = HashMap::new();
+            for route_object in &self.plan {
+                let result: RouteTestResult =
+                    self.run_request(&client, route_object, &mut ids).await;
+                let result_msg: String = format!(
+                    "{} {} {} {}",
+                    if result.passed { "✅" } else { "❌" },
+                    result.method.to_uppercase(),
+                    result.url_path,
+                    result.detail
+                );
+                PrintCommand::UnitTest.print_agent_msg(&self.attributes.position, &result_msg);
+                results.push(result);
+            }
+        }
+
+        if let Some(mut child) = server {
+            let _ = child.kill().await;
+        }
+        started?;
+        Ok(results)
+    }
+}
+
+#[async_trait]
+impl SpecialFunctions for AgentQaEngineer {
+    fn get_attributes_from_agent(&self) -> &BasicAgent {
+        &self.attributes
+    }
+
+    // Runs the server built from the backend code
+    fn reads(&self) -> Vec<FactField> {
+        vec![
+            FactField::BackendLanguage,
+            FactField::BackendCode,
+            FactField::ApiEndpointSchema,
+            FactField::ServerSource,
+            FactField::StaticFiles,
+        ]
+    }
+
+    fn writes(&self) -> Vec<FactField> {
+        vec![FactField::RouteTestResults]
+    }
+
+    fn reset_for_retry(&mut self) {
+        self.attributes.state = AgentState::Discovery;
+        self.plan = vec![];
+    }
+
+    async fn execute(
+        &mut self,
+        factsheet: &mut FactSheet,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        while self.attributes.state != AgentState::Finished {
+            // Give up as soon as the run budget is spent
+            if let Some(reason) = run_budget_exceeded() {
+                return Err(reason.into());
+            }
+
+            match self.attributes.state {
+                AgentState::Discovery => {
+                    let routes: Vec<RouteObject> =
+                        factsheet.api_endpoint_schema.clone().unwrap_or_default();
+                    if routes.is_empty() {
+                        PrintCommand::Issue.print_agent_msg(
+                            &self.attributes.position,
+                            "No API endpoints on the fact sheet, skipping integration tests",
+                        );
+                        self.attributes.state = AgentState::Finished;
+                        continue;
+                    }
+                    self.plan = plan_requests(&routes);
+                    self.attributes.state = AgentState::UnitTesting;
+                }
+                AgentState::UnitTesting => {
+                    let results: Vec<RouteTestResult> = self.run_plan().await?;
+                    let passed: usize = results.iter().filter(|result| result.passed).count();
+                    let summary_msg: String =
+                        format!("{}/{} route requests passed", passed, results.len());
+                    if passed == results.len() {
+                        PrintCommand::UnitTest
+                            .print_agent_msg(&self.attributes.position, &summary_msg);
+                    } else {
+                        PrintCommand::Issue
+                            .print_agent_msg(&self.attributes.position, &summary_msg);
+                    }
+                    factsheet.route_test_results = Some(results);
+                    self.attributes.state = AgentState::Finished;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::general::WEBSERVER_RUST_PATH;
+    use crate::models::agents::test_support::{factsheet, route_with_bodies};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn task_routes() -> Vec<RouteObject> {
+        let task: Value = serde_json::json!({"id": "number", "title": "string", "done": "bool"});
+        vec![
+            route_with_bodies(
+                "delete",
+                "/task/{id}",
+                Value::from("None"),
+                Value::from("None"),
+            ),
+            route_with_bodies("get", "/task/{id}", Value::from("None"), task.clone()),
+            route_with_bodies("put", "/task/{id}", task.clone(), task.clone()),
+            route_with_bodies("get", "/health", Value::from("None"), Value::from("None")),
+            route_with_bodies("post", "/task", task.clone(), task.clone()),
+            route_with_bodies(
+                "get",
+                "/task",
+                Value::from("None"),
+                serde_json::json!([task]),
+            ),
+        ]
+    }
+
+    #[test]
+    fn tests_plans_crud_sequences_and_checks_shapes() {
+        let plan: Vec<String> = plan_requests(&task_routes())
+            .iter()
+            .map(|route_object| format!("{} {}", route_object.method, route_object.route))
+            .collect();
+        assert_eq!(
+            plan,
+            vec![
+                "post /task",
+                "get /task",
+                "get /task/{id}",
+                "put /task/{id}",
+                "delete /task/{id}",
+                "get /health"
+            ]
+        );
+
+        let shape: Value = serde_json::json!({"pairs": [{"base": "string", "price": "number"}]});
+        assert_eq!(
+            example_value(&shape),
+            serde_json::json!({"pairs": [{"base": "example", "price": 1}]})
+        );
+        assert_eq!(
+            fill_path("/task/{id}/tags/{tag}", Some("7")),
+            "/task/7/tags/7"
+        );
+        assert_eq!(
+            shape_mismatches(&shape, &serde_json::json!({"pairs": [{"base": 3}]}), ""),
+            vec![
+                "response.pairs[0].base should be string but was 3",
+                "response.pairs[0].price is missing"
+            ]
+        );
+    }
+
+    // Minimal todo server: creates task 42, returns a task without "done" on GET by id
+    async fn serve_todo_api(listener: TcpListener) {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                return;
+            };
+            let mut buffer: Vec<u8> = vec![0; 4096];
+            let read: usize = stream.read(&mut buffer).await.unwrap_or_default();
+            let request: String = String::from_utf8_lossy(&buffer[..read]).to_string();
+            let request_line: &str = request.lines().next().unwrap_or_default();
+            let (status, body): (&str, &str) = match request_line {
+                line if line.starts_with("POST /task ") => {
+                    ("200 OK", r#"{"id": 42, "title": "example", "done": true}"#)
+                }
+                line if line.starts_with("GET /task ") => (
+                    "200 OK",
+                    r#"[{"id": 42, "title": "example", "done": true}]"#,
+                ),
+                line if line.starts_with("GET /task/42 ") => {
+                    ("200 OK", r#"{"id": 42, "title": "example"}"#)
+                }
+                line if line.starts_with("PUT /task/42 ") => {
+                    ("200 OK", r#"{"id": 42, "title": "example", "done": true}"#)
+                }
+                line if line.starts_with("DELETE /task/42 ") => ("204 No Content", ""),
+                line if line.starts_with("GET / ") => ("200 OK", ""),
+                _ => ("404 Not Found", ""),
+            };
+            let response: String = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn tests_qa_engineer_records_results_per_route() {
+        let listener: TcpListener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url: String = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve_todo_api(listener));
+
+        let mut factsheet: FactSheet = factsheet("todo list");
+        factsheet.api_endpoint_schema = Some(task_routes());
+
+        let mut agent: AgentQaEngineer =
+            AgentQaEngineer::new(BackendLanguage::Rust, Path::new(WEBSERVER_RUST_PATH));
+        agent.base_url = base_url;
+        agent.launch_server = false;
+        agent
+            .execute(&mut factsheet)
+            .await
+            .expect("QA agent failed");
+        server.abort();
+
+        let results: Vec<RouteTestResult> = factsheet.route_test_results.unwrap();
+        let outcomes: Vec<(&str, bool)> = results
+            .iter()
+            .map(|result| (result.url_path.as_str(), result.passed))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                ("/task", true),
+                ("/task", true),
+                ("/task/42", false),
+                ("/task/42", true),
+                ("/task/42", true),
+                ("/health", false)
+            ]
+        );
+        assert_eq!(results[2].detail, "response.done is missing");
+        assert_eq!(results[5].status, Some(404));
+    }
+
+    #[tokio::test]
+    async fn tests_stops_waiting_when_server_exits() {
+        let mut agent: AgentQaEngineer =
+            AgentQaEngineer::new(BackendLanguage::Rust, Path::new(WEBSERVER_RUST_PATH));
+        agent.base_url = format!("http://localhost:{}", free_port().unwrap());
+        let mut server: Child = Command::new("true").spawn().expect("Failed to spawn");
+
+        let client: Client = Client::new();
+        let started: time::Instant = time::Instant::now();
+        let error: String = agent
+            .wait_for_server(&client, Some(&mut server), None)
+            .await
+            .expect_err("Exited server was waited for");
+        assert!(error.starts_with("Server exited before answering"));
+        assert!(started.elapsed() < SERVER_STARTUP_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn tests_finds_server_that_ignored_port() {
+        let listener: TcpListener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let bound_url: String = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve_todo_api(listener));
+
+        // The port handed out through PORT stays closed, the server bound its own
+        let mut agent: AgentQaEngineer =
+            AgentQaEngineer::new(BackendLanguage::Rust, Path::new(WEBSERVER_RUST_PATH));
+        agent.base_url = format!("http://localhost:{}", free_port().unwrap());
+        let url: String = agent
+            .wait_for_server(&Client::new(), None, Some(&bound_url))
+            .await
+            .expect("Fallback url was not probed");
+        server.abort();
+        assert_eq!(url, bound_url);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::ai_functions::aifunc_security::PrintSecurityReview;
+use crate::helpers::command_line::PrintCommand;
+use crate::helpers::general::ai_task_request_typed;
+use crate::helpers::security_scan::{SecurityFinding, SecurityReport, scan_source};
+use crate::models::agent_basic::basic_agent::{AgentState, BasicAgent};
+use crate::models::agents::agent_traits::CodeInput;
+
+use std::fs;
+use std::path::Path;
+
+// Generated code and build scripts of each template, relative to the project directory
+pub const RUST_REVIEW_FILES: [&str; 2] = ["src/main.rs", "build.rs"];
+pub const SPRINGBOOT_REVIEW_FILES: [&str; 5] = [
+    "src/main/java/com/andreanator/webapi/Application.java",
+    "src/main/resources/application.properties",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+];
+
+// Reviews AI written code before the developer agents build and run it
+#[derive(Debug)]
+pub struct AgentSecurityReviewer {
+    attributes: BasicAgent,
+}
+
+impl AgentSecurityReviewer {
+    pub fn new() -> Self {
+        let attributes = BasicAgent {
+            objective: "Reviews generated code for risks before it runs on this machine"
+                .to_string(),
+            position: "Security Reviewer".to_string(),
+            state: AgentState::Discovery,
+            memory: vec![],
+        };
+
+        Self { attributes }
+    }
+
+    pub fn position(&self) -> &str {
+        &self.attributes.position
+    }
+
+    // Rule based scan of every file that exists, then an LLM pass over the same files
+    pub async fn review(&self, project_dir: &Path, files: &[&str]) -> SecurityReport {
+        let mut report: SecurityReport = SecurityReport::default();
+        let mut code_input: String = String::new();
+        for file in files {
+            let Ok(code) = fs::read_to_string(project_dir.join(file)) else {
+                continue;
+            };
+            report.findings.extend(scan_source(file, &code));
+            code_input.push_str(&format!("// FILE: {}\n{}\n", file, code));
+        }
+        if code_input.is_empty() {
+            return report;
+        }
+
+        let input: CodeInput = CodeInput { code_input };
+        match ai_task_request_typed::<PrintSecurityReview>(&input, &self.attributes.position).await
+        {
+            Ok(llm_findings) => {
+                report
+                    .findings
+                    .extend(llm_findings.into_iter().map(|finding| SecurityFinding {
+                        rule: "llm_review".to_string(),
+                        ..finding
+                    }));
+                report.llm_reviewed = true;
+            }
+            Err(e) => {
+                let err_msg: String = format!("LLM security review failed: {}", e);
+                PrintCommand::Issue.print_agent_msg(&self.attributes.position, &err_msg);
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::llm_provider::{LlmProvider, with_provider};
+    use crate::apis::scripted_provider::ScriptedProvider;
+    use crate::helpers::security_scan::Severity;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn tests_review_combines_rules_and_llm_findings() {
+        let project_dir =
+            std::env::temp_dir().join(format!("security_review_{}", std::process::id()));
+        fs::create_dir_all(project_dir.join("src")).expect("Failed to create temp project");
+        fs::write(
+            project_dir.join("src/main.rs"),
+            "fn main() {\n    let _ = std::fs::remove_dir_all(\"/home\");\n}\n",
+        )
+        .expect("Failed to write main.rs");
+
+        let provider: Arc<ScriptedProvider> = Arc::new(ScriptedProvider::new().reply_to_function(
+            "print_security_review",
+            r#"[{"severity": "high", "line": 2, "message": "deletes the user's home directories"}]"#,
+        ));
+        let reviewer: AgentSecurityReviewer = AgentSecurityReviewer::new();
+        let report: SecurityReport = with_provider(
+            provider.clone() as Arc<dyn LlmProvider>,
+            reviewer.review(&project_dir, &RUST_REVIEW_FILES),
+        )
+        .await;
+        let _ = fs::remove_dir_all(&project_dir);
+
+        assert!(report.llm_reviewed);
+        assert!(report.has_high());
+        let rules: Vec<&str> = report
+            .findings
+            .iter()
+            .map(|finding| finding.rule.as_str())
+            .collect();
+        assert_eq!(rules, vec!["write_outside_project", "llm_review"]);
+        assert_eq!(report.findings[1].severity, Severity::High);
+        // Only existing files are sent for review
+        assert!(!provider.calls()[0][0].content[0].text.contains("build.rs"));
+    }
+}
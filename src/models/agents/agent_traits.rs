@@ -1,9 +1,12 @@
+use crate::helpers::general::{WEBSERVER_RUST_PATH, WEBSERVER_SPRINGBOOT_PATH};
+use crate::helpers::image_input::ImageInput;
 use crate::models::agent_basic::basic_agent::BasicAgent;
 use async_trait::async_trait;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
 pub struct RouteObject {
     pub is_route_dynamic: String,
     pub method: String,
@@ -12,25 +15,247 @@ pub struct RouteObject {
     pub route: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq)]
 pub struct ProjectScope {
     pub is_crud_required: bool,
     pub is_user_login_and_logout: bool,
     pub is_external_urls_required: bool,
 }
 
+// Outcome of one request the QA agent sent to the running backend
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RouteTestResult {
+    pub route: String,
+    pub method: String,
+    // Path the request went to, with parameters filled in
+    pub url_path: String,
+    pub status: Option<u16>,
+    pub passed: bool,
+    pub detail: String,
+}
+
+// Input of the Solution Architect's ai_functions
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProjectDescription {
+    pub project_description: String,
+}
+
+// Webserver source handed to ai_functions that analyse code
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CodeInput {
+    pub code_input: String,
+}
+
+// Input of the Data Model Designer's first draft
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DataModelInput {
+    pub project_description: String,
+    pub is_crud_required: bool,
+}
+
+// Input of the Data Model Designer's fix up of a draft with design issues
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DataModelFix {
+    pub project_description: String,
+    pub broken_model: String,
+    pub error_bugs: String,
+}
+
+// Entities, fields and relationships the backend must implement
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq, Default)]
+pub struct DataModel {
+    pub entities: Vec<EntityModel>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct EntityModel {
+    pub name: String,
+    pub fields: Vec<FieldModel>,
+    #[serde(default)]
+    pub relationships: Vec<RelationshipModel>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct FieldModel {
+    pub name: String,
+    // "string", "integer", "number", "bool", "datetime" or "list<...>" of those
+    pub field_type: String,
+    #[serde(default)]
+    pub is_optional: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct RelationshipModel {
+    // "one_to_one", "one_to_many" or "many_to_many"
+    pub kind: String,
+    pub target: String,
+    // Field of this entity holding the target's id(s)
+    pub via_field: String,
+}
+
+// Language and framework the backend is generated in
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackendLanguage {
+    // actix-web, built with cargo
+    #[default]
+    Rust,
+    // Spring Boot, built with Maven or Gradle
+    Java,
+}
+
+impl BackendLanguage {
+    // Menu choice or --language value: "1"/"rust" or "2"/"java"/"spring"
+    pub fn parse(choice: &str) -> Option<Self> {
+        match choice.trim().to_lowercase().as_str() {
+            "1" | "rust" => Some(BackendLanguage::Rust),
+            "2" | "java" | "spring" | "springboot" => Some(BackendLanguage::Java),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BackendLanguage::Rust => "Rust",
+            BackendLanguage::Java => "Java (Spring Boot)",
+        }
+    }
+
+    // Web template the generated server is written into
+    pub fn template_dir(&self) -> &'static str {
+        match self {
+            BackendLanguage::Rust => WEBSERVER_RUST_PATH,
+            BackendLanguage::Java => WEBSERVER_SPRINGBOOT_PATH,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct FactSheet {
     pub project_description: String,
+    #[serde(default)]
+    pub backend_language: BackendLanguage,
     pub project_scope: Option<ProjectScope>,
     pub external_urls: Option<Vec<String>>,
     pub backend_code: Option<String>,
     pub api_endpoint_schema: Option<Vec<RouteObject>>,
+    // Designed ahead of the backend; generated structs are checked against it
+    #[serde(default)]
+    pub data_model: Option<DataModel>,
+    // Per route pass/fail of the QA agent's integration tests
+    #[serde(default)]
+    pub route_test_results: Option<Vec<RouteTestResult>>,
+    // Single page index.html served next to the API
+    #[serde(default)]
+    pub frontend_code: Option<String>,
+    // README.md written for the generated server
+    #[serde(default)]
+    pub documentation: Option<String>,
+    // Wireframes or diagrams the user attached to the request
+    #[serde(skip)]
+    pub reference_images: Vec<ImageInput>,
+}
+
+// Fact sheet fields agents declare they read or write, so independent agents can run together
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FactField {
+    ProjectDescription,
+    BackendLanguage,
+    ReferenceImages,
+    ProjectScope,
+    ExternalUrls,
+    DataModel,
+    BackendCode,
+    ApiEndpointSchema,
+    RouteTestResults,
+    FrontendCode,
+    Documentation,
+    // Shared resources outside the fact sheet; declaring them keeps agents using the same one apart
+    // Template sources and build output (main.rs, Cargo.toml or pom.xml, target/)
+    ServerSource,
+    // static/ directory the server serves the frontend from
+    StaticFiles,
+    // README.md of the template project
+    ServerReadme,
+    // Port 8080 the backend developers start the server on
+    ServerPort,
+}
+
+impl FactField {
+    pub const ALL: [FactField; 15] = [
+        FactField::ProjectDescription,
+        FactField::BackendLanguage,
+        FactField::ReferenceImages,
+        FactField::ProjectScope,
+        FactField::ExternalUrls,
+        FactField::DataModel,
+        FactField::BackendCode,
+        FactField::ApiEndpointSchema,
+        FactField::RouteTestResults,
+        FactField::FrontendCode,
+        FactField::Documentation,
+        FactField::ServerSource,
+        FactField::StaticFiles,
+        FactField::ServerReadme,
+        FactField::ServerPort,
+    ];
+}
+
+impl FactSheet {
+    // Copy the given fields from an agent's working copy of the fact sheet
+    pub fn merge_fields(&mut self, updated: &FactSheet, fields: &[FactField]) {
+        for field in fields {
+            match field {
+                FactField::ProjectDescription => {
+                    self.project_description = updated.project_description.clone()
+                }
+                FactField::BackendLanguage => self.backend_language = updated.backend_language,
+                FactField::ReferenceImages => {
+                    self.reference_images = updated.reference_images.clone()
+                }
+                FactField::ProjectScope => self.project_scope = updated.project_scope,
+                FactField::ExternalUrls => self.external_urls = updated.external_urls.clone(),
+                FactField::DataModel => self.data_model = updated.data_model.clone(),
+                FactField::BackendCode => self.backend_code = updated.backend_code.clone(),
+                FactField::ApiEndpointSchema => {
+                    self.api_endpoint_schema = updated.api_endpoint_schema.clone()
+                }
+                FactField::RouteTestResults => {
+                    self.route_test_results = updated.route_test_results.clone()
+                }
+                FactField::FrontendCode => self.frontend_code = updated.frontend_code.clone(),
+                FactField::Documentation => self.documentation = updated.documentation.clone(),
+                // Live on disk or the network, nothing to copy
+                FactField::ServerSource
+                | FactField::StaticFiles
+                | FactField::ServerReadme
+                | FactField::ServerPort => {}
+            }
+        }
+    }
 }
 
 #[async_trait]
 pub trait SpecialFunctions: Debug {
     fn get_attributes_from_agent(&self) -> &BasicAgent;
+
+    // Fields the agent uses; undeclared agents are assumed to touch everything and run alone
+    fn reads(&self) -> Vec<FactField> {
+        FactField::ALL.to_vec()
+    }
+
+    // Fields the agent changes; only these are merged back into the shared fact sheet
+    fn writes(&self) -> Vec<FactField> {
+        FactField::ALL.to_vec()
+    }
+
+    // Times the agent rewrote its output to fix reported bugs
+    fn bug_fix_iterations(&self) -> u8 {
+        0
+    }
+
+    // Start over before a retry: back to Discovery with bug counters and errors cleared
+    fn reset_for_retry(&mut self);
+
     async fn execute(
         &mut self,
         factsheet: &mut FactSheet,
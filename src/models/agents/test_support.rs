@@ -0,0 +1,63 @@
+use crate::helpers::general::copy_project;
+use crate::models::agents::agent_traits::{FactSheet, RouteObject};
+
+use serde_json::Value;
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+
+// Fact sheet with only the project description filled in
+pub fn factsheet(project_description: &str) -> FactSheet {
+    serde_json::from_value(serde_json::json!({
+        "project_description": project_description,
+        "project_scope": null,
+        "external_urls": null,
+        "backend_code": null,
+        "api_endpoint_schema": null
+    }))
+    .expect("Invalid test fact sheet")
+}
+
+// Route taking no request body and returning no response body
+pub fn route(method: &str, path: &str) -> RouteObject {
+    route_with_bodies(method, path, Value::from("None"), Value::from("None"))
+}
+
+pub fn route_with_bodies(
+    method: &str,
+    path: &str,
+    request_body: Value,
+    response: Value,
+) -> RouteObject {
+    RouteObject {
+        is_route_dynamic: path.contains('{').to_string(),
+        method: method.to_string(),
+        request_body,
+        response,
+        route: path.to_string(),
+    }
+}
+
+// Empty directory under the system temp dir, unique to this test process
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir: PathBuf = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("Failed to create temp dir");
+    dir
+}
+
+// Scratch copy of a web template, so generated code never lands in the tracked one; cargo
+// builds share the template's target dir and only recompile the server crate
+pub fn copy_template(template: &str, name: &str) -> PathBuf {
+    let dir: PathBuf = temp_dir(name);
+    copy_project(Path::new(template), &dir).expect("Failed to copy web template");
+    let target: PathBuf = Path::new(template).join("target");
+    if target.is_dir() {
+        symlink(
+            target.canonicalize().expect("Bad target dir"),
+            dir.join("target"),
+        )
+        .expect("Failed to link target dir");
+    }
+    dir
+}
@@ -1 +1,3 @@
+pub mod agent_scheduler;
 pub mod managing_agent;
+pub mod project_report;
@@ -0,0 +1,709 @@
+use crate::apis::usage_tracker::{UsageLedger, with_usage_ledger};
+use crate::helpers::command_line::PrintCommand;
+use crate::models::agent_basic::basic_agent::{AgentState, BasicAgent};
+use crate::models::agent_basic::basic_traits::BasicTraits;
+use crate::models::agents::agent_traits::{FactField, FactSheet, SpecialFunctions};
+
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::error::Error;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Earlier agents each agent waits for: one writes what the other reads or writes, or reads what it writes
+pub fn agent_dependencies(declarations: &[(Vec<FactField>, Vec<FactField>)]) -> Vec<Vec<usize>> {
+    let overlaps = |a: &[FactField], b: &[FactField]| a.iter().any(|field| b.contains(field));
+    declarations
+        .iter()
+        .enumerate()
+        .map(|(later, (later_reads, later_writes))| {
+            declarations[..later]
+                .iter()
+                .enumerate()
+                .filter(|(_, (earlier_reads, earlier_writes))| {
+                    overlaps(earlier_writes, later_reads)
+                        || overlaps(earlier_writes, later_writes)
+                        || overlaps(earlier_reads, later_writes)
+                })
+                .map(|(earlier, _)| earlier)
+                .collect()
+        })
+        .collect()
+}
+
+// What happens when an agent returns an error ("--on-failure" or AGENT_FAILURE_POLICY)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FailurePolicy {
+    // Start no more agents; those already running finish
+    #[default]
+    Abort,
+    // Run the agent again on a fresh copy of the fact sheet up to this many times, then abort
+    Retry(u8),
+    // Drop the agent's changes, skip every agent depending on it and carry on with the rest
+    Skip,
+}
+
+impl FailurePolicy {
+    // "abort", "skip", "retry" (once) or "retry:<times>"
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "abort" => Some(FailurePolicy::Abort),
+            "skip" => Some(FailurePolicy::Skip),
+            "retry" => Some(FailurePolicy::Retry(1)),
+            other => other
+                .strip_prefix("retry:")
+                .and_then(|times| times.parse().ok())
+                .map(FailurePolicy::Retry),
+        }
+    }
+
+    pub fn from_env() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        match std::env::var("AGENT_FAILURE_POLICY") {
+            Ok(value) => Self::parse(&value).ok_or_else(|| {
+                format!(
+                    "AGENT_FAILURE_POLICY must be abort, skip, retry or retry:<times>, got {}",
+                    value
+                )
+                .into()
+            }),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgentOutcome {
+    Succeeded,
+    Failed,
+    // Stopped by the budget or an aborted pipeline before it started
+    NotRun,
+    // Depends on an agent that failed under the skip policy
+    Skipped,
+}
+
+impl AgentOutcome {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AgentOutcome::Succeeded => "succeeded",
+            AgentOutcome::Failed => "failed",
+            AgentOutcome::NotRun => "not run",
+            AgentOutcome::Skipped => "skipped",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentRun {
+    pub position: String,
+    pub dependencies: Vec<usize>,
+    pub outcome: AgentOutcome,
+    // State the agent was left in after its last attempt
+    pub state: AgentState,
+    pub attempts: u8,
+    // Offset of the first attempt from the start of the schedule
+    pub started: Duration,
+    // Summed over all attempts
+    pub elapsed: Duration,
+    pub errors: Vec<String>,
+    pub bug_fix_iterations: u8,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScheduleReport {
+    // One entry per agent, in the order they were added
+    pub runs: Vec<AgentRun>,
+    pub wall_time: Duration,
+    // Why the pipeline stopped before every agent ran
+    pub stopped: Option<String>,
+}
+
+impl ScheduleReport {
+    // Chain of dependent agents with the longest total run time, and that time
+    pub fn critical_path(&self) -> (Vec<usize>, Duration) {
+        let mut longest: Vec<(Duration, Option<usize>)> = vec![];
+        for run in &self.runs {
+            let (before, previous) = run
+                .dependencies
+                .iter()
+                .filter(|&&dependency| self.runs[dependency].attempts > 0)
+                .map(|&dependency| (longest[dependency].0, Some(dependency)))
+                .max_by_key(|(length, _)| *length)
+                .unwrap_or((Duration::ZERO, None));
+            longest.push((before + run.elapsed, previous));
+        }
+
+        let Some((end, &(total, _))) = longest
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, (length, _))| *length)
+        else {
+            return (vec![], Duration::ZERO);
+        };
+        let mut path: Vec<usize> = vec![end];
+        while let Some(previous) = longest[path[path.len() - 1]].1 {
+            path.push(previous);
+        }
+        path.reverse();
+        (path, total)
+    }
+
+    pub fn print(&self, agent_position: &str) {
+        for run in &self.runs {
+            if matches!(run.outcome, AgentOutcome::NotRun | AgentOutcome::Skipped) {
+                let run_msg: String = format!("{} {}", run.position, run.outcome.label());
+                PrintCommand::Issue.print_agent_msg(agent_position, &run_msg);
+                continue;
+            }
+            let waited_on: Vec<&str> = run
+                .dependencies
+                .iter()
+                .map(|&dependency| self.runs[dependency].position.as_str())
+                .collect();
+            let run_msg: String = format!(
+                "{} {} ({:?}) after {} attempt(s) and {} bug fix(es), started at {:.1}s, took {:.1}s{}",
+                run.position,
+                run.outcome.label(),
+                run.state,
+                run.attempts,
+                run.bug_fix_iterations,
+                run.started.as_secs_f64(),
+                run.elapsed.as_secs_f64(),
+                if waited_on.is_empty() {
+                    String::new()
+                } else {
+                    format!(", after {}", waited_on.join(", "))
+                }
+            );
+            match run.outcome {
+                AgentOutcome::Succeeded => {
+                    PrintCommand::UnitTest.print_agent_msg(agent_position, &run_msg)
+                }
+                _ => PrintCommand::Issue.print_agent_msg(agent_position, &run_msg),
+            }
+            for error in &run.errors {
+                let error_msg: String = format!("{} error: {}", run.position, error);
+                PrintCommand::Issue.print_agent_msg(agent_position, &error_msg);
+            }
+        }
+
+        let (path, total) = self.critical_path();
+        let path_names: Vec<String> = path
+            .iter()
+            .map(|&index| {
+                let run: &AgentRun = &self.runs[index];
+                format!("{} ({:.1}s)", run.position, run.elapsed.as_secs_f64())
+            })
+            .collect();
+        let path_msg: String = format!(
+            "Critical path {:.1}s of {:.1}s wall time: {}",
+            total.as_secs_f64(),
+            self.wall_time.as_secs_f64(),
+            path_names.join(" -> ")
+        );
+        PrintCommand::UnitTest.print_agent_msg(agent_position, &path_msg);
+    }
+}
+
+type Attempt = (
+    usize,
+    Box<dyn SpecialFunctions>,
+    FactSheet,
+    Result<(), String>,
+    Duration,
+);
+
+// One attempt of one agent on its own copy of the fact sheet
+async fn run_attempt(
+    index: usize,
+    mut agent: Box<dyn SpecialFunctions>,
+    mut working_copy: FactSheet,
+    ledger: Arc<UsageLedger>,
+) -> Attempt {
+    let started: Instant = Instant::now();
+    let result: Result<(), Box<dyn Error>> =
+        with_usage_ledger(ledger, agent.execute(&mut working_copy)).await;
+    let result: Result<(), String> = result.map_err(|e| e.to_string());
+    (index, agent, working_copy, result, started.elapsed())
+}
+
+// Mark every agent that needs the failed one, directly or through others, as skipped
+fn skip_dependents(runs: &mut [AgentRun], failed: usize) -> Vec<usize> {
+    let mut blocked: Vec<usize> = vec![failed];
+    // Dependencies always point to earlier agents, so one pass finds the whole chain
+    for (later, run) in runs.iter_mut().enumerate().skip(failed + 1) {
+        let is_blocked: bool = run
+            .dependencies
+            .iter()
+            .any(|dependency| blocked.contains(dependency));
+        if is_blocked {
+            run.outcome = AgentOutcome::Skipped;
+            blocked.push(later);
+        }
+    }
+    blocked.split_off(1)
+}
+
+// Runs each agent once its dependencies finished, independent agents concurrently on their own
+// copy of the fact sheet; only the fields a successful agent declares it writes are merged back.
+// Agents are polled on this one task, so they must not block it (use tokio::process or spawn_blocking)
+pub async fn run_agents(
+    agents: &mut Vec<Box<dyn SpecialFunctions>>,
+    factsheet: &mut FactSheet,
+    usage: &Arc<UsageLedger>,
+    policy: FailurePolicy,
+    agent_position: &str,
+) -> ScheduleReport {
+    let declarations: Vec<(Vec<FactField>, Vec<FactField>)> = agents
+        .iter()
+        .map(|agent| (agent.reads(), agent.writes()))
+        .collect();
+    let dependencies: Vec<Vec<usize>> = agent_dependencies(&declarations);
+    let mut runs: Vec<AgentRun> = agents
+        .iter()
+        .zip(&dependencies)
+        .map(|(agent, dependencies)| AgentRun {
+            position: agent.get_attributes_from_agent().get_position().clone(),
+            dependencies: dependencies.clone(),
+            outcome: AgentOutcome::NotRun,
+            state: *agent.get_attributes_from_agent().get_state(),
+            attempts: 0,
+            started: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            errors: vec![],
+            bug_fix_iterations: 0,
+        })
+        .collect();
+    let mut slots: Vec<Option<Box<dyn SpecialFunctions>>> = agents.drain(..).map(Some).collect();
+    let mut finished: Vec<bool> = vec![false; slots.len()];
+    let mut stopped: Option<String> = None;
+    let schedule_start: Instant = Instant::now();
+    let mut running = FuturesUnordered::new();
+
+    loop {
+        for index in 0..slots.len() {
+            let is_ready: bool = runs[index].attempts == 0
+                && runs[index].outcome != AgentOutcome::Skipped
+                && dependencies[index]
+                    .iter()
+                    .all(|&dependency| finished[dependency]);
+            if stopped.is_some() || !is_ready {
+                continue;
+            }
+            // Stop cleanly once the run budget is spent; running agents still finish
+            if let Some(reason) = usage.budget_exceeded() {
+                let stop_msg: String = format!("Stopping project, {}", reason);
+                PrintCommand::Issue.print_agent_msg(agent_position, &stop_msg);
+                stopped = Some(reason);
+                continue;
+            }
+            let Some(agent) = slots[index].take() else {
+                continue;
+            };
+
+            let attributes: &BasicAgent = agent.get_attributes_from_agent();
+            let start_msg: String = format!("Starting: {}", attributes.get_objective());
+            PrintCommand::UnitTest.print_agent_msg(attributes.get_position(), &start_msg);
+            runs[index].attempts = 1;
+            runs[index].started = schedule_start.elapsed();
+            running.push(run_attempt(
+                index,
+                agent,
+                factsheet.clone(),
+                Arc::clone(usage),
+            ));
+        }
+
+        let Some((index, mut agent, working_copy, result, elapsed)) = running.next().await else {
+            break;
+        };
+        let run: &mut AgentRun = &mut runs[index];
+        run.elapsed += elapsed;
+        run.state = *agent.get_attributes_from_agent().get_state();
+        run.bug_fix_iterations = agent.bug_fix_iterations();
+        match result {
+            Ok(()) => {
+                factsheet.merge_fields(&working_copy, &agent.writes());
+                run.outcome = AgentOutcome::Succeeded;
+            }
+            Err(error) => {
+                let error_msg: String = format!("{} failed: {}", run.position, error);
+                PrintCommand::Issue.print_agent_msg(agent_position, &error_msg);
+                run.errors.push(error);
+                run.outcome = AgentOutcome::Failed;
+                match policy {
+                    // Agents start over instead of resuming with their bug counters used up
+                    FailurePolicy::Retry(times)
+                        if run.attempts <= times
+                            && stopped.is_none()
+                            && usage.budget_exceeded().is_none() =>
+                    {
+                        run.attempts += 1;
+                        agent.reset_for_retry();
+                        debug_assert!(
+                            agent.get_attributes_from_agent().get_memory().is_empty(),
+                            "{} kept its memory across a retry",
+                            run.position
+                        );
+                        running.push(run_attempt(
+                            index,
+                            agent,
+                            factsheet.clone(),
+                            Arc::clone(usage),
+                        ));
+                        continue;
+                    }
+                    FailurePolicy::Skip => {
+                        for skipped in skip_dependents(&mut runs, index) {
+                            let skip_msg: String = format!(
+                                "Skipping {}, it depends on {}",
+                                runs[skipped].position, runs[index].position
+                            );
+                            PrintCommand::Issue.print_agent_msg(agent_position, &skip_msg);
+                        }
+                    }
+                    FailurePolicy::Abort | FailurePolicy::Retry(_) => {
+                        let stop_msg: String = format!("Aborting project, {}", error_msg);
+                        PrintCommand::Issue.print_agent_msg(agent_position, &stop_msg);
+                        stopped.get_or_insert(error_msg);
+                    }
+                }
+            }
+        }
+        finished[index] = true;
+        slots[index] = Some(agent);
+    }
+
+    agents.extend(slots.into_iter().flatten());
+    ScheduleReport {
+        runs,
+        wall_time: schedule_start.elapsed(),
+        stopped,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::usage_tracker::{Budget, PriceTable};
+    use crate::models::agents::test_support::factsheet;
+    use async_trait::async_trait;
+
+    // Sleeps, then writes its position into the fields it declares; fails the first `failures` runs
+    #[derive(Debug)]
+    struct SleepyAgent {
+        attributes: BasicAgent,
+        reads: Vec<FactField>,
+        writes: Vec<FactField>,
+        sleep: Duration,
+        failures: u8,
+    }
+
+    impl SleepyAgent {
+        fn new(position: &str, reads: &[FactField], writes: &[FactField], millis: u64) -> Self {
+            Self {
+                attributes: BasicAgent {
+                    objective: "Sleeps".to_string(),
+                    position: position.to_string(),
+                    state: AgentState::Discovery,
+                    memory: vec![],
+                },
+                reads: reads.to_vec(),
+                writes: writes.to_vec(),
+                sleep: Duration::from_millis(millis),
+                failures: 0,
+            }
+        }
+
+        fn failing(mut self, failures: u8) -> Self {
+            self.failures = failures;
+            self
+        }
+    }
+
+    #[async_trait]
+    impl SpecialFunctions for SleepyAgent {
+        fn get_attributes_from_agent(&self) -> &BasicAgent {
+            &self.attributes
+        }
+
+        fn reads(&self) -> Vec<FactField> {
+            self.reads.clone()
+        }
+
+        fn writes(&self) -> Vec<FactField> {
+            self.writes.clone()
+        }
+
+        fn reset_for_retry(&mut self) {
+            self.attributes.state = AgentState::Discovery;
+        }
+
+        async fn execute(
+            &mut self,
+            factsheet: &mut FactSheet,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            tokio::time::sleep(self.sleep).await;
+            // Also scribbles on a field it did not declare, which must not be merged
+            factsheet.project_description = self.attributes.position.clone();
+            for field in &self.writes {
+                match field {
+                    FactField::BackendCode => {
+                        factsheet.backend_code = Some(self.attributes.position.clone())
+                    }
+                    FactField::FrontendCode => {
+                        factsheet.frontend_code = Some(self.attributes.position.clone())
+                    }
+                    FactField::Documentation => {
+                        factsheet.documentation = Some(self.attributes.position.clone())
+                    }
+                    _ => {}
+                }
+            }
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err(format!("{} broke", self.attributes.position).into());
+            }
+            self.attributes.state = AgentState::Finished;
+            Ok(())
+        }
+    }
+
+    // Backend fails `failures` times, frontend needs its code and docs does not
+    async fn run_with_failing_backend(
+        failures: u8,
+        policy: FailurePolicy,
+    ) -> (ScheduleReport, FactSheet) {
+        use FactField::*;
+        let mut agents: Vec<Box<dyn SpecialFunctions>> = vec![
+            Box::new(
+                SleepyAgent::new("Backend", &[ProjectDescription], &[BackendCode], 10)
+                    .failing(failures),
+            ),
+            Box::new(SleepyAgent::new(
+                "Frontend",
+                &[BackendCode],
+                &[FrontendCode],
+                10,
+            )),
+            Box::new(SleepyAgent::new(
+                "Docs",
+                &[ProjectDescription],
+                &[Documentation],
+                10,
+            )),
+        ];
+        let mut factsheet: FactSheet = factsheet("todo list");
+        let usage: Arc<UsageLedger> =
+            Arc::new(UsageLedger::new(Budget::default(), PriceTable::default()));
+        let report: ScheduleReport = run_agents(
+            &mut agents,
+            &mut factsheet,
+            &usage,
+            policy,
+            "Project Manager",
+        )
+        .await;
+        (report, factsheet)
+    }
+
+    #[test]
+    fn tests_parses_failure_policy() {
+        assert_eq!(FailurePolicy::parse("Abort"), Some(FailurePolicy::Abort));
+        assert_eq!(FailurePolicy::parse("skip"), Some(FailurePolicy::Skip));
+        assert_eq!(FailurePolicy::parse("retry"), Some(FailurePolicy::Retry(1)));
+        assert_eq!(
+            FailurePolicy::parse("retry:3"),
+            Some(FailurePolicy::Retry(3))
+        );
+        assert_eq!(FailurePolicy::parse("retry:many"), None);
+        assert_eq!(FailurePolicy::parse("ignore"), None);
+        assert_eq!(FailurePolicy::default(), FailurePolicy::Abort);
+    }
+
+    #[tokio::test]
+    async fn tests_failure_policy_decides_what_runs() {
+        let outcomes = |report: &ScheduleReport| -> Vec<AgentOutcome> {
+            report.runs.iter().map(|run| run.outcome).collect()
+        };
+
+        // Skip drops the failed agent's writes and its dependents, independent agents keep going
+        let (report, factsheet) = run_with_failing_backend(1, FailurePolicy::Skip).await;
+        assert_eq!(
+            outcomes(&report),
+            vec![
+                AgentOutcome::Failed,
+                AgentOutcome::Skipped,
+                AgentOutcome::Succeeded
+            ]
+        );
+        assert_eq!(report.runs[1].attempts, 0);
+        assert!(factsheet.frontend_code.is_none());
+        assert_eq!(factsheet.documentation.as_deref(), Some("Docs"));
+        assert_eq!(report.runs[0].errors, vec!["Backend broke".to_string()]);
+        assert_eq!(report.runs[0].state, AgentState::Discovery);
+        assert!(factsheet.backend_code.is_none());
+        assert!(report.stopped.is_none());
+
+        // Abort starts nothing new, the independent docs agent was already running
+        let (report, factsheet) = run_with_failing_backend(1, FailurePolicy::Abort).await;
+        assert_eq!(
+            outcomes(&report),
+            vec![
+                AgentOutcome::Failed,
+                AgentOutcome::NotRun,
+                AgentOutcome::Succeeded
+            ]
+        );
+        assert!(factsheet.frontend_code.is_none());
+        assert_eq!(
+            report.stopped.as_deref(),
+            Some("Backend failed: Backend broke")
+        );
+
+        // Retry runs the agent again until it succeeds or the retries run out
+        let (report, factsheet) = run_with_failing_backend(2, FailurePolicy::Retry(2)).await;
+        assert_eq!(
+            outcomes(&report),
+            vec![
+                AgentOutcome::Succeeded,
+                AgentOutcome::Succeeded,
+                AgentOutcome::Succeeded
+            ]
+        );
+        assert_eq!(report.runs[0].attempts, 3);
+        assert_eq!(report.runs[0].errors.len(), 2);
+        assert_eq!(report.runs[0].state, AgentState::Finished);
+        assert_eq!(factsheet.backend_code.as_deref(), Some("Backend"));
+
+        let (report, _) = run_with_failing_backend(2, FailurePolicy::Retry(1)).await;
+        assert_eq!(report.runs[0].attempts, 2);
+        assert_eq!(report.runs[1].outcome, AgentOutcome::NotRun);
+        assert!(report.stopped.is_some());
+    }
+
+    #[test]
+    fn tests_skips_transitive_dependents() {
+        let mut runs: Vec<AgentRun> = [vec![], vec![0], vec![], vec![1], vec![2, 3]]
+            .into_iter()
+            .enumerate()
+            .map(|(index, dependencies)| AgentRun {
+                position: format!("Agent {}", index),
+                dependencies,
+                outcome: AgentOutcome::NotRun,
+                state: AgentState::Discovery,
+                attempts: 0,
+                started: Duration::ZERO,
+                elapsed: Duration::ZERO,
+                errors: vec![],
+                bug_fix_iterations: 0,
+            })
+            .collect();
+
+        assert_eq!(skip_dependents(&mut runs, 0), vec![1, 3, 4]);
+        let outcomes: Vec<AgentOutcome> = runs.iter().map(|run| run.outcome).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                AgentOutcome::NotRun,
+                AgentOutcome::Skipped,
+                AgentOutcome::NotRun,
+                AgentOutcome::Skipped,
+                AgentOutcome::Skipped
+            ]
+        );
+    }
+
+    #[test]
+    fn tests_dependencies_follow_declared_fields() {
+        use FactField::*;
+        let declarations: Vec<(Vec<FactField>, Vec<FactField>)> = vec![
+            (vec![ProjectDescription], vec![ProjectScope, ExternalUrls]),
+            (vec![ProjectDescription, ProjectScope], vec![DataModel]),
+            (
+                vec![ProjectDescription, ProjectScope, ExternalUrls, DataModel],
+                vec![BackendCode, ApiEndpointSchema, ServerSource, ServerPort],
+            ),
+            (
+                vec![BackendCode, ApiEndpointSchema, ServerSource, StaticFiles],
+                vec![RouteTestResults],
+            ),
+            (
+                vec![ProjectDescription, BackendCode, ApiEndpointSchema],
+                vec![FrontendCode, BackendCode, ServerSource, StaticFiles],
+            ),
+            (
+                vec![ProjectDescription, ApiEndpointSchema],
+                vec![Documentation, ServerReadme],
+            ),
+        ];
+        assert_eq!(
+            agent_dependencies(&declarations),
+            vec![vec![], vec![0], vec![0, 1], vec![2], vec![2, 3], vec![2],]
+        );
+
+        // Agents sharing nothing but a resource off the fact sheet still take turns
+        let declarations: Vec<(Vec<FactField>, Vec<FactField>)> = vec![
+            (vec![], vec![BackendCode, ServerPort]),
+            (vec![], vec![FrontendCode, ServerPort]),
+            (vec![ServerReadme], vec![Documentation]),
+            (vec![], vec![ServerReadme]),
+        ];
+        assert_eq!(
+            agent_dependencies(&declarations),
+            vec![vec![], vec![0], vec![], vec![2]]
+        );
+    }
+
+    #[tokio::test]
+    async fn tests_runs_independent_agents_concurrently() {
+        use FactField::*;
+        let mut agents: Vec<Box<dyn SpecialFunctions>> = vec![
+            Box::new(SleepyAgent::new(
+                "Backend",
+                &[ProjectDescription],
+                &[BackendCode],
+                50,
+            )),
+            Box::new(SleepyAgent::new(
+                "Frontend",
+                &[BackendCode],
+                &[FrontendCode],
+                300,
+            )),
+            Box::new(SleepyAgent::new(
+                "Docs",
+                &[BackendCode],
+                &[Documentation],
+                300,
+            )),
+        ];
+        let mut factsheet: FactSheet = factsheet("todo list");
+        let usage: Arc<UsageLedger> =
+            Arc::new(UsageLedger::new(Budget::default(), PriceTable::default()));
+
+        let report: ScheduleReport = run_agents(
+            &mut agents,
+            &mut factsheet,
+            &usage,
+            FailurePolicy::Skip,
+            "Project Manager",
+        )
+        .await;
+
+        // Frontend and docs overlap, so the run takes far less than the 650ms sum
+        assert!(report.wall_time < Duration::from_millis(550));
+        assert_eq!(factsheet.project_description, "todo list");
+        assert_eq!(factsheet.backend_code.as_deref(), Some("Backend"));
+        assert_eq!(factsheet.frontend_code.as_deref(), Some("Frontend"));
+        assert_eq!(factsheet.documentation.as_deref(), Some("Docs"));
+
+        let (path, total) = report.critical_path();
+        assert_eq!(path.len(), 2);
+        assert_eq!(path[0], 0);
+        assert!(total >= Duration::from_millis(350));
+        let positions: Vec<&str> = agents
+            .iter()
+            .map(|agent| agent.get_attributes_from_agent().position.as_str())
+            .collect();
+        assert_eq!(positions, vec!["Backend", "Frontend", "Docs"]);
+    }
+}
@@ -1,87 +1,392 @@
-use crate::models::agent_basic::basic_agent::{AgentState, BasicAgent};
-use crate::models::agents::agent_traits::{FactSheet, SpecialFunctions};
+use crate::models::agent_basic::basic_agent::BasicAgent;
+use crate::models::agent_basic::basic_traits::BasicTraits;
+use crate::models::agents::agent_traits::{
+    BackendLanguage, FactSheet, RouteObject, SpecialFunctions,
+};
 
 use crate::ai_functions::aifunc_managing::convert_user_input_to_goal;
-use crate::helpers::general::ai_task_request;
+use crate::apis::usage_tracker::{UsageLedger, with_usage_ledger};
+use crate::helpers::general::ai_task_request_with_images;
+use crate::helpers::image_input::ImageInput;
 use crate::models::agents::agent_architect::AgentSolutionArchitect;
 use crate::models::agents::agent_backend::AgentBackendDeveloper;
+use crate::models::agents::agent_data_model::AgentDataModelDesigner;
+use crate::models::agents::agent_docs::AgentDocumentationWriter;
+use crate::models::agents::agent_frontend::AgentFrontendDeveloper;
+use crate::models::agents::agent_qa::AgentQaEngineer;
+use crate::models::agents::agent_springboot::AgentSpringBootDeveloper;
+use crate::models::agents_manager::agent_scheduler::{FailurePolicy, ScheduleReport, run_agents};
+use crate::models::agents_manager::project_report::ProjectReport;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const MANAGER_POSITION: &str = "Project Manager";
+
+// Agents that build a project with the given backend in project_dir, in the order they are scheduled
+fn project_agents(
+    backend_language: BackendLanguage,
+    project_dir: &Path,
+) -> Vec<Box<dyn SpecialFunctions>> {
+    let backend: Box<dyn SpecialFunctions> = match backend_language {
+        BackendLanguage::Rust => Box::new(AgentBackendDeveloper::new(project_dir)),
+        BackendLanguage::Java => Box::new(AgentSpringBootDeveloper::new(project_dir)),
+    };
+    vec![
+        Box::new(AgentSolutionArchitect::new()),
+        Box::new(AgentDataModelDesigner::new()),
+        backend,
+        Box::new(AgentQaEngineer::new(backend_language, project_dir)),
+        Box::new(AgentFrontendDeveloper::new(backend_language, project_dir)),
+        Box::new(AgentDocumentationWriter::new(backend_language, project_dir)),
+    ]
+}
+
+// Positions of the manager and every agent it creates for the given backend
+pub fn project_positions(backend_language: BackendLanguage) -> Vec<String> {
+    std::iter::once(MANAGER_POSITION.to_string())
+        .chain(
+            project_agents(backend_language, Path::new(backend_language.template_dir()))
+                .iter()
+                .map(|agent| agent.get_attributes_from_agent().get_position().clone()),
+        )
+        .collect()
+}
 
 #[derive(Debug)]
 pub struct ManagingAgent {
     attributes: BasicAgent,
     factsheet: FactSheet,
     agents: Vec<Box<dyn SpecialFunctions>>,
+    usage: Arc<UsageLedger>,
+    failure_policy: FailurePolicy,
+    // Web template the agents generate the server in
+    project_dir: PathBuf,
 }
 
 impl ManagingAgent {
-    pub async fn new(usr_req: String) -> Result<Self, Box<dyn std::error::Error>> {
-        let position: String = "Project Manager".to_string();
-
-        let attributes: BasicAgent = BasicAgent {
-            objective: "Managing agents who are building an excellent website".to_string(),
-            position: position.clone(),
-            state: AgentState::Discovery,
-            memory: vec![],
-        };
+    pub async fn new(
+        usr_req: String,
+        reference_images: Vec<ImageInput>,
+        backend_language: BackendLanguage,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let position: String = MANAGER_POSITION.to_string();
+
+        let attributes: BasicAgent = BasicAgent::new(
+            "Managing agents who are building an excellent website".to_string(),
+            position.clone(),
+        );
 
-        let project_description: String = ai_task_request(
-            usr_req,
-            &position,
-            get_function_string!(print_project_scope),
-            convert_user_input_to_goal,
+        // Token usage and budget for the whole run, starting with this first call
+        let usage: Arc<UsageLedger> = Arc::new(
+            UsageLedger::from_env().map_err(|e| -> Box<dyn std::error::Error> { e })?,
+        );
+        let failure_policy: FailurePolicy =
+            FailurePolicy::from_env().map_err(|e| -> Box<dyn std::error::Error> { e })?;
+
+        let project_description: String = with_usage_ledger(
+            Arc::clone(&usage),
+            ai_task_request_with_images(
+                usr_req,
+                &position,
+                get_function_string!(convert_user_input_to_goal),
+                convert_user_input_to_goal,
+                &reference_images,
+            ),
         )
-        .await;
+        .await?;
 
         let agents: Vec<Box<dyn SpecialFunctions>> = vec![];
         let factsheet: FactSheet = FactSheet {
             project_description,
+            backend_language,
             project_scope: None,
             external_urls: None,
             backend_code: None,
             api_endpoint_schema: None,
+            data_model: None,
+            route_test_results: None,
+            frontend_code: None,
+            documentation: None,
+            reference_images,
         };
 
         Ok(Self {
             attributes,
             factsheet,
             agents,
+            usage,
+            failure_policy,
+            project_dir: PathBuf::from(backend_language.template_dir()),
         })
     }
 
+    // Overrides AGENT_FAILURE_POLICY, e.g. from "--on-failure"
+    pub fn set_failure_policy(&mut self, failure_policy: FailurePolicy) {
+        self.failure_policy = failure_policy;
+    }
+
+    // Routes the backend agent extracted from the generated server
+    pub fn api_endpoint_schema(&self) -> Option<&Vec<RouteObject>> {
+        self.factsheet.api_endpoint_schema.as_ref()
+    }
+
     fn add_agent(&mut self, agent: Box<dyn SpecialFunctions>) {
         self.agents.push(agent);
     }
 
     fn create_agents(&mut self) {
-        self.add_agent(Box::new(AgentSolutionArchitect::new()));
-        self.add_agent(Box::new(AgentBackendDeveloper::new()));
+        for agent in project_agents(self.factsheet.backend_language, &self.project_dir) {
+            self.add_agent(agent);
+        }
     }
 
-    pub async fn execute_project(&mut self) {
+    pub async fn execute_project(&mut self) -> ProjectReport {
         self.create_agents();
-        for agent in &mut self.agents {
-            let _agent_res: Result<(), Box<dyn std::error::Error>> =
-                agent.execute(&mut self.factsheet).await;
-/* 
-            let agent_info: &BasicAgent = agent.get_attributes_from_agent();
-            dbg!(agent_info); */
-        }
+        let schedule: ScheduleReport = run_agents(
+            &mut self.agents,
+            &mut self.factsheet,
+            &self.usage,
+            self.failure_policy,
+            &self.attributes.position,
+        )
+        .await;
+
+        let report: ProjectReport = ProjectReport {
+            policy: self.failure_policy,
+            schedule,
+            route_test_results: self.factsheet.route_test_results.clone(),
+            usage: self.usage.totals(),
+        };
+        self.usage.print_report();
+        report.print(&self.attributes.position);
+        report
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::apis::llm_provider::{LlmProvider, with_provider};
+    use crate::apis::scripted_provider::ScriptedProvider;
+    use crate::apis::usage_tracker::{Budget, PriceTable};
+    use crate::helpers::command_line::set_auto_confirm;
+    use crate::helpers::general::{WEBSERVER_RUST_PATH, read_exec_main_contents};
+    use crate::models::agent_basic::basic_agent::AgentState;
+    use crate::models::agents_manager::agent_scheduler::AgentOutcome;
+    use crate::models::agents::test_support::copy_template;
+    use crate::models::general::llm::Message;
+    use std::fs;
+    use std::sync::Arc;
+
+    const TEMPLATE_ROUTES_JSON: &str = r#"[
+      {
+        "route": "/forex-prices",
+        "is_route_dynamic": "false",
+        "method": "post",
+        "request_body": { "pairs": [{ "base": "string", "quote": "string" }] },
+        "response": [{ "base": "string", "quote": "string", "price": "number", "source": "string" }]
+      }
+    ]"#;
+
+    const FOREX_MODEL_JSON: &str = r#"{"entities": [
+      {"name": "ForexPair", "fields": [{"name": "id", "field_type": "integer"}, {"name": "base", "field_type": "string"}, {"name": "quote", "field_type": "string"}]},
+      {"name": "ForexPrice", "fields": [{"name": "id", "field_type": "integer"}, {"name": "base", "field_type": "string"}, {"name": "quote", "field_type": "string"}, {"name": "price", "field_type": "number"}, {"name": "source", "field_type": "string"}]}
+    ]}"#;
 
+    #[test]
+    fn tests_project_positions_follow_backend_language() {
+        let rust: Vec<String> = project_positions(BackendLanguage::Rust);
+        assert_eq!(
+            rust,
+            [
+                "Project Manager",
+                "Solution Architect",
+                "Data Model Designer",
+                "Backend Developer",
+                "QA Engineer",
+                "Frontend Developer",
+                "Documentation Writer",
+            ]
+        );
+        let java: Vec<String> = project_positions(BackendLanguage::Java);
+        assert_eq!(java[3], "Spring Boot Developer");
+        assert_eq!(java.len(), rust.len());
+    }
+
+    // The backend, QA and frontend agents build and run the generated server with cargo, so the
+    // whole pipeline is opt-in: cargo test tests_managing_agent -- --ignored
     #[tokio::test]
+    #[ignore = "builds and runs the web template with cargo"]
     async fn tests_managing_agent() {
+        // Scripted replies keep the whole pipeline offline; generated pages and docs come from fixtures
+        let project_dir: PathBuf = copy_template(WEBSERVER_RUST_PATH, "managing_agent");
+        let backend_code: String = read_exec_main_contents(&project_dir).expect("Failed to read main.rs");
+        let frontend_code: String =
+            include_str!("../../../fixtures/generated/forex_index.html").to_string();
+        let readme: String = include_str!("../../../fixtures/generated/forex_README.md").to_string();
+        let provider: Arc<ScriptedProvider> = Arc::new(
+            ScriptedProvider::new()
+                .reply_to_function(
+                    "convert_user_input_to_goal",
+                    "build a webserver that fetches and tracks fitness progress",
+                )
+                .reply_to_function(
+                    "print_project_scope",
+                    r#"{"is_crud_required": true, "is_user_login_and_logout": false, "is_external_urls_required": false}"#,
+                )
+                .reply_to_function("print_data_model", FOREX_MODEL_JSON)
+                .reply_to_function("print_backend_webserver_code", &backend_code)
+                .reply_to_function("print_improved_webserver_code", &backend_code)
+                .reply_to_function("print_security_review", "[]")
+                // The template structs have no id, so both data model fix rounds run and the code is kept
+                .reply_to_function("print_fixed_code", &backend_code)
+                .reply_to_function("print_security_review", "[]")
+                .reply_to_function("print_fixed_code", &backend_code)
+                .reply_to_function("print_security_review", "[]")
+                .reply_to_function("print_rest_api_endpoints", TEMPLATE_ROUTES_JSON)
+                .reply_to_function("print_frontend_code", &format!("```html\n{}\n```", frontend_code))
+                .reply_to_function("print_project_readme", &readme),
+        );
+        set_auto_confirm(true);
+
         let user_request: &str = "I need a full website that fetches and track finest myfitness profress. Need to include timezone from the web";
-        let mut managing_agent: ManagingAgent = ManagingAgent::new(user_request.to_string())
-            .await
-            .expect("Failed to create ManagingAgent");
+        let (managing_agent, report): (ManagingAgent, ProjectReport) =
+            with_provider(provider.clone() as Arc<dyn LlmProvider>, async {
+                let mut managing_agent: ManagingAgent =
+                    ManagingAgent::new(user_request.to_string(), vec![], BackendLanguage::Rust)
+                        .await
+                        .expect("Failed to create ManagingAgent");
+                managing_agent.project_dir = project_dir.clone();
 
-        managing_agent.execute_project().await;
+                let report: ProjectReport = managing_agent.execute_project().await;
+                (managing_agent, report)
+            })
+            .await;
+
+        assert_eq!(provider.remaining_replies(), 0);
+        assert!(report.succeeded());
+        assert!(
+            report
+                .schedule
+                .runs
+                .iter()
+                .all(|run| run.outcome == AgentOutcome::Succeeded)
+        );
+        assert!(report.schedule.runs.iter().all(|run| run.state == AgentState::Finished));
+        assert_eq!(report.route_test_results, managing_agent.factsheet.route_test_results);
+        assert_eq!(report.usage.calls, 13);
+        assert!(managing_agent.factsheet.project_scope.is_some());
+        assert_eq!(managing_agent.factsheet.backend_code, Some(backend_code));
+        assert!(managing_agent.factsheet.api_endpoint_schema.is_some());
+        assert!(managing_agent.factsheet.data_model.is_some());
+        assert!(managing_agent.factsheet.route_test_results.is_some());
+        assert_eq!(managing_agent.factsheet.frontend_code, Some(frontend_code));
+        assert_eq!(managing_agent.factsheet.documentation.as_deref(), Some(readme.trim()));
+        assert_eq!(managing_agent.usage.totals().calls, 13);
 
         dbg!(managing_agent.factsheet);
+        let _ = fs::remove_dir_all(&project_dir);
+    }
+
+    #[tokio::test]
+    async fn tests_managing_agent_stops_on_budget() {
+        let provider: Arc<ScriptedProvider> = Arc::new(ScriptedProvider::new().reply_to_function(
+            "convert_user_input_to_goal",
+            "build a webserver that returns the current time",
+        ));
+
+        let (managing_agent, report): (ManagingAgent, ProjectReport) =
+            with_provider(provider.clone() as Arc<dyn LlmProvider>, async {
+                let mut managing_agent: ManagingAgent =
+                    ManagingAgent::new("Tell me the time".to_string(), vec![], BackendLanguage::Rust)
+                        .await
+                        .expect("Failed to create ManagingAgent");
+
+                // The goal summary alone is over a one token budget
+                let budget: Budget = Budget {
+                    max_tokens: Some(1),
+                    max_dollars: None,
+                };
+                managing_agent.usage = Arc::new(UsageLedger::new(budget, PriceTable::default()));
+                managing_agent.usage.record(
+                    "Project Manager",
+                    "convert_user_input_to_goal",
+                    "scripted",
+                    None,
+                    "Tell me the time",
+                    &managing_agent.factsheet.project_description,
+                );
+
+                let report: ProjectReport = managing_agent.execute_project().await;
+                (managing_agent, report)
+            })
+            .await;
+
+        assert!(managing_agent.usage.budget_exceeded().is_some());
+        assert!(!report.succeeded());
+        assert!(report.schedule.stopped.is_some());
+        assert!(report.schedule.runs.iter().all(|run| run.outcome == AgentOutcome::NotRun));
+        assert!(managing_agent.factsheet.project_scope.is_none());
+        assert_eq!(provider.calls().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn tests_managing_agent_attaches_reference_images() {
+        let provider: Arc<ScriptedProvider> = Arc::new(ScriptedProvider::new().reply_to_function(
+            "convert_user_input_to_goal",
+            "build a webserver serving the sketched todo pages",
+        ));
+        let wireframe: ImageInput =
+            ImageInput::from_data_url("data:image/png;base64,iVBORw0KGgo=").expect("Bad image");
+
+        let managing_agent: ManagingAgent = with_provider(
+            provider.clone() as Arc<dyn LlmProvider>,
+            ManagingAgent::new(
+                "Build the app in this sketch".to_string(),
+                vec![wireframe],
+                BackendLanguage::Rust,
+            ),
+        )
+        .await
+        .expect("Failed to create ManagingAgent");
+
+        let first_message: &Message = &provider.calls()[0][0];
+        assert_eq!(first_message.role, "user");
+        assert_eq!(first_message.content[1].r#type, "image_url");
+        assert_eq!(managing_agent.factsheet.reference_images.len(), 1);
+        // Agents formatting the factsheet into prompts never see the raw base64
+        assert!(!format!("{:?}", managing_agent.factsheet).contains("iVBORw0KGgo="));
+    }
+
+    #[tokio::test]
+    async fn tests_managing_agent_picks_backend_for_language() {
+        let provider: Arc<ScriptedProvider> = Arc::new(ScriptedProvider::new().reply_to_function(
+            "convert_user_input_to_goal",
+            "build a spring boot api that stores notes",
+        ));
+
+        let mut managing_agent: ManagingAgent = with_provider(
+            provider as Arc<dyn LlmProvider>,
+            ManagingAgent::new("Notes API in Java".to_string(), vec![], BackendLanguage::Java),
+        )
+        .await
+        .expect("Failed to create ManagingAgent");
+        managing_agent.create_agents();
+
+        let positions: Vec<&str> = managing_agent
+            .agents
+            .iter()
+            .map(|agent| agent.get_attributes_from_agent().position.as_str())
+            .collect();
+        assert_eq!(positions, vec![
+                "Solution Architect",
+                "Data Model Designer",
+                "Spring Boot Developer",
+                "QA Engineer",
+                "Frontend Developer",
+                "Documentation Writer"
+            ]);
+        assert_eq!(managing_agent.factsheet.backend_language, BackendLanguage::Java);
     }
 }
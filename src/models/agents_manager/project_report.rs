@@ -0,0 +1,74 @@
+use crate::apis::usage_tracker::UsageTotals;
+use crate::helpers::command_line::PrintCommand;
+use crate::models::agents::agent_traits::RouteTestResult;
+use crate::models::agents_manager::agent_scheduler::{
+    AgentOutcome, AgentRun, FailurePolicy, ScheduleReport,
+};
+
+// Everything execute_project learned about a run, returned instead of discarding agent results
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectReport {
+    pub policy: FailurePolicy,
+    // Per agent outcome, final state, attempts, duration, errors and bug fix iterations
+    pub schedule: ScheduleReport,
+    pub route_test_results: Option<Vec<RouteTestResult>>,
+    pub usage: UsageTotals,
+}
+
+impl ProjectReport {
+    pub fn succeeded(&self) -> bool {
+        self.schedule
+            .runs
+            .iter()
+            .all(|run| run.outcome == AgentOutcome::Succeeded)
+    }
+
+    pub fn failed_agents(&self) -> Vec<&AgentRun> {
+        self.schedule
+            .runs
+            .iter()
+            .filter(|run| run.outcome == AgentOutcome::Failed)
+            .collect()
+    }
+
+    pub fn print(&self, agent_position: &str) {
+        self.schedule.print(agent_position);
+
+        if let Some(results) = &self.route_test_results {
+            let passed: usize = results.iter().filter(|result| result.passed).count();
+            let routes_msg: String = format!("Endpoint tests: {}/{} passed", passed, results.len());
+            PrintCommand::UnitTest.print_agent_msg(agent_position, &routes_msg);
+            for result in results.iter().filter(|result| !result.passed) {
+                let failed_msg: String = format!(
+                    "{} {} failed: {}",
+                    result.method.to_uppercase(),
+                    result.url_path,
+                    result.detail
+                );
+                PrintCommand::Issue.print_agent_msg(agent_position, &failed_msg);
+            }
+        }
+
+        let usage_msg: String = format!(
+            "LLM usage: {} calls, {} tokens, ${:.4}",
+            self.usage.calls,
+            self.usage.total_tokens(),
+            self.usage.cost
+        );
+        PrintCommand::UnitTest.print_agent_msg(agent_position, &usage_msg);
+
+        let result_msg: String = match (&self.schedule.stopped, self.succeeded()) {
+            (Some(reason), _) => format!("Project stopped early: {}", reason),
+            (None, true) => "Project finished, every agent succeeded".to_string(),
+            (None, false) => format!(
+                "Project finished with {} failed agent(s) (policy {:?})",
+                self.failed_agents().len(),
+                self.policy
+            ),
+        };
+        match self.succeeded() {
+            true => PrintCommand::UnitTest.print_agent_msg(agent_position, &result_msg),
+            false => PrintCommand::Issue.print_agent_msg(agent_position, &result_msg),
+        }
+    }
+}
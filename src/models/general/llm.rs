@@ -1,15 +1,91 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Clone)]
+// Image part payload ({"url": "https://..." or "data:image/png;base64,..."})
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ImageUrl {
+    pub url: String,
+}
+
+// Content part of a message: "text" with text, or "image_url" with image_url
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Content {
     pub r#type: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<ImageUrl>,
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 pub struct Message {
     pub role: String,
+    // Empty on assistant messages that only request tool calls
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub content: Vec<Content>,
+    // Set on assistant messages asking for tools to be run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    // Set on "tool" messages carrying a tool's result back to the model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FunctionDefinition {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+// Rust function offered to the model ({"type": "function", "function": {...}})
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ToolDefinition {
+    pub r#type: String,
+    pub function: FunctionDefinition,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ToolCallFunction {
+    pub name: String,
+    // JSON encoded arguments, exactly as the model wrote them
+    pub arguments: String,
+}
+
+// Model's request to run one tool
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ToolCall {
+    pub id: String,
+    pub r#type: String,
+    pub function: ToolCallFunction,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct JsonSchemaFormat {
+    pub name: String,
+    pub schema: serde_json::Value,
+    pub strict: bool,
+}
+
+// Structured output request ({"type": "json_schema", "json_schema": {...}})
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ResponseFormat {
+    pub r#type: String,
+    pub json_schema: JsonSchemaFormat,
+}
+
+// Per-call knobs a provider applies on top of its own settings
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+pub struct ChatOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<ToolDefinition>>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -17,11 +93,21 @@ pub struct ChatCompletion {
     pub model: String,
     pub messages: Vec<Message>,
     pub temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<ToolDefinition>>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ApiMessage {
-    pub content: String,
+    // null when the model only asks for tool calls
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub tool_calls: Vec<ToolCall>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -29,7 +115,43 @@ pub struct ApiChoice {
     pub message: ApiMessage,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    #[serde(default)]
+    pub total_tokens: u64,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ApiResponse {
     pub choices: Vec<ApiChoice>,
+    pub usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiStreamDelta {
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiStreamChoice {
+    pub delta: ApiStreamDelta,
+}
+
+// One "data:" event of a streamed chat completion; usage arrives on the last chunk
+#[derive(Debug, Deserialize)]
+pub struct ApiStreamChunk {
+    #[serde(default)]
+    pub choices: Vec<ApiStreamChoice>,
+    pub usage: Option<Usage>,
+}
+
+// Text, tool calls and token usage returned by a provider for one call
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LlmReply {
+    pub content: String,
+    pub usage: Option<Usage>,
+    pub tool_calls: Vec<ToolCall>,
 }
@@ -144,6 +144,11 @@ async fn login(app_state: web::Data<AppState>, user: web::Json<User>) -> impl Re
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    // Tests run the server on a free port passed through PORT
+    let port: u16 = std::env::var("PORT")
+        .ok()
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(8080);
 
     let db: Database = match Database::load_from_file() {
         Ok(db) => db,
@@ -176,7 +181,7 @@ async fn main() -> std::io::Result<()> {
             .route("/register", web::post().to(register))
             .route("/login", web::post().to(login))
     })
-    .bind("127.0.0.1:8080")?
+    .bind(("127.0.0.1", port))?
     .run()
     .await
 }
@@ -76,9 +76,15 @@ async fn get_forex_prices(
 async fn main() -> std::io::Result<()> {
     let client = HttpClient::new();
     let data = web::Data::new(AppState { client });
+    // Tests run the server on a free port passed through PORT
+    let port: u16 = std::env::var("PORT")
+        .ok()
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(8080);
 
     HttpServer::new(move || {
         App::new()
+            .service(actix_files::Files::new("/ui", "./static").index_file("index.html"))
             .wrap(
                 Cors::permissive()
                     .allowed_origin_fn(|origin, _req_head| {
@@ -93,7 +99,7 @@ async fn main() -> std::io::Result<()> {
             .app_data(data.clone())
             .route("/forex-prices", web::post().to(get_forex_prices))
     })
-    .bind("127.0.0.1:8080")?
+    .bind(("127.0.0.1", port))?
     .run()
     .await
 }
\ No newline at end of file
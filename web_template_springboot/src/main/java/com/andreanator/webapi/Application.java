@@ -0,0 +1,88 @@
+package com.andreanator.webapi;
+
+import java.util.List;
+import java.util.Optional;
+
+import jakarta.persistence.Entity;
+import jakarta.persistence.GeneratedValue;
+import jakarta.persistence.GenerationType;
+import jakarta.persistence.Id;
+
+import org.springframework.boot.SpringApplication;
+import org.springframework.boot.autoconfigure.SpringBootApplication;
+import org.springframework.data.jpa.repository.JpaRepository;
+import org.springframework.http.ResponseEntity;
+import org.springframework.web.bind.annotation.DeleteMapping;
+import org.springframework.web.bind.annotation.GetMapping;
+import org.springframework.web.bind.annotation.PathVariable;
+import org.springframework.web.bind.annotation.PostMapping;
+import org.springframework.web.bind.annotation.PutMapping;
+import org.springframework.web.bind.annotation.RequestBody;
+import org.springframework.web.bind.annotation.RequestMapping;
+import org.springframework.web.bind.annotation.RestController;
+
+@SpringBootApplication
+public class Application {
+    public static void main(String[] args) {
+        SpringApplication.run(Application.class, args);
+    }
+}
+
+@Entity
+class Task {
+    @Id
+    @GeneratedValue(strategy = GenerationType.IDENTITY)
+    private Long id;
+    private String name;
+    private boolean completed;
+
+    public Long getId() { return id; }
+    public void setId(Long id) { this.id = id; }
+    public String getName() { return name; }
+    public void setName(String name) { this.name = name; }
+    public boolean isCompleted() { return completed; }
+    public void setCompleted(boolean completed) { this.completed = completed; }
+}
+
+interface TaskRepository extends JpaRepository<Task, Long> {}
+
+@RestController
+@RequestMapping("/task")
+class TaskController {
+    private final TaskRepository tasks;
+
+    TaskController(TaskRepository tasks) {
+        this.tasks = tasks;
+    }
+
+    @GetMapping
+    public List<Task> getAll() {
+        return tasks.findAll();
+    }
+
+    @GetMapping("/{id}")
+    public ResponseEntity<Task> get(@PathVariable Long id) {
+        Optional<Task> task = tasks.findById(id);
+        return task.map(ResponseEntity::ok).orElse(ResponseEntity.notFound().build());
+    }
+
+    @PostMapping
+    public Task create(@RequestBody Task task) {
+        return tasks.save(task);
+    }
+
+    @PutMapping("/{id}")
+    public ResponseEntity<Task> update(@PathVariable Long id, @RequestBody Task task) {
+        if (!tasks.existsById(id)) {
+            return ResponseEntity.notFound().build();
+        }
+        task.setId(id);
+        return ResponseEntity.ok(tasks.save(task));
+    }
+
+    @DeleteMapping("/{id}")
+    public ResponseEntity<Void> delete(@PathVariable Long id) {
+        tasks.deleteById(id);
+        return ResponseEntity.noContent().build();
+    }
+}
\ No newline at end of file